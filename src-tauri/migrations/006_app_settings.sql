@@ -0,0 +1,8 @@
+-- Simple key/value store for user preferences
+CREATE TABLE IF NOT EXISTS app_settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO app_settings (key, value) VALUES ('market_data_provider', 'yahoo');
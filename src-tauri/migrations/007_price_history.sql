@@ -0,0 +1,22 @@
+-- Daily OHLCV bars, one row per stock per trading day
+CREATE TABLE IF NOT EXISTS price_history (
+    id INTEGER PRIMARY KEY,
+    stock_id INTEGER NOT NULL REFERENCES stocks(id) ON DELETE CASCADE,
+    date TEXT NOT NULL,
+    open REAL,
+    high REAL,
+    low REAL,
+    close REAL NOT NULL,
+    adj_close REAL NOT NULL,
+    volume INTEGER,
+    UNIQUE(stock_id, date)
+);
+
+CREATE INDEX IF NOT EXISTS idx_price_history_date ON price_history(date);
+
+-- Backfill bookkeeping: how far back we've asked the provider for each stock
+CREATE TABLE IF NOT EXISTS price_history_status (
+    stock_id INTEGER PRIMARY KEY REFERENCES stocks(id) ON DELETE CASCADE,
+    history_start TEXT NOT NULL,
+    last_backfill_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
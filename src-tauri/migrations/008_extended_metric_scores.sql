@@ -0,0 +1,9 @@
+ALTER TABLE outlier_detections ADD COLUMN dividend_yield_z_score REAL;
+
+ALTER TABLE outlier_detections ADD COLUMN beta_z_score REAL;
+
+ALTER TABLE outlier_detections ADD COLUMN eps_z_score REAL;
+
+ALTER TABLE outlier_detections ADD COLUMN range_position_z_score REAL;
+
+ALTER TABLE outlier_detections ADD COLUMN week52_position REAL;
@@ -0,0 +1,12 @@
+-- Quote snapshots for each sector's ETF (XLK, XLV, ...), the benchmark its members are judged against
+CREATE TABLE IF NOT EXISTS benchmark_quotes (
+    id INTEGER PRIMARY KEY,
+    sector_id INTEGER NOT NULL REFERENCES sectors(id),
+    timestamp DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    price REAL NOT NULL,
+    price_change REAL NOT NULL,
+    price_change_percent REAL NOT NULL,
+    volume INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_benchmark_quotes_sector_timestamp ON benchmark_quotes(sector_id, timestamp);
@@ -0,0 +1,19 @@
+-- One row per distinct detection: the parameters it ran with and the data it saw.
+-- Re-running with the same inputs reuses the run instead of writing its detections again.
+CREATE TABLE IF NOT EXISTS detection_runs (
+    id INTEGER PRIMARY KEY,
+    universe_type TEXT NOT NULL,
+    sector_id INTEGER NOT NULL REFERENCES sectors(id),
+    threshold REAL NOT NULL,
+    -- JSON of the composite weights, statistics options and return horizon
+    params TEXT NOT NULL,
+    -- Newest quote timestamp among the sector members that were scored
+    data_as_of DATETIME NOT NULL,
+    detection_date TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(universe_type, sector_id, threshold, params, data_as_of)
+);
+
+ALTER TABLE outlier_detections ADD COLUMN run_id INTEGER REFERENCES detection_runs(id);
+
+CREATE INDEX IF NOT EXISTS idx_outlier_run ON outlier_detections(run_id);
@@ -0,0 +1,41 @@
+-- Registry of stock universes. Membership rows reference it instead of a fixed list of names.
+CREATE TABLE IF NOT EXISTS universes (
+    id TEXT PRIMARY KEY,
+    display_name TEXT NOT NULL,
+    -- How membership is discovered, NULL for lists maintained by hand
+    discovery_source TEXT,
+    default_threshold REAL NOT NULL DEFAULT 1.5,
+    -- 'ttl' keeps sector summaries in memory for the configured lifetime, 'never' always reads the database
+    cache_policy TEXT NOT NULL DEFAULT 'never' CHECK (cache_policy IN ('ttl', 'never')),
+    sort_order INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT OR IGNORE INTO universes (id, display_name, discovery_source, default_threshold, cache_policy, sort_order)
+VALUES
+    ('sp500', 'S&P 500', 'sp500_wikipedia', 1.5, 'ttl', 0),
+    ('russell2000', 'Russell 2000', 'ishares_iwm', 2.0, 'never', 1);
+
+-- Carry over thresholds saved as app settings
+UPDATE universes SET default_threshold = (
+    SELECT CAST(value AS REAL) FROM app_settings WHERE key = universes.id || '_threshold'
+)
+WHERE EXISTS (SELECT 1 FROM app_settings WHERE key = universes.id || '_threshold');
+
+DELETE FROM app_settings WHERE key IN ('sp500_threshold', 'russell2000_threshold');
+
+-- Rebuild stock_universe without the CHECK on universe_type
+CREATE TABLE stock_universe_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    stock_id INTEGER NOT NULL REFERENCES stocks(id) ON DELETE CASCADE,
+    universe_type TEXT NOT NULL REFERENCES universes(id) ON DELETE CASCADE,
+    date_added TEXT NOT NULL DEFAULT (date('now')),
+    date_removed TEXT,
+    UNIQUE(stock_id, universe_type)
+);
+
+INSERT INTO stock_universe_new (id, stock_id, universe_type, date_added, date_removed)
+SELECT id, stock_id, universe_type, date_added, date_removed FROM stock_universe;
+
+DROP TABLE stock_universe;
+
+ALTER TABLE stock_universe_new RENAME TO stock_universe;
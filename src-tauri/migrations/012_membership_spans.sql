@@ -0,0 +1,21 @@
+-- Allow a stock to leave a universe and rejoin it later as a new membership span.
+-- Only one span per stock and universe may be open at a time.
+CREATE TABLE stock_universe_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    stock_id INTEGER NOT NULL REFERENCES stocks(id) ON DELETE CASCADE,
+    universe_type TEXT NOT NULL REFERENCES universes(id) ON DELETE CASCADE,
+    date_added TEXT NOT NULL DEFAULT (date('now')),
+    date_removed TEXT
+);
+
+INSERT INTO stock_universe_new (id, stock_id, universe_type, date_added, date_removed)
+SELECT id, stock_id, universe_type, date_added, date_removed FROM stock_universe;
+
+DROP TABLE stock_universe;
+
+ALTER TABLE stock_universe_new RENAME TO stock_universe;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_stock_universe_open
+    ON stock_universe(stock_id, universe_type) WHERE date_removed IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_stock_universe_universe ON stock_universe(universe_type, stock_id);
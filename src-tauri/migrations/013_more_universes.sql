@@ -0,0 +1,51 @@
+-- Nasdaq-100, S&P MidCap 400 and S&P SmallCap 600, discovered from their Wikipedia lists.
+
+-- A watchlist named after one of them may already hold its id. Move it to the first free
+-- id of the form <id>_watchlist, <id>_watchlist_2, ... the way new watchlist ids are
+-- suffixed, along with its memberships and detection history, before the index takes the id.
+CREATE TEMP TABLE universe_moves (old_id TEXT PRIMARY KEY, new_id TEXT NOT NULL UNIQUE);
+
+INSERT INTO universe_moves (old_id, new_id)
+SELECT u.id, (
+    WITH RECURSIVE suffix(n) AS (
+        SELECT 1
+        UNION ALL
+        SELECT n + 1 FROM suffix
+        WHERE u.id || '_watchlist' || CASE n WHEN 1 THEN '' ELSE '_' || n END
+            IN (SELECT id FROM universes)
+    )
+    SELECT u.id || '_watchlist' || CASE MAX(n) WHEN 1 THEN '' ELSE '_' || MAX(n) END FROM suffix
+)
+FROM universes u
+WHERE u.id IN ('nasdaq100', 'sp400', 'sp600') AND u.discovery_source IS NULL;
+
+INSERT INTO universes (id, display_name, discovery_source, default_threshold, cache_policy, sort_order)
+SELECT m.new_id, u.display_name, NULL, u.default_threshold, u.cache_policy, u.sort_order
+FROM universe_moves m
+JOIN universes u ON u.id = m.old_id;
+
+UPDATE stock_universe
+SET universe_type = (SELECT new_id FROM universe_moves WHERE old_id = universe_type)
+WHERE universe_type IN (SELECT old_id FROM universe_moves);
+
+UPDATE outlier_detections
+SET universe_type = (SELECT new_id FROM universe_moves WHERE old_id = universe_type)
+WHERE universe_type IN (SELECT old_id FROM universe_moves);
+
+UPDATE detection_runs
+SET universe_type = (SELECT new_id FROM universe_moves WHERE old_id = universe_type)
+WHERE universe_type IN (SELECT old_id FROM universe_moves);
+
+DELETE FROM universes WHERE id IN (SELECT old_id FROM universe_moves);
+
+DROP TABLE universe_moves;
+
+-- Keep the indexes ahead of any watchlists in the universe list.
+UPDATE universes SET sort_order = sort_order + 3 WHERE discovery_source IS NULL;
+
+-- No OR IGNORE: if the ids are still taken the migration fails rather than skipping an index.
+INSERT INTO universes (id, display_name, discovery_source, default_threshold, cache_policy, sort_order)
+VALUES
+    ('nasdaq100', 'Nasdaq-100', 'nasdaq100_wikipedia', 1.5, 'ttl', 2),
+    ('sp400', 'S&P MidCap 400', 'sp400_wikipedia', 2.0, 'never', 3),
+    ('sp600', 'S&P SmallCap 600', 'sp600_wikipedia', 2.0, 'never', 4);
@@ -0,0 +1,318 @@
+-- The GICS levels below sector: industry group, industry and sub-industry (2023 structure).
+CREATE TABLE IF NOT EXISTS gics_industry_groups (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    sector_id INTEGER NOT NULL REFERENCES sectors(id)
+);
+
+CREATE TABLE IF NOT EXISTS gics_industries (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    industry_group_id INTEGER NOT NULL REFERENCES gics_industry_groups(id)
+);
+
+CREATE TABLE IF NOT EXISTS gics_sub_industries (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    industry_id INTEGER NOT NULL REFERENCES gics_industries(id)
+);
+
+-- The levels above the sub-industry follow from it
+ALTER TABLE stocks ADD COLUMN sub_industry_id INTEGER REFERENCES gics_sub_industries(id);
+
+-- A stock's group at every level, NULL below the sector when its sub-industry is unknown
+CREATE VIEW IF NOT EXISTS stock_gics AS
+SELECT s.id AS stock_id,
+       s.sector_id,
+       gi.industry_group_id,
+       gsi.industry_id,
+       s.sub_industry_id
+FROM stocks s
+LEFT JOIN gics_sub_industries gsi ON gsi.id = s.sub_industry_id
+LEFT JOIN gics_industries gi ON gi.id = gsi.industry_id;
+
+-- Detections record the level they were scored at and the peer group at that level.
+-- Everything before now was scored against the sector.
+ALTER TABLE outlier_detections ADD COLUMN gics_level TEXT NOT NULL DEFAULT 'sector';
+ALTER TABLE outlier_detections ADD COLUMN group_id INTEGER;
+UPDATE outlier_detections SET group_id = sector_id;
+
+CREATE INDEX IF NOT EXISTS idx_outlier_group ON outlier_detections(universe_type, gics_level, group_id);
+
+-- Information Technology is stored under the name 'Technology'
+WITH g(name, sector) AS (VALUES
+    ('Energy', 'Energy'),
+    ('Materials', 'Materials'),
+    ('Capital Goods', 'Industrials'),
+    ('Commercial & Professional Services', 'Industrials'),
+    ('Transportation', 'Industrials'),
+    ('Automobiles & Components', 'Consumer Discretionary'),
+    ('Consumer Durables & Apparel', 'Consumer Discretionary'),
+    ('Consumer Services', 'Consumer Discretionary'),
+    ('Consumer Discretionary Distribution & Retail', 'Consumer Discretionary'),
+    ('Consumer Staples Distribution & Retail', 'Consumer Staples'),
+    ('Food, Beverage & Tobacco', 'Consumer Staples'),
+    ('Household & Personal Products', 'Consumer Staples'),
+    ('Health Care Equipment & Services', 'Health Care'),
+    ('Pharmaceuticals, Biotechnology & Life Sciences', 'Health Care'),
+    ('Banks', 'Financials'),
+    ('Financial Services', 'Financials'),
+    ('Insurance', 'Financials'),
+    ('Software & Services', 'Technology'),
+    ('Technology Hardware & Equipment', 'Technology'),
+    ('Semiconductors & Semiconductor Equipment', 'Technology'),
+    ('Telecommunication Services', 'Communication Services'),
+    ('Media & Entertainment', 'Communication Services'),
+    ('Utilities', 'Utilities'),
+    ('Equity Real Estate Investment Trusts (REITs)', 'Real Estate'),
+    ('Real Estate Management & Development', 'Real Estate')
+)
+INSERT OR IGNORE INTO gics_industry_groups (name, sector_id)
+SELECT g.name, sec.id FROM g JOIN sectors sec ON sec.name = g.sector;
+
+WITH i(name, industry_group) AS (VALUES
+    ('Energy Equipment & Services', 'Energy'),
+    ('Oil, Gas & Consumable Fuels', 'Energy'),
+    ('Chemicals', 'Materials'),
+    ('Construction Materials', 'Materials'),
+    ('Containers & Packaging', 'Materials'),
+    ('Metals & Mining', 'Materials'),
+    ('Paper & Forest Products', 'Materials'),
+    ('Aerospace & Defense', 'Capital Goods'),
+    ('Building Products', 'Capital Goods'),
+    ('Construction & Engineering', 'Capital Goods'),
+    ('Electrical Equipment', 'Capital Goods'),
+    ('Industrial Conglomerates', 'Capital Goods'),
+    ('Machinery', 'Capital Goods'),
+    ('Trading Companies & Distributors', 'Capital Goods'),
+    ('Commercial Services & Supplies', 'Commercial & Professional Services'),
+    ('Professional Services', 'Commercial & Professional Services'),
+    ('Air Freight & Logistics', 'Transportation'),
+    ('Passenger Airlines', 'Transportation'),
+    ('Marine Transportation', 'Transportation'),
+    ('Ground Transportation', 'Transportation'),
+    ('Transportation Infrastructure', 'Transportation'),
+    ('Automobile Components', 'Automobiles & Components'),
+    ('Automobiles', 'Automobiles & Components'),
+    ('Household Durables', 'Consumer Durables & Apparel'),
+    ('Leisure Products', 'Consumer Durables & Apparel'),
+    ('Textiles, Apparel & Luxury Goods', 'Consumer Durables & Apparel'),
+    ('Hotels, Restaurants & Leisure', 'Consumer Services'),
+    ('Diversified Consumer Services', 'Consumer Services'),
+    ('Distributors', 'Consumer Discretionary Distribution & Retail'),
+    ('Broadline Retail', 'Consumer Discretionary Distribution & Retail'),
+    ('Specialty Retail', 'Consumer Discretionary Distribution & Retail'),
+    ('Consumer Staples Distribution & Retail', 'Consumer Staples Distribution & Retail'),
+    ('Beverages', 'Food, Beverage & Tobacco'),
+    ('Food Products', 'Food, Beverage & Tobacco'),
+    ('Tobacco', 'Food, Beverage & Tobacco'),
+    ('Household Products', 'Household & Personal Products'),
+    ('Personal Care Products', 'Household & Personal Products'),
+    ('Health Care Equipment & Supplies', 'Health Care Equipment & Services'),
+    ('Health Care Providers & Services', 'Health Care Equipment & Services'),
+    ('Health Care Technology', 'Health Care Equipment & Services'),
+    ('Biotechnology', 'Pharmaceuticals, Biotechnology & Life Sciences'),
+    ('Pharmaceuticals', 'Pharmaceuticals, Biotechnology & Life Sciences'),
+    ('Life Sciences Tools & Services', 'Pharmaceuticals, Biotechnology & Life Sciences'),
+    ('Banks', 'Banks'),
+    ('Financial Services', 'Financial Services'),
+    ('Consumer Finance', 'Financial Services'),
+    ('Capital Markets', 'Financial Services'),
+    ('Mortgage Real Estate Investment Trusts (REITs)', 'Financial Services'),
+    ('Insurance', 'Insurance'),
+    ('IT Services', 'Software & Services'),
+    ('Software', 'Software & Services'),
+    ('Communications Equipment', 'Technology Hardware & Equipment'),
+    ('Technology Hardware, Storage & Peripherals', 'Technology Hardware & Equipment'),
+    ('Electronic Equipment, Instruments & Components', 'Technology Hardware & Equipment'),
+    ('Semiconductors & Semiconductor Equipment', 'Semiconductors & Semiconductor Equipment'),
+    ('Diversified Telecommunication Services', 'Telecommunication Services'),
+    ('Wireless Telecommunication Services', 'Telecommunication Services'),
+    ('Media', 'Media & Entertainment'),
+    ('Entertainment', 'Media & Entertainment'),
+    ('Interactive Media & Services', 'Media & Entertainment'),
+    ('Electric Utilities', 'Utilities'),
+    ('Gas Utilities', 'Utilities'),
+    ('Multi-Utilities', 'Utilities'),
+    ('Water Utilities', 'Utilities'),
+    ('Independent Power and Renewable Electricity Producers', 'Utilities'),
+    ('Diversified REITs', 'Equity Real Estate Investment Trusts (REITs)'),
+    ('Industrial REITs', 'Equity Real Estate Investment Trusts (REITs)'),
+    ('Hotel & Resort REITs', 'Equity Real Estate Investment Trusts (REITs)'),
+    ('Office REITs', 'Equity Real Estate Investment Trusts (REITs)'),
+    ('Health Care REITs', 'Equity Real Estate Investment Trusts (REITs)'),
+    ('Residential REITs', 'Equity Real Estate Investment Trusts (REITs)'),
+    ('Retail REITs', 'Equity Real Estate Investment Trusts (REITs)'),
+    ('Specialized REITs', 'Equity Real Estate Investment Trusts (REITs)'),
+    ('Real Estate Management & Development', 'Real Estate Management & Development')
+)
+INSERT OR IGNORE INTO gics_industries (name, industry_group_id)
+SELECT i.name, ig.id FROM i JOIN gics_industry_groups ig ON ig.name = i.industry_group;
+
+WITH si(name, industry) AS (VALUES
+    ('Oil & Gas Drilling', 'Energy Equipment & Services'),
+    ('Oil & Gas Equipment & Services', 'Energy Equipment & Services'),
+    ('Integrated Oil & Gas', 'Oil, Gas & Consumable Fuels'),
+    ('Oil & Gas Exploration & Production', 'Oil, Gas & Consumable Fuels'),
+    ('Oil & Gas Refining & Marketing', 'Oil, Gas & Consumable Fuels'),
+    ('Oil & Gas Storage & Transportation', 'Oil, Gas & Consumable Fuels'),
+    ('Coal & Consumable Fuels', 'Oil, Gas & Consumable Fuels'),
+    ('Commodity Chemicals', 'Chemicals'),
+    ('Diversified Chemicals', 'Chemicals'),
+    ('Fertilizers & Agricultural Chemicals', 'Chemicals'),
+    ('Industrial Gases', 'Chemicals'),
+    ('Specialty Chemicals', 'Chemicals'),
+    ('Construction Materials', 'Construction Materials'),
+    ('Metal, Glass & Plastic Containers', 'Containers & Packaging'),
+    ('Paper & Plastic Packaging Products & Materials', 'Containers & Packaging'),
+    ('Aluminum', 'Metals & Mining'),
+    ('Diversified Metals & Mining', 'Metals & Mining'),
+    ('Copper', 'Metals & Mining'),
+    ('Gold', 'Metals & Mining'),
+    ('Precious Metals & Minerals', 'Metals & Mining'),
+    ('Silver', 'Metals & Mining'),
+    ('Steel', 'Metals & Mining'),
+    ('Forest Products', 'Paper & Forest Products'),
+    ('Paper Products', 'Paper & Forest Products'),
+    ('Aerospace & Defense', 'Aerospace & Defense'),
+    ('Building Products', 'Building Products'),
+    ('Construction & Engineering', 'Construction & Engineering'),
+    ('Electrical Components & Equipment', 'Electrical Equipment'),
+    ('Heavy Electrical Equipment', 'Electrical Equipment'),
+    ('Industrial Conglomerates', 'Industrial Conglomerates'),
+    ('Construction Machinery & Heavy Transportation Equipment', 'Machinery'),
+    ('Agricultural & Farm Machinery', 'Machinery'),
+    ('Industrial Machinery & Supplies & Components', 'Machinery'),
+    ('Trading Companies & Distributors', 'Trading Companies & Distributors'),
+    ('Commercial Printing', 'Commercial Services & Supplies'),
+    ('Environmental & Facilities Services', 'Commercial Services & Supplies'),
+    ('Office Services & Supplies', 'Commercial Services & Supplies'),
+    ('Diversified Support Services', 'Commercial Services & Supplies'),
+    ('Security & Alarm Services', 'Commercial Services & Supplies'),
+    ('Human Resource & Employment Services', 'Professional Services'),
+    ('Research & Consulting Services', 'Professional Services'),
+    ('Data Processing & Outsourced Services', 'Professional Services'),
+    ('Air Freight & Logistics', 'Air Freight & Logistics'),
+    ('Passenger Airlines', 'Passenger Airlines'),
+    ('Marine Transportation', 'Marine Transportation'),
+    ('Rail Transportation', 'Ground Transportation'),
+    ('Cargo Ground Transportation', 'Ground Transportation'),
+    ('Passenger Ground Transportation', 'Ground Transportation'),
+    ('Airport Services', 'Transportation Infrastructure'),
+    ('Highways & Railtracks', 'Transportation Infrastructure'),
+    ('Marine Ports & Services', 'Transportation Infrastructure'),
+    ('Automotive Parts & Equipment', 'Automobile Components'),
+    ('Tires & Rubber', 'Automobile Components'),
+    ('Automobile Manufacturers', 'Automobiles'),
+    ('Motorcycle Manufacturers', 'Automobiles'),
+    ('Consumer Electronics', 'Household Durables'),
+    ('Home Furnishings', 'Household Durables'),
+    ('Homebuilding', 'Household Durables'),
+    ('Household Appliances', 'Household Durables'),
+    ('Housewares & Specialties', 'Household Durables'),
+    ('Leisure Products', 'Leisure Products'),
+    ('Apparel, Accessories & Luxury Goods', 'Textiles, Apparel & Luxury Goods'),
+    ('Footwear', 'Textiles, Apparel & Luxury Goods'),
+    ('Textiles', 'Textiles, Apparel & Luxury Goods'),
+    ('Casinos & Gaming', 'Hotels, Restaurants & Leisure'),
+    ('Hotels, Resorts & Cruise Lines', 'Hotels, Restaurants & Leisure'),
+    ('Leisure Facilities', 'Hotels, Restaurants & Leisure'),
+    ('Restaurants', 'Hotels, Restaurants & Leisure'),
+    ('Education Services', 'Diversified Consumer Services'),
+    ('Specialized Consumer Services', 'Diversified Consumer Services'),
+    ('Distributors', 'Distributors'),
+    ('Broadline Retail', 'Broadline Retail'),
+    ('Apparel Retail', 'Specialty Retail'),
+    ('Computer & Electronics Retail', 'Specialty Retail'),
+    ('Home Improvement Retail', 'Specialty Retail'),
+    ('Other Specialty Retail', 'Specialty Retail'),
+    ('Automotive Retail', 'Specialty Retail'),
+    ('Homefurnishing Retail', 'Specialty Retail'),
+    ('Drug Retail', 'Consumer Staples Distribution & Retail'),
+    ('Food Distributors', 'Consumer Staples Distribution & Retail'),
+    ('Food Retail', 'Consumer Staples Distribution & Retail'),
+    ('Consumer Staples Merchandise Retail', 'Consumer Staples Distribution & Retail'),
+    ('Brewers', 'Beverages'),
+    ('Distillers & Vintners', 'Beverages'),
+    ('Soft Drinks & Non-alcoholic Beverages', 'Beverages'),
+    ('Agricultural Products & Services', 'Food Products'),
+    ('Packaged Foods & Meats', 'Food Products'),
+    ('Tobacco', 'Tobacco'),
+    ('Household Products', 'Household Products'),
+    ('Personal Care Products', 'Personal Care Products'),
+    ('Health Care Equipment', 'Health Care Equipment & Supplies'),
+    ('Health Care Supplies', 'Health Care Equipment & Supplies'),
+    ('Health Care Distributors', 'Health Care Providers & Services'),
+    ('Health Care Services', 'Health Care Providers & Services'),
+    ('Health Care Facilities', 'Health Care Providers & Services'),
+    ('Managed Health Care', 'Health Care Providers & Services'),
+    ('Health Care Technology', 'Health Care Technology'),
+    ('Biotechnology', 'Biotechnology'),
+    ('Pharmaceuticals', 'Pharmaceuticals'),
+    ('Life Sciences Tools & Services', 'Life Sciences Tools & Services'),
+    ('Diversified Banks', 'Banks'),
+    ('Regional Banks', 'Banks'),
+    ('Diversified Financial Services', 'Financial Services'),
+    ('Multi-Sector Holdings', 'Financial Services'),
+    ('Specialized Finance', 'Financial Services'),
+    ('Commercial & Residential Mortgage Finance', 'Financial Services'),
+    ('Transaction & Payment Processing Services', 'Financial Services'),
+    ('Consumer Finance', 'Consumer Finance'),
+    ('Asset Management & Custody Banks', 'Capital Markets'),
+    ('Investment Banking & Brokerage', 'Capital Markets'),
+    ('Diversified Capital Markets', 'Capital Markets'),
+    ('Financial Exchanges & Data', 'Capital Markets'),
+    ('Mortgage REITs', 'Mortgage Real Estate Investment Trusts (REITs)'),
+    ('Insurance Brokers', 'Insurance'),
+    ('Life & Health Insurance', 'Insurance'),
+    ('Multi-line Insurance', 'Insurance'),
+    ('Property & Casualty Insurance', 'Insurance'),
+    ('Reinsurance', 'Insurance'),
+    ('IT Consulting & Other Services', 'IT Services'),
+    ('Internet Services & Infrastructure', 'IT Services'),
+    ('Application Software', 'Software'),
+    ('Systems Software', 'Software'),
+    ('Communications Equipment', 'Communications Equipment'),
+    ('Technology Hardware, Storage & Peripherals', 'Technology Hardware, Storage & Peripherals'),
+    ('Electronic Equipment & Instruments', 'Electronic Equipment, Instruments & Components'),
+    ('Electronic Components', 'Electronic Equipment, Instruments & Components'),
+    ('Electronic Manufacturing Services', 'Electronic Equipment, Instruments & Components'),
+    ('Technology Distributors', 'Electronic Equipment, Instruments & Components'),
+    ('Semiconductor Materials & Equipment', 'Semiconductors & Semiconductor Equipment'),
+    ('Semiconductors', 'Semiconductors & Semiconductor Equipment'),
+    ('Alternative Carriers', 'Diversified Telecommunication Services'),
+    ('Integrated Telecommunication Services', 'Diversified Telecommunication Services'),
+    ('Wireless Telecommunication Services', 'Wireless Telecommunication Services'),
+    ('Advertising', 'Media'),
+    ('Broadcasting', 'Media'),
+    ('Cable & Satellite', 'Media'),
+    ('Publishing', 'Media'),
+    ('Movies & Entertainment', 'Entertainment'),
+    ('Interactive Home Entertainment', 'Entertainment'),
+    ('Interactive Media & Services', 'Interactive Media & Services'),
+    ('Electric Utilities', 'Electric Utilities'),
+    ('Gas Utilities', 'Gas Utilities'),
+    ('Multi-Utilities', 'Multi-Utilities'),
+    ('Water Utilities', 'Water Utilities'),
+    ('Independent Power Producers & Energy Traders', 'Independent Power and Renewable Electricity Producers'),
+    ('Renewable Electricity', 'Independent Power and Renewable Electricity Producers'),
+    ('Diversified REITs', 'Diversified REITs'),
+    ('Industrial REITs', 'Industrial REITs'),
+    ('Hotel & Resort REITs', 'Hotel & Resort REITs'),
+    ('Office REITs', 'Office REITs'),
+    ('Health Care REITs', 'Health Care REITs'),
+    ('Multi-Family Residential REITs', 'Residential REITs'),
+    ('Single-Family Residential REITs', 'Residential REITs'),
+    ('Retail REITs', 'Retail REITs'),
+    ('Other Specialized REITs', 'Specialized REITs'),
+    ('Self-Storage REITs', 'Specialized REITs'),
+    ('Telecom Tower REITs', 'Specialized REITs'),
+    ('Timber REITs', 'Specialized REITs'),
+    ('Data Center REITs', 'Specialized REITs'),
+    ('Diversified Real Estate Activities', 'Real Estate Management & Development'),
+    ('Real Estate Operating Companies', 'Real Estate Management & Development'),
+    ('Real Estate Development', 'Real Estate Management & Development'),
+    ('Real Estate Services', 'Real Estate Management & Development')
+)
+INSERT OR IGNORE INTO gics_sub_industries (name, industry_id)
+SELECT si.name, ind.id FROM si JOIN gics_industries ind ON ind.name = si.industry;
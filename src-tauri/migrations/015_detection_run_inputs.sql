@@ -0,0 +1,33 @@
+-- Key runs on a digest of everything scored instead of the newest quote time alone, which
+-- stays put when membership changes, a backfill moves a return or a benchmark quote arrives.
+-- Detections keep pointing at their runs while the table is rebuilt, so the foreign key
+-- check waits for the commit.
+PRAGMA defer_foreign_keys = ON;
+
+CREATE TEMP TABLE detection_runs_old AS SELECT * FROM detection_runs;
+
+DROP TABLE detection_runs;
+
+CREATE TABLE detection_runs (
+    id INTEGER PRIMARY KEY,
+    universe_type TEXT NOT NULL,
+    sector_id INTEGER NOT NULL REFERENCES sectors(id),
+    threshold REAL NOT NULL,
+    -- JSON of the composite weights, statistics options and return horizon
+    params TEXT NOT NULL,
+    -- Member count and digest of each scored member's quote time and inputs
+    inputs TEXT NOT NULL,
+    -- Newest quote timestamp among the members that were scored
+    data_as_of DATETIME NOT NULL,
+    detection_date TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(universe_type, sector_id, threshold, params, inputs)
+);
+
+-- Earlier runs only know their quote time
+INSERT INTO detection_runs
+    (id, universe_type, sector_id, threshold, params, inputs, data_as_of, detection_date, created_at)
+SELECT id, universe_type, sector_id, threshold, params, data_as_of, data_as_of, detection_date, created_at
+FROM detection_runs_old;
+
+DROP TABLE detection_runs_old;
@@ -0,0 +1,8 @@
+-- Interior date ranges the provider returned no bars for (trading halts, market
+-- closures, delisted-then-relisted tickers), so backfill doesn't ask again every run
+CREATE TABLE IF NOT EXISTS price_history_gaps (
+    stock_id INTEGER NOT NULL REFERENCES stocks(id) ON DELETE CASCADE,
+    start_date TEXT NOT NULL,
+    end_date TEXT NOT NULL,
+    PRIMARY KEY (stock_id, start_date)
+);
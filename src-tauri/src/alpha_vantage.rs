@@ -0,0 +1,260 @@
+use crate::market_data::{Fundamentals, PriceQuote};
+use crate::provider::MarketDataProvider;
+use crate::throttle::{RetryPolicy, Throttle};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+
+/// Response structure for Alpha Vantage GLOBAL_QUOTE.
+/// Alpha Vantage returns every number as a string, keyed with numbered labels.
+#[derive(Debug, Deserialize)]
+struct GlobalQuoteResponse {
+    #[serde(rename = "Global Quote")]
+    global_quote: Option<GlobalQuote>,
+    #[serde(rename = "Note")]
+    note: Option<String>,
+    #[serde(rename = "Information")]
+    information: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GlobalQuote {
+    #[serde(rename = "05. price")]
+    price: Option<String>,
+    #[serde(rename = "06. volume")]
+    volume: Option<String>,
+    #[serde(rename = "08. previous close")]
+    previous_close: Option<String>,
+}
+
+/// Response structure for Alpha Vantage OVERVIEW (company fundamentals).
+#[derive(Debug, Deserialize)]
+struct OverviewResponse {
+    #[serde(rename = "MarketCapitalization")]
+    market_cap: Option<String>,
+    #[serde(rename = "PERatio")]
+    pe_ratio: Option<String>,
+    #[serde(rename = "PriceToBookRatio")]
+    pb_ratio: Option<String>,
+    #[serde(rename = "EPS")]
+    eps: Option<String>,
+    #[serde(rename = "DividendYield")]
+    dividend_yield: Option<String>,
+    #[serde(rename = "Beta")]
+    beta: Option<String>,
+    #[serde(rename = "52WeekHigh")]
+    week52_high: Option<String>,
+    #[serde(rename = "52WeekLow")]
+    week52_low: Option<String>,
+}
+
+/// Free-tier Alpha Vantage allows 5 requests per minute.
+pub const AV_REQUESTS_PER_MIN: f64 = 5.0;
+
+/// Build the Alpha Vantage query URL for a given function, symbol and API key.
+fn build_query_url(function: &str, symbol: &str, api_key: &str) -> String {
+    format!(
+        "https://www.alphavantage.co/query?function={}&symbol={}&apikey={}",
+        function, symbol, api_key
+    )
+}
+
+/// Parse an Alpha Vantage numeric string. "None", "-" and empty strings mean missing.
+fn parse_number(value: Option<&str>) -> Option<f64> {
+    let trimmed = value?.trim();
+    match trimmed {
+        "" | "-" | "None" => None,
+        _ => trimmed.parse().ok(),
+    }
+}
+
+/// Alpha Vantage provider, used as a fallback when Yahoo Finance is unavailable.
+///
+/// The free tier is heavily rate limited, so this is meant to keep a refresh
+/// cycle alive rather than to be the primary source.
+pub struct AlphaVantageProvider {
+    client: Client,
+    api_key: String,
+    throttle: Throttle,
+}
+
+impl AlphaVantageProvider {
+    /// `requests_per_minute` should match the API key's plan (5 on the free tier).
+    pub fn new(api_key: &str, requests_per_minute: f64) -> Result<Self, String> {
+        let client = Client::builder()
+            .user_agent("SectorView/1.0")
+            .build()
+            .map_err(|e| format!("Failed to build Alpha Vantage client: {e}"))?;
+        Ok(Self {
+            client,
+            api_key: api_key.trim().to_string(),
+            throttle: Throttle::new(requests_per_minute / 60.0, 1, RetryPolicy::default()),
+        })
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for AlphaVantageProvider {
+    fn name(&self) -> &'static str {
+        "alpha_vantage"
+    }
+
+    async fn fetch_quote(&self, symbol: &str) -> Result<PriceQuote, String> {
+        let url = build_query_url("GLOBAL_QUOTE", symbol, &self.api_key);
+        let data: GlobalQuoteResponse = self
+            .throttle
+            .send(self.client.get(&url))
+            .await
+            .map_err(|e| format!("Network error fetching {symbol}: {e}"))?
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse Alpha Vantage quote for {symbol}: {e}"))?;
+
+        if let Some(msg) = data.note.or(data.information) {
+            return Err(format!("Alpha Vantage rejected {symbol}: {msg}"));
+        }
+
+        let quote = data
+            .global_quote
+            .ok_or_else(|| format!("No quote data for {symbol}"))?;
+        let price = parse_number(quote.price.as_deref())
+            .ok_or_else(|| format!("No price for {symbol}"))?;
+        let prev_close = parse_number(quote.previous_close.as_deref()).unwrap_or(price);
+        let volume = parse_number(quote.volume.as_deref()).map(|v| v as i64);
+
+        Ok(PriceQuote {
+            price,
+            prev_close,
+            volume,
+        })
+    }
+
+    async fn fetch_fundamentals(&self, symbol: &str) -> Fundamentals {
+        let url = build_query_url("OVERVIEW", symbol, &self.api_key);
+        let resp = match self.throttle.send(self.client.get(&url)).await {
+            Ok(r) if r.status().is_success() => r,
+            _ => return Fundamentals::default(),
+        };
+        let data: OverviewResponse = match resp.json().await {
+            Ok(d) => d,
+            Err(_) => return Fundamentals::default(),
+        };
+
+        // Alpha Vantage reports SIC-style sectors ("LIFE SCIENCES", "TRADE & SERVICES")
+        // that don't map cleanly onto GICS, so sector stays unset.
+        Fundamentals {
+            pe_ratio: parse_number(data.pe_ratio.as_deref()),
+            pb_ratio: parse_number(data.pb_ratio.as_deref()),
+            market_cap: parse_number(data.market_cap.as_deref()).map(|v| v as i64),
+            eps: parse_number(data.eps.as_deref()),
+            dividend_yield: parse_number(data.dividend_yield.as_deref()),
+            beta: parse_number(data.beta.as_deref()),
+            avg_volume_10d: None,
+            week52_high: parse_number(data.week52_high.as_deref()),
+            week52_low: parse_number(data.week52_low.as_deref()),
+            sector: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f64 = 1e-9;
+
+    fn approx_eq(a: f64, b: f64) -> bool {
+        (a - b).abs() < EPSILON
+    }
+
+    // ---- URL construction ----
+
+    #[test]
+    fn test_build_query_url_contains_function_symbol_and_key() {
+        let url = build_query_url("GLOBAL_QUOTE", "AAPL", "my-key");
+        assert!(url.starts_with("https://www.alphavantage.co/query?"));
+        assert!(url.contains("function=GLOBAL_QUOTE"), "Missing function: {url}");
+        assert!(url.contains("symbol=AAPL"), "Missing symbol: {url}");
+        assert!(url.contains("apikey=my-key"), "Missing key: {url}");
+    }
+
+    // ---- parse_number ----
+
+    #[test]
+    fn test_parse_number_valid() {
+        assert!(approx_eq(parse_number(Some("28.5")).unwrap(), 28.5));
+        assert!(approx_eq(parse_number(Some(" 0.0061 ")).unwrap(), 0.0061));
+    }
+
+    #[test]
+    fn test_parse_number_missing_markers() {
+        assert!(parse_number(Some("None")).is_none());
+        assert!(parse_number(Some("-")).is_none());
+        assert!(parse_number(Some("")).is_none());
+        assert!(parse_number(None).is_none());
+    }
+
+    #[test]
+    fn test_parse_number_garbage_gives_none() {
+        assert!(parse_number(Some("n/a")).is_none());
+    }
+
+    // ---- JSON parsing ----
+
+    #[test]
+    fn test_global_quote_json_full_response() {
+        let json = r#"{
+            "Global Quote": {
+                "01. symbol": "IBM",
+                "05. price": "150.2500",
+                "06. volume": "3512345",
+                "08. previous close": "147.5000",
+                "10. change percent": "1.8644%"
+            }
+        }"#;
+        let parsed: GlobalQuoteResponse = serde_json::from_str(json).unwrap();
+        let quote = parsed.global_quote.unwrap();
+        assert!(approx_eq(parse_number(quote.price.as_deref()).unwrap(), 150.25));
+        assert!(approx_eq(parse_number(quote.previous_close.as_deref()).unwrap(), 147.5));
+        assert!(approx_eq(parse_number(quote.volume.as_deref()).unwrap(), 3_512_345.0));
+        assert!(parsed.note.is_none());
+    }
+
+    #[test]
+    fn test_global_quote_json_rate_limit_note() {
+        let json = r#"{"Note": "Thank you for using Alpha Vantage! Our standard API call frequency is 5 calls per minute."}"#;
+        let parsed: GlobalQuoteResponse = serde_json::from_str(json).unwrap();
+        assert!(parsed.global_quote.is_none());
+        assert!(parsed.note.unwrap().contains("call frequency"));
+    }
+
+    #[test]
+    fn test_overview_json_full_response() {
+        let json = r#"{
+            "Symbol": "IBM",
+            "Sector": "TECHNOLOGY",
+            "MarketCapitalization": "138000000000",
+            "PERatio": "22.1",
+            "PriceToBookRatio": "6.5",
+            "EPS": "6.8",
+            "DividendYield": "0.0445",
+            "Beta": "0.71",
+            "52WeekHigh": "199.18",
+            "52WeekLow": "135.87"
+        }"#;
+        let parsed: OverviewResponse = serde_json::from_str(json).unwrap();
+        assert!(approx_eq(parse_number(parsed.pe_ratio.as_deref()).unwrap(), 22.1));
+        assert!(approx_eq(parse_number(parsed.pb_ratio.as_deref()).unwrap(), 6.5));
+        assert!(approx_eq(parse_number(parsed.market_cap.as_deref()).unwrap(), 138_000_000_000.0));
+        assert!(approx_eq(parse_number(parsed.week52_low.as_deref()).unwrap(), 135.87));
+    }
+
+    #[test]
+    fn test_overview_json_none_values() {
+        let json = r#"{"PERatio": "None", "PriceToBookRatio": "-", "Beta": "None"}"#;
+        let parsed: OverviewResponse = serde_json::from_str(json).unwrap();
+        assert!(parse_number(parsed.pe_ratio.as_deref()).is_none());
+        assert!(parse_number(parsed.pb_ratio.as_deref()).is_none());
+        assert!(parsed.eps.is_none());
+    }
+}
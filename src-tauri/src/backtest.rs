@@ -0,0 +1,494 @@
+use crate::detection_runs;
+use crate::outlier_detection::DetectionParams;
+use crate::outlier_history::is_next_session;
+use crate::returns::MAX_BASE_STALENESS_DAYS;
+use crate::stats::mean_std;
+use crate::types::{BacktestGroup, BacktestReport, ForwardStats, GicsLevel};
+use chrono::{Duration, NaiveDate};
+use sqlx::sqlite::SqlitePool;
+use std::collections::{BTreeMap, HashMap};
+
+/// Trading sessions after the signal at which returns are measured.
+const FORWARD_SESSIONS: [usize; 4] = [1, 5, 20, 60];
+
+/// Calendar days of history loaded past the window: room for the longest
+/// forward horizon plus weekends and holidays.
+const FORWARD_DAYS: i64 = 100;
+
+/// Calendar days of detections loaded before the window, enough to reach the
+/// previous session across a long weekend.
+const EPISODE_LOOKBACK_DAYS: i64 = 7;
+
+/// Significance levels in report order, after the all-levels row.
+const LEVELS: [&str; 3] = ["Moderate", "Strong", "Extreme"];
+
+/// One flagged stock on one day. When several runs flagged it that day, the
+/// strongest detection stands.
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct Signal {
+    stock_id: i32,
+    sector_id: i32,
+    detection_date: String,
+    outlier_type: String,
+    significance_level: String,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct Close {
+    stock_id: i32,
+    sector_id: i32,
+    date: String,
+    adj_close: f64,
+}
+
+/// One span of a stock's membership in the universe.
+#[derive(Debug, sqlx::FromRow)]
+struct Span {
+    stock_id: i32,
+    date_added: String,
+    date_removed: Option<String>,
+}
+
+/// Ascending daily closes per stock, the stocks in each sector, and when each
+/// stock was a member of the universe.
+#[derive(Debug, Default)]
+struct PriceBook {
+    closes: HashMap<i32, Vec<(NaiveDate, f64)>>,
+    sectors: HashMap<i32, Vec<i32>>,
+    spans: HashMap<i32, Vec<(String, Option<String>)>>,
+}
+
+impl PriceBook {
+    fn from_closes(rows: Vec<Close>) -> Self {
+        let mut book = PriceBook::default();
+        for row in rows {
+            let date = match NaiveDate::parse_from_str(&row.date, "%Y-%m-%d") {
+                Ok(d) => d,
+                Err(_) => continue,
+            };
+            let series = book.closes.entry(row.stock_id).or_default();
+            if series.is_empty() {
+                book.sectors
+                    .entry(row.sector_id)
+                    .or_default()
+                    .push(row.stock_id);
+            }
+            series.push((date, row.adj_close));
+        }
+        book
+    }
+
+    fn with_spans(mut self, rows: Vec<Span>) -> Self {
+        for row in rows {
+            self.spans
+                .entry(row.stock_id)
+                .or_default()
+                .push((row.date_added, row.date_removed));
+        }
+        self
+    }
+
+    /// Whether the stock was in the universe on `date`, counted the way
+    /// detection picks a day's members.
+    fn member_on(&self, stock_id: i32, date: NaiveDate) -> bool {
+        let day = date.to_string();
+        self.spans.get(&stock_id).is_some_and(|spans| {
+            spans.iter().any(|(added, removed)| {
+                *added <= day && removed.as_deref().is_none_or(|r| r > day.as_str())
+            })
+        })
+    }
+
+    /// Last close on or before `date`, unless it's too stale to stand in for it.
+    fn close_on(&self, stock_id: i32, date: NaiveDate) -> Option<f64> {
+        let series = self.closes.get(&stock_id)?;
+        let i = series.partition_point(|(d, _)| *d <= date).checked_sub(1)?;
+        let (found, close) = series[i];
+        (date - found <= Duration::days(MAX_BASE_STALENESS_DAYS)).then_some(close)
+    }
+
+    /// Percent return of the equal-weight basket of a sector's other members
+    /// between two dates. Only stocks in the universe on `from` count, so the
+    /// basket is the peer group the signal was scored against.
+    fn sector_return(
+        &self,
+        sector_id: i32,
+        exclude: i32,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Option<f64> {
+        let returns: Vec<f64> = self
+            .sectors
+            .get(&sector_id)?
+            .iter()
+            .filter(|&&id| id != exclude && self.member_on(id, from))
+            .filter_map(|&id| percent_change(self.close_on(id, from)?, self.close_on(id, to)?))
+            .collect();
+        (!returns.is_empty()).then(|| returns.iter().sum::<f64>() / returns.len() as f64)
+    }
+}
+
+fn percent_change(from: f64, to: f64) -> Option<f64> {
+    (from > 0.0).then(|| (to / from - 1.0) * 100.0)
+}
+
+/// The stock's return `sessions` bars after its close on the signal date, with
+/// the exit date. Entry is the signal day's close (or the last one before it).
+fn forward_return(
+    series: &[(NaiveDate, f64)],
+    date: NaiveDate,
+    sessions: usize,
+) -> Option<(f64, NaiveDate)> {
+    let entry = series.partition_point(|(d, _)| *d <= date).checked_sub(1)?;
+    if date - series[entry].0 > Duration::days(MAX_BASE_STALENESS_DAYS) {
+        return None;
+    }
+    let (exit_date, exit) = *series.get(entry + sessions)?;
+    Some((percent_change(series[entry].1, exit)?, exit_date))
+}
+
+/// Per-signal returns at each horizon: (raw, excess over sector).
+type Outcomes = Vec<Vec<(f64, f64)>>;
+
+/// Summary of one horizon's outcomes. The t-stat tests mean excess return
+/// against zero.
+fn summarize(sessions: usize, outcomes: &[(f64, f64)]) -> ForwardStats {
+    let count = outcomes.len();
+    let raw: Vec<f64> = outcomes.iter().map(|o| o.0).collect();
+    let excess: Vec<f64> = outcomes.iter().map(|o| o.1).collect();
+    let (mean_excess, std_excess) = mean_std(&excess);
+    let t_stat = (count >= 2 && std_excess > 0.0)
+        .then(|| mean_excess / (std_excess / (count as f64).sqrt()));
+
+    ForwardStats {
+        sessions: sessions as u32,
+        count,
+        mean_return: (count > 0).then(|| mean_std(&raw).0),
+        mean_excess_return: (count > 0).then_some(mean_excess),
+        hit_rate: (count > 0)
+            .then(|| excess.iter().filter(|e| **e > 0.0).count() as f64 / count as f64),
+        t_stat,
+    }
+}
+
+/// Forward performance of the outliers flagged in `universe` between `start`
+/// and `end`, grouped by type and by type and significance.
+///
+/// Only runs with `params` (threshold and scoring) count, and a stock flagged
+/// on consecutive sessions is one signal on the first day, so overlapping
+/// returns of a long episode don't inflate the t-stat.
+pub async fn run_backtest(
+    pool: &SqlitePool,
+    universe: &str,
+    params: &DetectionParams,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<BacktestReport, String> {
+    // SQLite fills bare columns from the row that supplied MAX(). Only
+    // sector-level flags count, as forward returns are measured against sectors.
+    let signals: Vec<Signal> = sqlx::query_as(
+        "SELECT od.stock_id, od.sector_id, od.detection_date, od.outlier_type,
+                od.significance_level, MAX(od.composite_score) as composite_score
+         FROM outlier_detections od
+         JOIN detection_runs dr ON dr.id = od.run_id
+         WHERE od.universe_type = ? AND od.gics_level = 'sector'
+           AND dr.threshold = ? AND dr.params = ?
+           AND od.detection_date >= ? AND od.detection_date <= ?
+         GROUP BY od.stock_id, od.detection_date
+         ORDER BY od.stock_id, od.detection_date",
+    )
+    .bind(universe)
+    .bind(params.threshold)
+    .bind(detection_runs::params_key(params, GicsLevel::Sector, 0)?)
+    .bind((start - Duration::days(EPISODE_LOOKBACK_DAYS)).to_string())
+    .bind(end.to_string())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch outlier detections: {e}"))?;
+
+    let start_date = start.to_string();
+    let signals: Vec<Signal> = episode_starts(signals)
+        .into_iter()
+        .filter(|s| s.detection_date >= start_date)
+        .collect();
+
+    let closes: Vec<Close> = sqlx::query_as(
+        "SELECT ph.stock_id, s.sector_id, ph.date, ph.adj_close
+         FROM price_history ph
+         JOIN stocks s ON s.id = ph.stock_id
+         WHERE s.sector_id IS NOT NULL
+           AND s.id IN (SELECT stock_id FROM stock_universe WHERE universe_type = ?)
+           AND ph.date >= ? AND ph.date <= ?
+         ORDER BY ph.stock_id, ph.date",
+    )
+    .bind(universe)
+    .bind((start - Duration::days(MAX_BASE_STALENESS_DAYS)).to_string())
+    .bind((end + Duration::days(FORWARD_DAYS)).to_string())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch price history: {e}"))?;
+
+    let spans: Vec<Span> = sqlx::query_as(
+        "SELECT stock_id, date_added, date_removed FROM stock_universe WHERE universe_type = ?",
+    )
+    .bind(universe)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch universe membership: {e}"))?;
+
+    let book = PriceBook::from_closes(closes).with_spans(spans);
+    Ok(BacktestReport {
+        universe: universe.to_string(),
+        start_date: start.to_string(),
+        end_date: end.to_string(),
+        signal_count: signals.len(),
+        groups: evaluate(&book, &signals),
+    })
+}
+
+/// The first day of each episode: signals whose stock wasn't flagged at the
+/// previous session. `signals` must be ordered by stock, then date.
+fn episode_starts(signals: Vec<Signal>) -> Vec<Signal> {
+    let mut starts = Vec::new();
+    let mut last: Option<(i32, NaiveDate)> = None;
+    for signal in signals {
+        let date = match NaiveDate::parse_from_str(&signal.detection_date, "%Y-%m-%d") {
+            Ok(d) => d,
+            Err(_) => continue,
+        };
+        let continues = matches!(last, Some((stock_id, prev))
+            if stock_id == signal.stock_id && is_next_session(prev, date));
+        last = Some((signal.stock_id, date));
+        if !continues {
+            starts.push(signal);
+        }
+    }
+    starts
+}
+
+fn evaluate(book: &PriceBook, signals: &[Signal]) -> Vec<BacktestGroup> {
+    // (type, level) → outcomes per horizon; level "" is every level together
+    let mut by_group: BTreeMap<(String, usize), Outcomes> = BTreeMap::new();
+
+    for signal in signals {
+        let date = match NaiveDate::parse_from_str(&signal.detection_date, "%Y-%m-%d") {
+            Ok(d) => d,
+            Err(_) => continue,
+        };
+        let Some(series) = book.closes.get(&signal.stock_id) else {
+            continue;
+        };
+        let level = LEVELS
+            .iter()
+            .position(|l| *l == signal.significance_level)
+            .map_or(LEVELS.len() + 1, |i| i + 1);
+
+        for (h, &sessions) in FORWARD_SESSIONS.iter().enumerate() {
+            let Some((raw, exit_date)) = forward_return(series, date, sessions) else {
+                continue;
+            };
+            let Some(sector) =
+                book.sector_return(signal.sector_id, signal.stock_id, date, exit_date)
+            else {
+                continue;
+            };
+            for key in [0, level] {
+                by_group
+                    .entry((signal.outlier_type.clone(), key))
+                    .or_insert_with(|| vec![Vec::new(); FORWARD_SESSIONS.len()])[h]
+                    .push((raw, raw - sector));
+            }
+        }
+    }
+
+    by_group
+        .into_iter()
+        .map(|((outlier_type, level), outcomes)| BacktestGroup {
+            outlier_type,
+            significance_level: level
+                .checked_sub(1)
+                .map(|i| LEVELS.get(i).copied().unwrap_or("Other").to_string()),
+            horizons: FORWARD_SESSIONS
+                .iter()
+                .zip(&outcomes)
+                .map(|(&sessions, o)| summarize(sessions, o))
+                .collect(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx_eq(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    fn d(y: i32, m: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, day).unwrap()
+    }
+
+    /// Consecutive-day closes from June 2 2025.
+    fn closes(stock_id: i32, sector_id: i32, prices: &[f64]) -> Vec<Close> {
+        prices
+            .iter()
+            .enumerate()
+            .map(|(i, &adj_close)| Close {
+                stock_id,
+                sector_id,
+                date: (d(2025, 6, 2) + Duration::days(i as i64)).to_string(),
+                adj_close,
+            })
+            .collect()
+    }
+
+    /// Membership spans open since before the test data starts.
+    fn members(stock_ids: &[i32]) -> Vec<Span> {
+        stock_ids
+            .iter()
+            .map(|&stock_id| Span {
+                stock_id,
+                date_added: "2025-01-01".to_string(),
+                date_removed: None,
+            })
+            .collect()
+    }
+
+    fn signal(stock_id: i32, date: &str, outlier_type: &str, level: &str) -> Signal {
+        Signal {
+            stock_id,
+            sector_id: 1,
+            detection_date: date.to_string(),
+            outlier_type: outlier_type.to_string(),
+            significance_level: level.to_string(),
+        }
+    }
+
+    // ---- forward_return ----
+
+    #[test]
+    fn test_forward_return_counts_sessions_from_entry() {
+        let book = PriceBook::from_closes(closes(1, 1, &[100.0, 110.0, 121.0]));
+        let series = &book.closes[&1];
+        let (r, exit) = forward_return(series, d(2025, 6, 2), 2).unwrap();
+        assert!(approx_eq(r, 21.0));
+        assert_eq!(exit, d(2025, 6, 4));
+        assert_eq!(forward_return(series, d(2025, 6, 2), 3), None);
+    }
+
+    #[test]
+    fn test_forward_return_enters_at_prior_close() {
+        let series = [(d(2025, 6, 6), 100.0), (d(2025, 6, 9), 110.0)];
+        // A Saturday signal enters at Friday's close
+        let (r, exit) = forward_return(&series, d(2025, 6, 7), 1).unwrap();
+        assert!(approx_eq(r, 10.0));
+        assert_eq!(exit, d(2025, 6, 9));
+        assert_eq!(forward_return(&series, d(2025, 6, 5), 1), None);
+        assert_eq!(forward_return(&series, d(2025, 7, 1), 1), None);
+    }
+
+    // ---- sector_return ----
+
+    #[test]
+    fn test_sector_return_averages_other_members() {
+        let mut rows = closes(1, 1, &[100.0, 200.0]);
+        rows.extend(closes(2, 1, &[100.0, 110.0]));
+        rows.extend(closes(3, 1, &[100.0, 90.0]));
+        let book = PriceBook::from_closes(rows).with_spans(members(&[1, 2, 3]));
+        let r = book
+            .sector_return(1, 1, d(2025, 6, 2), d(2025, 6, 3))
+            .unwrap();
+        assert!(approx_eq(r, 0.0));
+        assert_eq!(book.sector_return(2, 1, d(2025, 6, 2), d(2025, 6, 3)), None);
+    }
+
+    #[test]
+    fn test_sector_return_only_counts_members_at_the_signal() {
+        let mut rows = closes(1, 1, &[100.0, 200.0]);
+        rows.extend(closes(2, 1, &[100.0, 110.0]));
+        rows.extend(closes(3, 1, &[100.0, 90.0]));
+        rows.extend(closes(4, 1, &[100.0, 50.0]));
+        let mut spans = members(&[1, 2]);
+        // Left the day of the signal, and joined the day after it
+        spans.push(Span {
+            stock_id: 3,
+            date_added: "2025-01-01".to_string(),
+            date_removed: Some("2025-06-02".to_string()),
+        });
+        spans.push(Span {
+            stock_id: 4,
+            date_added: "2025-06-03".to_string(),
+            date_removed: None,
+        });
+        let book = PriceBook::from_closes(rows).with_spans(spans);
+        let r = book
+            .sector_return(1, 1, d(2025, 6, 2), d(2025, 6, 3))
+            .unwrap();
+        assert!(approx_eq(r, 10.0));
+    }
+
+    // ---- summarize ----
+
+    #[test]
+    fn test_summarize_hit_rate_and_t_stat() {
+        let s = summarize(5, &[(3.0, 1.0), (1.0, 2.0), (0.0, 3.0), (-2.0, -2.0)]);
+        assert_eq!(s.count, 4);
+        assert!(approx_eq(s.mean_return.unwrap(), 0.5));
+        assert!(approx_eq(s.mean_excess_return.unwrap(), 1.0));
+        assert!(approx_eq(s.hit_rate.unwrap(), 0.75));
+        // std of [1, 2, 3, -2] = sqrt(14/3)
+        let expected = 1.0 / ((14.0_f64 / 3.0).sqrt() / 2.0);
+        assert!(approx_eq(s.t_stat.unwrap(), expected));
+    }
+
+    #[test]
+    fn test_summarize_empty_and_single() {
+        let empty = summarize(1, &[]);
+        assert_eq!(empty.count, 0);
+        assert_eq!(empty.hit_rate, None);
+        assert_eq!(summarize(1, &[(1.0, 1.0)]).t_stat, None);
+    }
+
+    // ---- episode_starts ----
+
+    #[test]
+    fn test_episode_starts_counts_a_run_once() {
+        let starts = episode_starts(vec![
+            // Friday through Monday is one run
+            signal(1, "2025-06-06", "Undervalued", "Strong"),
+            signal(1, "2025-06-09", "Undervalued", "Moderate"),
+            // A missed session starts another
+            signal(1, "2025-06-11", "Overvalued", "Moderate"),
+            signal(2, "2025-06-12", "Undervalued", "Extreme"),
+        ]);
+        let firsts: Vec<(i32, &str)> = starts
+            .iter()
+            .map(|s| (s.stock_id, s.detection_date.as_str()))
+            .collect();
+        assert_eq!(
+            firsts,
+            vec![(1, "2025-06-06"), (1, "2025-06-11"), (2, "2025-06-12")]
+        );
+        // The first day's significance stands for the run
+        assert_eq!(starts[0].significance_level, "Strong");
+    }
+
+    // ---- evaluate ----
+
+    #[test]
+    fn test_evaluate_groups_by_type_and_level() {
+        let mut rows = closes(1, 1, &[100.0, 105.0]);
+        rows.extend(closes(2, 1, &[100.0, 101.0]));
+        let book = PriceBook::from_closes(rows).with_spans(members(&[1, 2]));
+        let groups = evaluate(&book, &[signal(1, "2025-06-02", "Undervalued", "Strong")]);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].significance_level, None);
+        assert_eq!(groups[1].significance_level.as_deref(), Some("Strong"));
+        let one_day = &groups[0].horizons[0];
+        assert_eq!((one_day.sessions, one_day.count), (1, 1));
+        assert!(approx_eq(one_day.mean_excess_return.unwrap(), 4.0));
+        // Not enough history for the longer horizons
+        assert_eq!(groups[0].horizons[1].count, 0);
+    }
+}
@@ -0,0 +1,214 @@
+use crate::market_data::{self, PriceQuote};
+use crate::provider::MarketDataProvider;
+use crate::types::{Sector, SectorBenchmark};
+use sqlx::sqlite::SqlitePool;
+use std::collections::HashMap;
+
+/// SQL expression for the daily change of a stock's sector ETF as of the
+/// stock's quote: the newest ETF quote stored at or before it on the same
+/// Eastern-time day. NULL when there is none, rather than measuring the stock
+/// against a different session. Expects `s` (stocks) and `md` (the stock's
+/// market_data row) in scope.
+///
+/// Timestamps are shifted by the standard-time offset, which only matters for
+/// quotes an hour either side of midnight.
+pub fn change_sql() -> String {
+    "(
+    SELECT bq.price_change_percent FROM benchmark_quotes bq
+    WHERE bq.sector_id = s.sector_id
+      AND bq.timestamp <= md.timestamp
+      AND date(bq.timestamp, '-5 hours') = date(md.timestamp, '-5 hours')
+    ORDER BY bq.timestamp DESC, bq.id DESC LIMIT 1
+)"
+    .to_string()
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct BenchmarkRow {
+    sector_id: i32,
+    symbol: String,
+    price: f64,
+    price_change_percent: f64,
+    timestamp: String,
+}
+
+/// Quote each sector's ETF and store it as that sector's benchmark.
+///
+/// Uses the provider's batch endpoint first and falls back to per-symbol quotes
+/// for anything it missed. Returns how many benchmarks were saved; failures are
+/// logged and never abort the refresh they ride along with.
+pub async fn refresh_benchmarks(
+    pool: &SqlitePool,
+    provider: &dyn MarketDataProvider,
+    sectors: &[Sector],
+) -> u32 {
+    let symbols: Vec<String> = sectors.iter().map(|s| s.symbol.clone()).collect();
+    let mut batched: HashMap<String, PriceQuote> = provider
+        .fetch_quote_batch(&symbols)
+        .await
+        .into_iter()
+        .map(|b| (b.symbol.to_uppercase(), b.quote))
+        .collect();
+
+    let mut saved = 0;
+    for sector in sectors {
+        let quote = match batched.remove(&sector.symbol.to_uppercase()) {
+            Some(q) => Ok(q),
+            None => provider.fetch_quote(&sector.symbol).await,
+        };
+        let result = match quote {
+            Ok(q) => save_benchmark(pool, sector.id, &q).await,
+            Err(e) => Err(format!("Failed to fetch {}: {e}", sector.symbol)),
+        };
+        match result {
+            Ok(()) => saved += 1,
+            Err(e) => eprintln!("Benchmark {}: {e}", sector.symbol),
+        }
+    }
+    saved
+}
+
+async fn save_benchmark(
+    pool: &SqlitePool,
+    sector_id: i32,
+    quote: &PriceQuote,
+) -> Result<(), String> {
+    let (change, change_percent) =
+        market_data::calculate_price_change(quote.price, quote.prev_close);
+    sqlx::query(
+        "INSERT INTO benchmark_quotes (sector_id, price, price_change, price_change_percent, volume)
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(sector_id)
+    .bind(quote.price)
+    .bind(change)
+    .bind(change_percent)
+    .bind(quote.volume)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save benchmark quote: {e}"))?;
+    Ok(())
+}
+
+/// Every sector's most recent benchmark quote, keyed by sector id.
+pub async fn latest_benchmarks(
+    pool: &SqlitePool,
+) -> Result<HashMap<i32, SectorBenchmark>, String> {
+    let rows: Vec<BenchmarkRow> = sqlx::query_as(
+        "SELECT bq.sector_id, sec.symbol, bq.price, bq.price_change_percent, bq.timestamp
+         FROM benchmark_quotes bq
+         JOIN sectors sec ON sec.id = bq.sector_id
+         WHERE bq.id = (
+             SELECT bq2.id FROM benchmark_quotes bq2
+             WHERE bq2.sector_id = bq.sector_id
+             ORDER BY bq2.timestamp DESC, bq2.id DESC LIMIT 1
+         )",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch benchmark quotes: {e}"))?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| {
+            let benchmark = SectorBenchmark {
+                symbol: r.symbol,
+                price: r.price,
+                change_percent: r.price_change_percent,
+                as_of: r.timestamp,
+            };
+            (r.sector_id, benchmark)
+        })
+        .collect())
+}
+
+/// How far a constituent-derived change sits above (positive) or below the ETF's.
+pub fn divergence(constituent: Option<f64>, benchmark: Option<&SectorBenchmark>) -> Option<f64> {
+    Some(constituent? - benchmark?.change_percent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn benchmark(change_percent: f64) -> SectorBenchmark {
+        SectorBenchmark {
+            symbol: "XLK".to_string(),
+            price: 200.0,
+            change_percent,
+            as_of: "2025-06-10 15:00:00".to_string(),
+        }
+    }
+
+    // ---- divergence ----
+
+    #[test]
+    fn test_divergence_is_constituents_minus_etf() {
+        let d = divergence(Some(1.5), Some(&benchmark(1.0))).unwrap();
+        assert!((d - 0.5).abs() < 1e-9);
+        let d = divergence(Some(-2.0), Some(&benchmark(1.0))).unwrap();
+        assert!((d + 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_divergence_needs_both_sides() {
+        assert_eq!(divergence(None, Some(&benchmark(1.0))), None);
+        assert_eq!(divergence(Some(1.0), None), None);
+    }
+
+    // ---- change_sql ----
+
+    #[tokio::test]
+    async fn test_change_sql_matches_the_stock_quote_session() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::database::run_migrations(&pool).await.unwrap();
+        let (stock_id, sector_id): (i32, i32) =
+            sqlx::query_as("SELECT id, sector_id FROM stocks WHERE sector_id IS NOT NULL LIMIT 1")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        for (timestamp, change) in [
+            ("2025-06-10 14:00:00", 1.0),
+            ("2025-06-10 19:00:00", 2.0),
+            ("2025-06-11 15:00:00", 3.0),
+        ] {
+            sqlx::query(
+                "INSERT INTO benchmark_quotes
+                    (sector_id, price, price_change, price_change_percent, timestamp)
+                 VALUES (?, 100.0, 0.0, ?, ?)",
+            )
+            .bind(sector_id)
+            .bind(change)
+            .bind(timestamp)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let sql = format!(
+            "SELECT {} FROM stocks s, (SELECT ? AS timestamp) md WHERE s.id = ?",
+            change_sql()
+        );
+        let change_at = |timestamp: &'static str| {
+            let (pool, sql) = (&pool, &sql);
+            async move {
+                sqlx::query_scalar::<_, Option<f64>>(sql)
+                    .bind(timestamp)
+                    .bind(stock_id)
+                    .fetch_one(pool)
+                    .await
+                    .unwrap()
+            }
+        };
+        // Not the later ETF quote that day, nor the next day's
+        assert_eq!(change_at("2025-06-10 15:00:00").await, Some(1.0));
+        assert_eq!(change_at("2025-06-10 20:00:00").await, Some(2.0));
+        // A stale stock quote isn't measured against the newer ETF move
+        assert_eq!(change_at("2025-06-09 20:00:00").await, None);
+        assert_eq!(change_at("2025-06-12 15:00:00").await, None);
+    }
+}
@@ -0,0 +1,434 @@
+use crate::returns;
+use crate::types::{BreadthMetrics, MarketBreadth, SectorBreadth};
+use chrono::Duration;
+use sqlx::sqlite::SqlitePool;
+use std::collections::{BTreeMap, HashMap};
+
+const SHORT_MA_SESSIONS: usize = 50;
+const LONG_MA_SESSIONS: usize = 200;
+
+/// Calendar days of history loaded: enough for the long moving average plus
+/// weekends and holidays.
+const HISTORY_DAYS: i64 = 300;
+
+/// Sessions in the exponential average of the daily advance ratio (Zweig).
+const THRUST_EMA_SESSIONS: usize = 10;
+
+/// A thrust fires when the averaged advance ratio climbs from below
+/// `THRUST_LOW` to above `THRUST_HIGH` within `THRUST_EMA_SESSIONS` sessions.
+const THRUST_LOW: f64 = 0.40;
+const THRUST_HIGH: f64 = 0.615;
+
+/// How close to the stored 52-week extreme a quote must be to count as a new
+/// high or low. The provider moves the extreme intraday, so a stock that set a
+/// new high and faded slightly by the close still counts.
+const NEW_EXTREME_TOLERANCE: f64 = 0.005;
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct MemberSnapshot {
+    stock_id: i32,
+    sector_id: i32,
+    price: f64,
+    price_change_percent: f64,
+    week52_high: Option<f64>,
+    week52_low: Option<f64>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct Close {
+    stock_id: i32,
+    sector_id: i32,
+    date: String,
+    close: f64,
+    adj_close: f64,
+}
+
+/// A member's latest quote with its moving averages, where history allows.
+#[derive(Debug, Clone)]
+struct Member {
+    quote: MemberSnapshot,
+    /// The quote on the basis of the adjusted closes the averages are taken over.
+    adjusted_price: f64,
+    short_ma: Option<f64>,
+    long_ma: Option<f64>,
+}
+
+/// Day-over-day direction of one member's close.
+#[derive(Debug, Clone)]
+struct DailyMove {
+    sector_id: i32,
+    date: String,
+    advanced: bool,
+}
+
+/// Breadth for the whole universe and for each sector.
+pub async fn market_breadth(pool: &SqlitePool, universe: &str) -> Result<MarketBreadth, String> {
+    let sectors: Vec<(i32, String, String)> =
+        sqlx::query_as("SELECT id, name, symbol FROM sectors ORDER BY name")
+            .fetch_all(pool)
+            .await
+            .map_err(|e| format!("Failed to fetch sectors: {e}"))?;
+
+    let quotes: Vec<MemberSnapshot> = sqlx::query_as(
+        "SELECT s.id as stock_id, s.sector_id, md.price, md.price_change_percent,
+                md.week52_high, md.week52_low
+         FROM stocks s
+         JOIN stock_universe su ON su.stock_id = s.id
+            AND su.universe_type = ?
+            AND su.date_removed IS NULL
+         JOIN market_data md ON md.stock_id = s.id
+            AND md.id = (
+                SELECT md2.id FROM market_data md2
+                WHERE md2.stock_id = s.id
+                ORDER BY md2.timestamp DESC LIMIT 1
+            )
+         WHERE s.sector_id IS NOT NULL",
+    )
+    .bind(universe)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch member quotes: {e}"))?;
+
+    let since = returns::market_today() - Duration::days(HISTORY_DAYS);
+    let closes: Vec<Close> = sqlx::query_as(
+        "SELECT ph.stock_id, s.sector_id, ph.date, ph.close, ph.adj_close
+         FROM price_history ph
+         JOIN stocks s ON s.id = ph.stock_id
+         JOIN stock_universe su ON su.stock_id = s.id
+            AND su.universe_type = ?
+            AND su.date_removed IS NULL
+         WHERE s.sector_id IS NOT NULL AND ph.date >= ?
+         ORDER BY ph.stock_id, ph.date",
+    )
+    .bind(universe)
+    .bind(since.to_string())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch price history: {e}"))?;
+
+    let mut by_stock: HashMap<i32, Vec<f64>> = HashMap::new();
+    let mut latest: HashMap<i32, &Close> = HashMap::new();
+    for close in &closes {
+        by_stock.entry(close.stock_id).or_default().push(close.adj_close);
+        latest.insert(close.stock_id, close);
+    }
+    let members: Vec<Member> = quotes
+        .into_iter()
+        .map(|quote| {
+            let history = by_stock.get(&quote.stock_id).map(Vec::as_slice).unwrap_or_default();
+            Member {
+                adjusted_price: adjusted_price(quote.price, latest.get(&quote.stock_id).copied()),
+                short_ma: moving_average(history, SHORT_MA_SESSIONS),
+                long_ma: moving_average(history, LONG_MA_SESSIONS),
+                quote,
+            }
+        })
+        .collect();
+    let moves = daily_moves(&closes);
+
+    let sectors = sectors
+        .into_iter()
+        .map(|(sector_id, name, symbol)| {
+            let in_sector: Vec<Member> = members
+                .iter()
+                .filter(|m| m.quote.sector_id == sector_id)
+                .cloned()
+                .collect();
+            let ratios = advance_ratios(moves.iter().filter(|m| m.sector_id == sector_id));
+            SectorBreadth {
+                sector_id,
+                name,
+                symbol,
+                breadth: breadth(&in_sector, &ratios),
+            }
+        })
+        .collect();
+
+    Ok(MarketBreadth {
+        universe: breadth(&members, &advance_ratios(moves.iter())),
+        sectors,
+    })
+}
+
+/// A quote scaled like the latest stored bar's adjusted close. Dividends put
+/// adjusted closes below actual ones, so comparing the raw quote with their
+/// average would flatter dividend payers.
+fn adjusted_price(price: f64, latest: Option<&Close>) -> f64 {
+    match latest {
+        Some(bar) if bar.close > 0.0 => price * bar.adj_close / bar.close,
+        _ => price,
+    }
+}
+
+/// Mean of the last `window` closes, or `None` with fewer than that stored.
+fn moving_average(closes: &[f64], window: usize) -> Option<f64> {
+    let start = closes.len().checked_sub(window)?;
+    Some(closes[start..].iter().sum::<f64>() / window as f64)
+}
+
+/// Up/down moves between consecutive stored closes. `closes` must be ordered by
+/// stock, then date; flat days count as neither.
+fn daily_moves(closes: &[Close]) -> Vec<DailyMove> {
+    closes
+        .windows(2)
+        .filter(|pair| {
+            pair[0].stock_id == pair[1].stock_id && pair[0].adj_close != pair[1].adj_close
+        })
+        .map(|pair| DailyMove {
+            sector_id: pair[1].sector_id,
+            date: pair[1].date.clone(),
+            advanced: pair[1].adj_close > pair[0].adj_close,
+        })
+        .collect()
+}
+
+/// Advancers over advancers plus decliners for each session, oldest first.
+fn advance_ratios<'a>(moves: impl Iterator<Item = &'a DailyMove>) -> Vec<f64> {
+    let mut by_date: BTreeMap<&str, (u32, u32)> = BTreeMap::new();
+    for m in moves {
+        let (up, down) = by_date.entry(m.date.as_str()).or_default();
+        if m.advanced {
+            *up += 1;
+        } else {
+            *down += 1;
+        }
+    }
+    by_date
+        .into_values()
+        .map(|(up, down)| up as f64 / (up + down) as f64)
+        .collect()
+}
+
+/// Exponential moving average over `ratios`, seeded with the first value.
+fn ema(ratios: &[f64], sessions: usize) -> Vec<f64> {
+    let alpha = 2.0 / (sessions as f64 + 1.0);
+    ratios
+        .iter()
+        .scan(None, |prev: &mut Option<f64>, &r| {
+            let next = match *prev {
+                Some(p) => p + alpha * (r - p),
+                None => r,
+            };
+            *prev = Some(next);
+            Some(next)
+        })
+        .collect()
+}
+
+/// Whether the averaged advance ratio went from oversold to overbought within
+/// the most recent `THRUST_EMA_SESSIONS` sessions.
+fn thrust_fired(averaged: &[f64]) -> bool {
+    let recent = &averaged[averaged.len().saturating_sub(THRUST_EMA_SESSIONS + 1)..];
+    let mut lowest = f64::INFINITY;
+    for &value in recent {
+        if lowest < THRUST_LOW && value > THRUST_HIGH {
+            return true;
+        }
+        lowest = lowest.min(value);
+    }
+    false
+}
+
+fn percent_of(count: u32, total: u32) -> Option<f64> {
+    (total > 0).then(|| count as f64 / total as f64 * 100.0)
+}
+
+/// Tally breadth for a group of members, with `ratios` being the group's daily
+/// advance ratios.
+fn breadth(members: &[Member], ratios: &[f64]) -> BreadthMetrics {
+    let mut metrics = BreadthMetrics {
+        stock_count: members.len() as u32,
+        ..BreadthMetrics::default()
+    };
+    let (mut above_short, mut with_short) = (0, 0);
+    let (mut above_long, mut with_long) = (0, 0);
+
+    for m in members {
+        let q = &m.quote;
+        if q.price_change_percent > 0.0 {
+            metrics.advancers += 1;
+        } else if q.price_change_percent < 0.0 {
+            metrics.decliners += 1;
+        } else {
+            metrics.unchanged += 1;
+        }
+        if let Some(ma) = m.short_ma {
+            with_short += 1;
+            if m.adjusted_price > ma {
+                above_short += 1;
+            }
+        }
+        if let Some(ma) = m.long_ma {
+            with_long += 1;
+            if m.adjusted_price > ma {
+                above_long += 1;
+            }
+        }
+        if q.week52_high.is_some_and(|h| h > 0.0 && q.price >= h * (1.0 - NEW_EXTREME_TOLERANCE)) {
+            metrics.new_highs += 1;
+        }
+        if q.week52_low.is_some_and(|l| l > 0.0 && q.price <= l * (1.0 + NEW_EXTREME_TOLERANCE)) {
+            metrics.new_lows += 1;
+        }
+    }
+
+    metrics.percent_above_50d = percent_of(above_short, with_short);
+    metrics.percent_above_200d = percent_of(above_long, with_long);
+    let averaged = ema(ratios, THRUST_EMA_SESSIONS);
+    metrics.thrust_ema = averaged.last().copied();
+    metrics.breadth_thrust = thrust_fired(&averaged);
+    metrics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx_eq(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    fn member(change: f64, price: f64, short_ma: Option<f64>, long_ma: Option<f64>) -> Member {
+        Member {
+            quote: MemberSnapshot {
+                stock_id: 1,
+                sector_id: 1,
+                price,
+                price_change_percent: change,
+                week52_high: Some(200.0),
+                week52_low: Some(50.0),
+            },
+            adjusted_price: price,
+            short_ma,
+            long_ma,
+        }
+    }
+
+    fn close(stock_id: i32, date: &str, adj_close: f64) -> Close {
+        Close {
+            stock_id,
+            sector_id: 1,
+            date: date.to_string(),
+            close: adj_close,
+            adj_close,
+        }
+    }
+
+    // ---- adjusted_price ----
+
+    #[test]
+    fn test_adjusted_price_scales_by_latest_bar() {
+        // Dividends have put the adjusted close 2% under the actual one
+        let bar = Close { close: 100.0, adj_close: 98.0, ..close(1, "2025-06-10", 0.0) };
+        assert!(approx_eq(adjusted_price(101.0, Some(&bar)), 98.98));
+    }
+
+    #[test]
+    fn test_adjusted_price_without_history_is_the_quote() {
+        assert!(approx_eq(adjusted_price(101.0, None), 101.0));
+    }
+
+    // ---- moving_average ----
+
+    #[test]
+    fn test_moving_average_uses_latest_window() {
+        assert!(approx_eq(moving_average(&[1.0, 2.0, 3.0, 4.0], 2).unwrap(), 3.5));
+        assert_eq!(moving_average(&[1.0, 2.0], 3), None);
+    }
+
+    // ---- daily_moves / advance_ratios ----
+
+    #[test]
+    fn test_daily_moves_stay_within_each_stock() {
+        let closes = [
+            close(1, "2025-06-09", 10.0),
+            close(1, "2025-06-10", 11.0),
+            close(2, "2025-06-09", 20.0),
+            close(2, "2025-06-10", 19.0),
+            close(3, "2025-06-10", 5.0),
+        ];
+        let moves = daily_moves(&closes);
+        assert_eq!(moves.len(), 2);
+        assert!(moves[0].advanced);
+        assert!(!moves[1].advanced);
+    }
+
+    #[test]
+    fn test_daily_moves_skip_flat_days() {
+        let closes = [close(1, "2025-06-09", 10.0), close(1, "2025-06-10", 10.0)];
+        assert!(daily_moves(&closes).is_empty());
+    }
+
+    #[test]
+    fn test_advance_ratios_by_date() {
+        let closes = [
+            close(1, "2025-06-09", 10.0),
+            close(1, "2025-06-10", 11.0),
+            close(1, "2025-06-11", 12.0),
+            close(2, "2025-06-09", 20.0),
+            close(2, "2025-06-10", 19.0),
+            close(2, "2025-06-11", 21.0),
+        ];
+        let ratios = advance_ratios(daily_moves(&closes).iter());
+        assert_eq!(ratios, vec![0.5, 1.0]);
+    }
+
+    // ---- ema / thrust_fired ----
+
+    #[test]
+    fn test_ema_seeds_with_first_value() {
+        let averaged = ema(&[0.5, 1.0], 3);
+        assert!(approx_eq(averaged[0], 0.5));
+        assert!(approx_eq(averaged[1], 0.75));
+    }
+
+    #[test]
+    fn test_thrust_fires_on_fast_swing() {
+        assert!(thrust_fired(&[0.35, 0.45, 0.55, 0.65]));
+    }
+
+    #[test]
+    fn test_thrust_needs_the_low_first() {
+        assert!(!thrust_fired(&[0.65, 0.35]));
+        assert!(!thrust_fired(&[0.45, 0.5, 0.7]));
+    }
+
+    #[test]
+    fn test_thrust_ignores_lows_outside_window() {
+        let mut averaged = vec![0.35];
+        averaged.extend(std::iter::repeat_n(0.5, THRUST_EMA_SESSIONS));
+        averaged.push(0.7);
+        assert!(!thrust_fired(&averaged));
+        assert!(!thrust_fired(&[]));
+    }
+
+    // ---- breadth ----
+
+    #[test]
+    fn test_breadth_counts_direction_and_moving_averages() {
+        let members = [
+            member(1.0, 100.0, Some(90.0), Some(110.0)),
+            member(-2.0, 100.0, Some(105.0), None),
+            member(0.0, 100.0, None, None),
+        ];
+        let b = breadth(&members, &[]);
+        assert_eq!(b.stock_count, 3);
+        assert_eq!((b.advancers, b.decliners, b.unchanged), (1, 1, 1));
+        assert!(approx_eq(b.percent_above_50d.unwrap(), 50.0));
+        assert!(approx_eq(b.percent_above_200d.unwrap(), 0.0));
+        assert_eq!(b.thrust_ema, None);
+        assert!(!b.breadth_thrust);
+    }
+
+    #[test]
+    fn test_breadth_new_highs_and_lows() {
+        let members = [
+            member(1.0, 199.5, None, None),
+            member(1.0, 150.0, None, None),
+            member(-1.0, 50.0, None, None),
+        ];
+        let b = breadth(&members, &[]);
+        assert_eq!((b.new_highs, b.new_lows), (1, 1));
+        assert_eq!(b.percent_above_50d, None);
+    }
+}
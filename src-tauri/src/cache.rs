@@ -1,8 +1,12 @@
 use crate::types::SectorSummary;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Mutex;
 
+/// Sector summaries per universe, for universes whose cache policy allows it.
 pub struct SectorCache {
-    data: Mutex<Option<CacheEntry>>,
+    data: Mutex<HashMap<String, CacheEntry>>,
+    ttl_secs: AtomicU64,
 }
 
 struct CacheEntry {
@@ -10,36 +14,52 @@ struct CacheEntry {
     cached_at: std::time::Instant,
 }
 
-const CACHE_TTL_SECS: u64 = 15 * 60; // 15 minutes
+const DEFAULT_TTL_SECS: u64 = 15 * 60; // 15 minutes
 
 impl SectorCache {
     pub fn new() -> Self {
         Self {
-            data: Mutex::new(None),
+            data: Mutex::new(HashMap::new()),
+            ttl_secs: AtomicU64::new(DEFAULT_TTL_SECS),
         }
     }
 
-    pub fn get(&self) -> Option<Vec<SectorSummary>> {
+    /// Change how long entries stay fresh. Applies to the current entry too.
+    pub fn set_ttl_minutes(&self, minutes: u32) {
+        self.ttl_secs.store(minutes as u64 * 60, Ordering::Relaxed);
+    }
+
+    pub fn get(&self, universe: &str) -> Option<Vec<SectorSummary>> {
         let guard = self.data.lock().ok()?;
-        let entry = guard.as_ref()?;
-        if entry.cached_at.elapsed().as_secs() < CACHE_TTL_SECS {
+        let entry = guard.get(universe)?;
+        if entry.cached_at.elapsed().as_secs() < self.ttl_secs.load(Ordering::Relaxed) {
             Some(entry.sectors.clone())
         } else {
             None
         }
     }
 
-    pub fn set(&self, sectors: Vec<SectorSummary>) {
+    pub fn set(&self, universe: &str, sectors: Vec<SectorSummary>) {
+        if let Ok(mut guard) = self.data.lock() {
+            guard.insert(
+                universe.to_string(),
+                CacheEntry {
+                    sectors,
+                    cached_at: std::time::Instant::now(),
+                },
+            );
+        }
+    }
+
+    /// Drop every universe's cached summaries so the next read goes to the database.
+    pub fn invalidate(&self) {
         if let Ok(mut guard) = self.data.lock() {
-            *guard = Some(CacheEntry {
-                sectors,
-                cached_at: std::time::Instant::now(),
-            });
+            guard.clear();
         }
     }
 
-    pub fn get_even_if_expired(&self) -> Option<Vec<SectorSummary>> {
+    pub fn get_even_if_expired(&self, universe: &str) -> Option<Vec<SectorSummary>> {
         let guard = self.data.lock().ok()?;
-        guard.as_ref().map(|entry| entry.sectors.clone())
+        guard.get(universe).map(|entry| entry.sectors.clone())
     }
 }
@@ -1,25 +1,62 @@
+use crate::backtest;
+use crate::benchmarks;
+use crate::breadth;
 use crate::cache::SectorCache;
-use crate::market_data;
-use crate::outlier_detection;
-use crate::russell_discovery;
-use crate::stock_discovery;
-use crate::types::{OutlierStock, RefreshResult, Sector, SectorOutliers, SectorSummary, Stock};
+use crate::gics;
+use crate::jobs::{JobRegistry, JobToken};
+use crate::market_hours;
+use crate::outlier_detection::{self, DetectionParams};
+use crate::outlier_history;
+use crate::price_history;
+use crate::provider::{self, ProviderKind};
+use crate::refresh_engine;
+use crate::returns;
+use crate::rotation;
+use crate::scheduler::SchedulerState;
+use crate::sector_aggregates;
+use crate::settings;
+use crate::stock_detail;
+use crate::universes;
+use crate::types::{
+    AppSettings, BackfillResult, BacktestReport, CachePolicy, CompositeWeights, DetectionRecord,
+    GicsLevel, GroupSummary, JobInfo, JobStatus, MarketBreadth, OutlierEpisode, OutlierStock, PeriodReturns, PricePoint,
+    RefreshResult, ReturnHorizon, SchedulerStatus, ScoreMetric, ScoringArgs, ScoringPreset, Sector,
+    SectorOutliers, SectorRotation, SectorSummary, StatsOptions, Stock, StockZScores, Universe,
+    UniverseMembership,
+};
 use crate::DbState;
 use reqwest::Client;
 use serde::Serialize;
-use tauri::{Emitter, State};
+use sqlx::sqlite::SqlitePool;
+use tauri::{AppHandle, Emitter, Manager, State};
 
 #[derive(Clone, Serialize)]
 struct ProgressPayload {
+    job_id: u64,
     current: u32,
     total: u32,
     phase: String,
 }
 
+/// Record progress on the job and forward it to the frontend.
+fn emit_progress(app: &AppHandle, job: &JobToken, phase: &str, current: u32, total: u32) {
+    job.report(phase, current, total);
+    let _ = app.emit("refresh-progress", ProgressPayload {
+        job_id: job.id(),
+        current,
+        total,
+        phase: phase.to_string(),
+    });
+}
+
 #[tauri::command]
 pub async fn get_sectors(db: State<'_, DbState>) -> Result<Vec<Sector>, String> {
+    fetch_sectors(&db.0).await
+}
+
+async fn fetch_sectors(pool: &SqlitePool) -> Result<Vec<Sector>, String> {
     sqlx::query_as::<_, Sector>("SELECT id, name, symbol FROM sectors ORDER BY name")
-        .fetch_all(&db.0)
+        .fetch_all(pool)
         .await
         .map_err(|e| format!("Failed to fetch sectors: {e}"))
 }
@@ -38,100 +75,157 @@ pub async fn get_stocks_by_sector(
     .map_err(|e| format!("Failed to fetch stocks: {e}"))
 }
 
+/// Sector summaries for a universe. The ETF benchmark and its divergence are
+/// one-day only: sector ETFs are quoted but not backfilled into price history,
+/// so the longer-period returns have nothing to compare against.
 #[tauri::command]
 pub async fn get_sector_performance(
     universe: Option<String>,
     db: State<'_, DbState>,
     cache: State<'_, SectorCache>,
 ) -> Result<Vec<SectorSummary>, String> {
-    let universe_str = universe.as_deref().unwrap_or("sp500");
+    let universe = universes::resolve(&db.0, universe.as_deref()).await?;
+    let cached = universe.cache_policy == CachePolicy::Ttl;
 
-    // Use cache only for the default sp500 universe
-    if universe_str == "sp500" {
-        if let Some(cached) = cache.get() {
-            return Ok(cached);
+    if cached {
+        if let Some(summaries) = cache.get(&universe.id) {
+            return Ok(summaries);
         }
     }
 
-    let summaries = query_sector_summaries(&db.0, universe_str).await?;
+    let summaries = query_sector_summaries(&db.0, &universe.id).await?;
 
-    if universe_str == "sp500" && !summaries.is_empty() {
-        cache.set(summaries.clone());
+    if cached && !summaries.is_empty() {
+        cache.set(&universe.id, summaries.clone());
     }
 
     Ok(summaries)
 }
 
+/// Performance of the industry groups, industries or sub-industries with
+/// members in the universe. Read from the database each time, uncached.
+#[tauri::command]
+pub async fn get_group_performance(
+    universe: Option<String>,
+    level: GicsLevel,
+    db: State<'_, DbState>,
+) -> Result<Vec<GroupSummary>, String> {
+    let universe = universes::resolve(&db.0, universe.as_deref()).await?;
+    gics::group_summaries(&db.0, &universe.id, level).await
+}
+
+/// Advance/decline, moving-average and new-high/low breadth for the universe
+/// and each of its sectors.
+#[tauri::command]
+pub async fn get_market_breadth(
+    universe: Option<String>,
+    db: State<'_, DbState>,
+) -> Result<MarketBreadth, String> {
+    let universe = universes::resolve(&db.0, universe.as_deref()).await?;
+    breadth::market_breadth(&db.0, &universe.id).await
+}
+
+/// Discover the universe's members (when it has a discovery source) and
+/// refresh their quotes.
 #[tauri::command]
 pub async fn refresh_market_data(
     app: tauri::AppHandle,
+    universe: Option<String>,
     db: State<'_, DbState>,
-    cache: State<'_, SectorCache>,
+    jobs: State<'_, JobRegistry>,
+) -> Result<RefreshResult, String> {
+    let universe = universes::resolve(&db.0, universe.as_deref()).await?;
+    let pool = db.0.clone();
+    let kind = universe.id.clone();
+    jobs.run(&kind, |job| run_universe_refresh(app, pool, universe, job))
+        .await
+}
+
+/// Discovery, quote refresh, and sector assignment for unclassified members.
+async fn run_universe_refresh(
+    app: AppHandle,
+    pool: SqlitePool,
+    universe: Universe,
+    job: JobToken,
 ) -> Result<RefreshResult, String> {
     let client = Client::new();
 
-    // Step 1: Stock discovery (non-fatal — if it fails, continue with existing stocks)
-    let _ = app.emit("refresh-progress", ProgressPayload {
-        current: 0,
-        total: 0,
-        phase: "discovery".to_string(),
-    });
+    // Step 1: Discovery (non-fatal — if it fails, continue with existing members)
+    emit_progress(&app, &job, "discovery", 0, 0);
 
-    let discovery = match stock_discovery::discover_stocks(&db.0, &client).await {
-        Ok(result) => Some(result),
+    let discovery = match universes::discover(&pool, &client, &universe).await {
+        Ok(result) => result,
         Err(e) => {
-            eprintln!("Stock discovery failed (non-fatal): {e}");
+            eprintln!("{} discovery failed (non-fatal): {e}", universe.display_name);
             None
         }
     };
+    job.checkpoint().await?;
 
-    // Step 2: Authenticate with Yahoo Finance for fundamentals data
-    let session = market_data::YahooSession::new().await
-        .map_err(|e| format!("Yahoo Finance auth failed: {e}"))?;
+    // Step 2: Connect to the configured market data provider (with fallback)
+    let config = settings::load_app_settings(&pool).await?;
+    let provider = provider::connect_provider(&pool).await?;
 
-    // Step 3: Fetch market data for ALL stocks (including any newly discovered)
-    let stocks = sqlx::query_as::<_, Stock>(
-        "SELECT id, symbol, name, sector_id FROM stocks WHERE sector_id IS NOT NULL ORDER BY symbol",
+    // Step 3: Build sector name → id map for assigning sectors to unclassified stocks
+    let sector_rows: Vec<(i32, String)> = sqlx::query_as("SELECT id, name FROM sectors")
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("Failed to fetch sectors: {e}"))?;
+    let sector_map: std::collections::HashMap<String, i32> =
+        sector_rows.into_iter().map(|(id, name)| (name, id)).collect();
+
+    // Step 4: Quote the sector ETFs first, so every stock quote has a benchmark
+    // quote from at or before it to be measured against
+    let sectors = fetch_sectors(&pool).await?;
+    benchmarks::refresh_benchmarks(&pool, provider.as_ref(), &sectors).await;
+
+    // Step 5: Fetch market data for every current member (including any newly discovered)
+    let stocks: Vec<Stock> = sqlx::query_as(
+        "SELECT s.id, s.symbol, s.name, s.sector_id
+         FROM stocks s
+         JOIN stock_universe su ON su.stock_id = s.id
+         WHERE su.universe_type = ? AND su.date_removed IS NULL
+         ORDER BY s.symbol",
     )
-    .fetch_all(&db.0)
+    .bind(&universe.id)
+    .fetch_all(&pool)
     .await
-    .map_err(|e| format!("Failed to fetch stocks: {e}"))?;
-
-    let total = stocks.len() as u32;
-    let mut success_count = 0;
-    let mut error_count = 0;
-
-    for (i, stock) in stocks.iter().enumerate() {
-        let _ = app.emit("refresh-progress", ProgressPayload {
-            current: (i + 1) as u32,
-            total,
-            phase: "market-data".to_string(),
-        });
-
-        match market_data::fetch_stock_quote(&client, &session, stock.id, &stock.symbol).await {
-            Ok(quote) => {
-                if let Err(e) = market_data::save_quote(&db.0, &quote).await {
-                    eprintln!("Failed to save {}: {e}", stock.symbol);
-                    error_count += 1;
-                } else {
-                    success_count += 1;
-                }
-            }
-            Err(e) => {
-                eprintln!("Failed to fetch {}: {e}", stock.symbol);
-                error_count += 1;
+    .map_err(|e| format!("Failed to fetch {} stocks: {e}", universe.display_name))?;
+
+    let stats = refresh_engine::refresh_quotes(
+        &pool,
+        provider.as_ref(),
+        &stocks,
+        config.refresh_concurrency as usize,
+        &job,
+        |current, total| emit_progress(&app, &job, "market-data", current, total),
+    )
+    .await;
+
+    // Step 6: Assign sector_id from provider data for unclassified stocks
+    for (stock_id, yahoo_sector) in &stats.unclassified_sectors {
+        if let Some(db_name) = map_yahoo_sector_to_db(yahoo_sector) {
+            if let Some(&sector_id) = sector_map.get(db_name) {
+                let _ = sqlx::query(
+                    "UPDATE stocks SET sector_id = ? WHERE id = ? AND sector_id IS NULL",
+                )
+                .bind(sector_id)
+                .bind(stock_id)
+                .execute(&pool)
+                .await;
             }
         }
-
-        // Small delay to respect rate limits
-        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
     }
 
-    println!("Refresh complete: {success_count} succeeded, {error_count} failed");
+    println!(
+        "{} refresh: {} succeeded, {} failed, {} skipped",
+        universe.display_name, stats.succeeded, stats.failed, stats.skipped
+    );
 
-    // Recalculate sector summaries from fresh data
-    let summaries = query_sector_summaries(&db.0, "sp500").await?;
-    cache.set(summaries.clone());
+    // Recalculate sector summaries from fresh data (even a cancelled run saved some)
+    let summaries = query_sector_summaries(&pool, &universe.id).await?;
+    cache_summaries(&app, &universe, &summaries);
+    job.checkpoint().await?;
 
     Ok(RefreshResult {
         sectors: summaries,
@@ -139,56 +233,76 @@ pub async fn refresh_market_data(
     })
 }
 
+/// Keep freshly computed summaries for universes that are served from the cache.
+fn cache_summaries(app: &AppHandle, universe: &Universe, summaries: &[SectorSummary]) {
+    if universe.cache_policy == CachePolicy::Ttl {
+        app.state::<SectorCache>().set(&universe.id, summaries.to_vec());
+    }
+}
+
 #[tauri::command]
 pub async fn refresh_sector_data(
     app: tauri::AppHandle,
     sector_symbol: String,
+    universe: Option<String>,
     db: State<'_, DbState>,
-    cache: State<'_, SectorCache>,
+    jobs: State<'_, JobRegistry>,
 ) -> Result<Vec<SectorSummary>, String> {
-    let client = Client::new();
-    let session = market_data::YahooSession::new().await
-        .map_err(|e| format!("Yahoo Finance auth failed: {e}"))?;
+    let universe = universes::resolve(&db.0, universe.as_deref()).await?;
+    let pool = db.0.clone();
+    jobs.run("sector", |job| run_sector_refresh(app, pool, sector_symbol, universe, job))
+        .await
+}
+
+/// Quote refresh for the universe's stocks in a single sector.
+async fn run_sector_refresh(
+    app: AppHandle,
+    pool: SqlitePool,
+    sector_symbol: String,
+    universe: Universe,
+    job: JobToken,
+) -> Result<Vec<SectorSummary>, String> {
+    let config = settings::load_app_settings(&pool).await?;
+    let provider = provider::connect_provider(&pool).await?;
 
-    // Get stocks for this sector only
+    // The ETF first, so the stock quotes have a benchmark quote to match
+    let sectors: Vec<Sector> = fetch_sectors(&pool)
+        .await?
+        .into_iter()
+        .filter(|s| s.symbol == sector_symbol)
+        .collect();
+    benchmarks::refresh_benchmarks(&pool, provider.as_ref(), &sectors).await;
+
+    // Get this universe's stocks for this sector only
     let stocks = sqlx::query_as::<_, Stock>(
         "SELECT s.id, s.symbol, s.name, s.sector_id FROM stocks s
          JOIN sectors sec ON s.sector_id = sec.id
+         JOIN stock_universe su ON su.stock_id = s.id
+            AND su.universe_type = ? AND su.date_removed IS NULL
          WHERE sec.symbol = ?
          ORDER BY s.symbol",
     )
+    .bind(&universe.id)
     .bind(&sector_symbol)
-    .fetch_all(&db.0)
+    .fetch_all(&pool)
     .await
     .map_err(|e| format!("Failed to fetch stocks: {e}"))?;
 
-    let total = stocks.len() as u32;
-    let mut success_count = 0;
-
-    for (i, stock) in stocks.iter().enumerate() {
-        let _ = app.emit("refresh-progress", ProgressPayload {
-            current: (i + 1) as u32,
-            total,
-            phase: "market-data".to_string(),
-        });
-
-        match market_data::fetch_stock_quote(&client, &session, stock.id, &stock.symbol).await {
-            Ok(quote) => {
-                if market_data::save_quote(&db.0, &quote).await.is_ok() {
-                    success_count += 1;
-                }
-            }
-            Err(e) => {
-                eprintln!("Failed to fetch {}: {e}", stock.symbol);
-            }
-        }
-        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-    }
+    let stats = refresh_engine::refresh_quotes(
+        &pool,
+        provider.as_ref(),
+        &stocks,
+        config.refresh_concurrency as usize,
+        &job,
+        |current, total| emit_progress(&app, &job, "market-data", current, total),
+    )
+    .await;
 
-    println!("Sector refresh ({sector_symbol}): {success_count}/{} succeeded", stocks.len());
+    println!("Sector refresh ({sector_symbol}): {}/{} succeeded", stats.succeeded, stocks.len());
 
-    let summaries = query_sector_summaries(&db.0, "sp500").await?;
-    cache.set(summaries.clone());
+    let summaries = query_sector_summaries(&pool, &universe.id).await?;
+    cache_summaries(&app, &universe, &summaries);
+    job.checkpoint().await?;
 
     Ok(summaries)
 }
@@ -198,7 +312,8 @@ async fn query_sector_summaries(
     pool: &sqlx::sqlite::SqlitePool,
     universe: &str,
 ) -> Result<Vec<SectorSummary>, String> {
-    let rows: Vec<SectorSummaryRow> = sqlx::query_as(
+    let today = returns::market_today();
+    let sql = format!(
         "SELECT
             sec.id as sector_id,
             sec.name,
@@ -207,7 +322,12 @@ async fn query_sector_summaries(
             AVG(md.pe_ratio) as avg_pe_ratio,
             SUM(md.market_cap) as total_market_cap,
             COUNT(DISTINCT s.id) as stock_count,
-            AVG(md.beta) as avg_beta
+            AVG(md.beta) as avg_beta,
+            AVG(md.price_change_percent) as return_1d,
+            AVG({week}) as return_1w,
+            AVG({month}) as return_1m,
+            AVG({quarter}) as return_3m,
+            AVG({ytd}) as return_ytd
         FROM sectors sec
         LEFT JOIN stocks s ON s.sector_id = sec.id
             AND s.id IN (
@@ -222,23 +342,54 @@ async fn query_sector_summaries(
             )
         GROUP BY sec.id
         ORDER BY sec.name",
-    )
+        week = returns::return_sql(ReturnHorizon::OneWeek, today),
+        month = returns::return_sql(ReturnHorizon::OneMonth, today),
+        quarter = returns::return_sql(ReturnHorizon::ThreeMonths, today),
+        ytd = returns::return_sql(ReturnHorizon::YearToDate, today),
+    );
+
+    let rows: Vec<SectorSummaryRow> = sqlx::query_as(&sql)
     .bind(universe)
     .fetch_all(pool)
     .await
     .map_err(|e| format!("Failed to query sector summaries: {e}"))?;
 
+    let members = sector_aggregates::fetch_member_quotes(pool, universe, GicsLevel::Sector).await?;
+    let mut etfs = benchmarks::latest_benchmarks(pool).await?;
+
     Ok(rows
         .into_iter()
-        .map(|r| SectorSummary {
-            sector_id: r.sector_id,
-            name: r.name,
-            symbol: r.symbol,
-            avg_change_percent: r.avg_change_percent,
-            avg_pe_ratio: r.avg_pe_ratio,
-            total_market_cap: r.total_market_cap,
-            stock_count: r.stock_count,
-            avg_beta: r.avg_beta,
+        .map(|r| {
+            let agg = sector_aggregates::aggregate(
+                members.get(&r.sector_id).map(Vec::as_slice).unwrap_or_default(),
+            );
+            let benchmark = etfs.remove(&r.sector_id);
+            SectorSummary {
+                sector_id: r.sector_id,
+                name: r.name,
+                symbol: r.symbol,
+                avg_change_percent: r.avg_change_percent,
+                avg_pe_ratio: r.avg_pe_ratio,
+                total_market_cap: r.total_market_cap,
+                stock_count: r.stock_count,
+                avg_beta: r.avg_beta,
+                returns: PeriodReturns {
+                    one_day: r.return_1d,
+                    one_week: r.return_1w,
+                    one_month: r.return_1m,
+                    three_months: r.return_3m,
+                    year_to_date: r.return_ytd,
+                },
+                cap_weighted_change_percent: agg.cap_weighted_change_percent,
+                change_quartiles: agg.change_quartiles,
+                cap_weighted_pe_ratio: agg.cap_weighted_pe_ratio,
+                pe_quartiles: agg.pe_quartiles,
+                benchmark_divergence: benchmarks::divergence(
+                    agg.cap_weighted_change_percent,
+                    benchmark.as_ref(),
+                ),
+                benchmark,
+            }
         })
         .collect())
 }
@@ -253,33 +404,370 @@ struct SectorSummaryRow {
     total_market_cap: Option<i64>,
     stock_count: i32,
     avg_beta: Option<f64>,
+    return_1d: Option<f64>,
+    return_1w: Option<f64>,
+    return_1m: Option<f64>,
+    return_3m: Option<f64>,
+    return_ytd: Option<f64>,
+}
+
+// -- Universe Commands --
+
+#[tauri::command]
+pub async fn get_universes(db: State<'_, DbState>) -> Result<Vec<Universe>, String> {
+    universes::list_universes(&db.0).await
+}
+
+/// Rename a universe or change its default threshold or cache policy.
+/// Omitted fields are left as they are.
+#[tauri::command]
+pub async fn update_universe(
+    universe: String,
+    display_name: Option<String>,
+    default_threshold: Option<f64>,
+    cache_policy: Option<CachePolicy>,
+    db: State<'_, DbState>,
+    cache: State<'_, SectorCache>,
+) -> Result<Universe, String> {
+    let updated =
+        universes::update_universe(&db.0, &universe, display_name, default_threshold, cache_policy)
+            .await?;
+    cache.invalidate();
+    Ok(updated)
+}
+
+// -- Watchlist Commands --
+
+/// Create an empty watchlist universe.
+#[tauri::command]
+pub async fn create_watchlist(
+    display_name: String,
+    default_threshold: Option<f64>,
+    db: State<'_, DbState>,
+) -> Result<Universe, String> {
+    universes::create_watchlist(&db.0, &display_name, default_threshold).await
+}
+
+#[tauri::command]
+pub async fn rename_watchlist(
+    universe: String,
+    display_name: String,
+    db: State<'_, DbState>,
+) -> Result<Universe, String> {
+    let current = universes::resolve(&db.0, Some(&universe)).await?;
+    universes::require_watchlist(&current)?;
+    universes::update_universe(&db.0, &current.id, Some(display_name), None, None).await
+}
+
+/// Delete a watchlist along with its memberships and detection history.
+#[tauri::command]
+pub async fn delete_watchlist(
+    universe: String,
+    db: State<'_, DbState>,
+    cache: State<'_, SectorCache>,
+) -> Result<(), String> {
+    universes::delete_watchlist(&db.0, &universe).await?;
+    cache.invalidate();
+    Ok(())
+}
+
+/// Add a ticker to a watchlist. The symbol must have a quote from the market
+/// data provider; stocks we haven't seen before are classified by the
+/// provider's sector.
+#[tauri::command]
+pub async fn add_watchlist_symbol(
+    universe: String,
+    symbol: String,
+    db: State<'_, DbState>,
+    cache: State<'_, SectorCache>,
+) -> Result<Stock, String> {
+    let universe = universes::resolve(&db.0, Some(&universe)).await?;
+    universes::require_watchlist(&universe)?;
+    let symbol = universes::normalize_symbol(&symbol)?;
+
+    let provider = provider::connect_provider(&db.0).await?;
+    provider
+        .fetch_quote(&symbol)
+        .await
+        .map_err(|e| format!("No quote for {symbol} from {}: {e}", provider.name()))?;
+    let stock = upsert_watchlist_stock(&db.0, provider.as_ref(), &symbol).await?;
+
+    universes::add_member(&db.0, &universe, &stock).await?;
+    cache.invalidate();
+    Ok(stock)
+}
+
+#[tauri::command]
+pub async fn remove_watchlist_symbol(
+    universe: String,
+    symbol: String,
+    db: State<'_, DbState>,
+    cache: State<'_, SectorCache>,
+) -> Result<(), String> {
+    let universe = universes::resolve(&db.0, Some(&universe)).await?;
+    universes::require_watchlist(&universe)?;
+    let symbol = universes::normalize_symbol(&symbol)?;
+
+    universes::remove_member(&db.0, &universe, &symbol).await?;
+    cache.invalidate();
+    Ok(())
+}
+
+/// The stock row for a validated symbol, inserting it if new. Stocks without a
+/// sector get one from the provider when it maps onto ours.
+async fn upsert_watchlist_stock(
+    pool: &SqlitePool,
+    provider: &dyn provider::MarketDataProvider,
+    symbol: &str,
+) -> Result<Stock, String> {
+    let existing: Option<Stock> =
+        sqlx::query_as("SELECT id, symbol, name, sector_id FROM stocks WHERE symbol = ?")
+            .bind(symbol)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("DB error checking {symbol}: {e}"))?;
+    if let Some(stock) = existing.filter(|s| s.sector_id.is_some()) {
+        return Ok(stock);
+    }
+
+    let sector_id: Option<i32> = match provider.fetch_sector(symbol).await {
+        Some(yahoo_sector) => match map_yahoo_sector_to_db(&yahoo_sector) {
+            Some(db_name) => sqlx::query_scalar("SELECT id FROM sectors WHERE name = ?")
+                .bind(db_name)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| format!("Failed to fetch sector {db_name}: {e}"))?,
+            None => None,
+        },
+        None => None,
+    };
+
+    // The provider doesn't report company names, so new stocks go by their ticker
+    sqlx::query(
+        "INSERT INTO stocks (symbol, name, sector_id) VALUES (?, ?, ?)
+         ON CONFLICT(symbol) DO UPDATE SET sector_id = COALESCE(stocks.sector_id, excluded.sector_id)",
+    )
+    .bind(symbol)
+    .bind(symbol)
+    .bind(sector_id)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save {symbol}: {e}"))?;
+
+    sqlx::query_as("SELECT id, symbol, name, sector_id FROM stocks WHERE symbol = ?")
+        .bind(symbol)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Failed to fetch {symbol}: {e}"))
+}
+
+// -- Sector Rotation Commands --
+
+/// Sectors ranked by strength against their universe over `rank_by` (one month
+/// by default), each with its rotation quadrant and last `trail` sessions of path.
+#[tauri::command]
+pub async fn get_sector_rotation(
+    universe: Option<String>,
+    rank_by: Option<ReturnHorizon>,
+    trail: Option<u32>,
+    db: State<'_, DbState>,
+) -> Result<Vec<SectorRotation>, String> {
+    let universe = universes::resolve(&db.0, universe.as_deref()).await?;
+    let rank_by = rank_by.unwrap_or(ReturnHorizon::OneMonth);
+    let trail = trail.unwrap_or(10).clamp(1, 250) as usize;
+    rotation::sector_rotation(&db.0, &universe.id, rank_by, trail).await
 }
 
 // -- Outlier Detection Commands --
 
+/// Composite weights for a detection request. Explicit `weights` win over a
+/// `preset`; with neither, the saved settings apply. `metrics`, when given,
+/// leaves every other metric out of the score.
+fn scoring_weights(
+    config: &AppSettings,
+    preset: Option<ScoringPreset>,
+    weights: Option<CompositeWeights>,
+    metrics: Option<Vec<ScoreMetric>>,
+) -> Result<CompositeWeights, String> {
+    let base = weights
+        .or_else(|| preset.map(outlier_detection::preset_weights))
+        .unwrap_or(config.composite_weights);
+    let selected = match metrics {
+        Some(metrics) => outlier_detection::select_metrics(base, &metrics),
+        None => base,
+    };
+    outlier_detection::validate_weights(&selected)?;
+    Ok(selected)
+}
+
+/// Sector statistics estimator for a detection request; plain mean/std by default.
+fn stats_options(stats: Option<StatsOptions>) -> Result<StatsOptions, String> {
+    let stats = stats.unwrap_or_default();
+    outlier_detection::validate_stats(&stats)?;
+    Ok(stats)
+}
+
+/// Detection parameters for a request against `universe`.
+fn detection_params(
+    universe: &Universe,
+    config: &AppSettings,
+    args: ScoringArgs,
+) -> Result<DetectionParams, String> {
+    Ok(DetectionParams {
+        threshold: args.threshold.unwrap_or(universe.default_threshold),
+        weights: scoring_weights(config, args.preset, args.weights, args.metrics)?,
+        stats: stats_options(args.stats)?,
+        horizon: args.horizon.unwrap_or_default(),
+    })
+}
+
+/// Score every peer group at `level` (sectors by default). Each outlier's
+/// `excess_return_1d_percent` is against the sector ETF's quote from the same
+/// session, and one-day only, as ETFs have no stored price history.
 #[tauri::command]
 pub async fn detect_outliers(
-    threshold: Option<f64>,
     universe: Option<String>,
+    scoring: Option<ScoringArgs>,
+    level: Option<GicsLevel>,
     db: State<'_, DbState>,
 ) -> Result<Vec<SectorOutliers>, String> {
-    let universe_str = universe.as_deref().unwrap_or("sp500");
-    let default_threshold = if universe_str == "russell2000" { 2.0 } else { 1.5 };
-    let threshold = threshold.unwrap_or(default_threshold);
-    outlier_detection::detect_all_outliers(&db.0, threshold, universe_str).await
+    let universe = universes::resolve(&db.0, universe.as_deref()).await?;
+    let config = settings::load_app_settings(&db.0).await?;
+    let params = detection_params(&universe, &config, scoring.unwrap_or_default())?;
+    let level = level.unwrap_or_default();
+    outlier_detection::detect_all_outliers(&db.0, &params, &universe.id, None, level).await
+}
+
+/// Re-run detection as it would have gone at `as_of`: an RFC 3339 timestamp,
+/// or a date meaning the end of that day in New York. Only quotes stored by
+/// then and the universe membership at the time are used.
+#[tauri::command]
+pub async fn replay_outliers(
+    as_of: String,
+    universe: Option<String>,
+    scoring: Option<ScoringArgs>,
+    level: Option<GicsLevel>,
+    db: State<'_, DbState>,
+) -> Result<Vec<SectorOutliers>, String> {
+    let as_of = parse_as_of(&as_of)?;
+    if as_of > chrono::Utc::now() {
+        return Err(format!("Cannot replay detection at {as_of}, which is in the future"));
+    }
+    let universe = universes::resolve(&db.0, universe.as_deref()).await?;
+    let config = settings::load_app_settings(&db.0).await?;
+    let params = detection_params(&universe, &config, scoring.unwrap_or_default())?;
+    let level = level.unwrap_or_default();
+    outlier_detection::detect_all_outliers(&db.0, &params, &universe.id, Some(as_of), level).await
+}
+
+fn parse_as_of(as_of: &str) -> Result<chrono::DateTime<chrono::Utc>, String> {
+    if let Ok(t) = chrono::DateTime::parse_from_rfc3339(as_of) {
+        return Ok(t.with_timezone(&chrono::Utc));
+    }
+    chrono::NaiveDate::parse_from_str(as_of, "%Y-%m-%d")
+        .map(market_hours::eastern_day_end)
+        .map_err(|_| format!("Invalid replay time '{as_of}': expected RFC 3339 or YYYY-MM-DD"))
 }
 
 #[tauri::command]
 pub async fn get_sector_outliers(
     sector_id: i32,
-    threshold: Option<f64>,
     universe: Option<String>,
+    scoring: Option<ScoringArgs>,
     db: State<'_, DbState>,
 ) -> Result<Vec<OutlierStock>, String> {
-    let universe_str = universe.as_deref().unwrap_or("sp500");
-    let default_threshold = if universe_str == "russell2000" { 2.0 } else { 1.5 };
-    let threshold = threshold.unwrap_or(default_threshold);
-    outlier_detection::detect_sector_outliers(&db.0, sector_id, threshold, universe_str).await
+    let universe = universes::resolve(&db.0, universe.as_deref()).await?;
+    let config = settings::load_app_settings(&db.0).await?;
+    let params = detection_params(&universe, &config, scoring.unwrap_or_default())?;
+    let sector = gics::peer_group(&db.0, GicsLevel::Sector, sector_id).await?;
+    let detection =
+        outlier_detection::detect_group_outliers(&db.0, &sector, &params, &universe.id, None)
+            .await?;
+    Ok(detection.outliers)
+}
+
+/// Runs of consecutive outlier flags that were active within the last `days`
+/// (30 by default), most recent first. `level` picks which peer groups' flags
+/// are returned, sectors by default. Only flags from detection with the given
+/// threshold and scoring count, defaulting as `detect_outliers` does.
+#[tauri::command]
+pub async fn get_outlier_episodes(
+    universe: Option<String>,
+    sector_id: Option<i32>,
+    level: Option<GicsLevel>,
+    group_id: Option<i32>,
+    days: Option<u32>,
+    scoring: Option<ScoringArgs>,
+    db: State<'_, DbState>,
+) -> Result<Vec<OutlierEpisode>, String> {
+    let universe = universes::resolve(&db.0, universe.as_deref()).await?;
+    let config = settings::load_app_settings(&db.0).await?;
+    let params = detection_params(&universe, &config, scoring.unwrap_or_default())?;
+    let days = days.unwrap_or(30).clamp(1, 365);
+    let today = returns::market_today();
+    let since = today - chrono::Duration::days(days as i64);
+    outlier_history::outlier_episodes(
+        &db.0,
+        &universe.id,
+        &params,
+        level.unwrap_or_default(),
+        sector_id,
+        group_id,
+        since,
+        today,
+    )
+    .await
+}
+
+/// Forward returns of the outliers flagged between `start_date` and `end_date`
+/// (the last year by default) against their sectors, for runs scored with the
+/// given threshold and scoring.
+#[tauri::command]
+pub async fn get_outlier_backtest(
+    universe: Option<String>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    scoring: Option<ScoringArgs>,
+    db: State<'_, DbState>,
+) -> Result<BacktestReport, String> {
+    let universe = universes::resolve(&db.0, universe.as_deref()).await?;
+    let config = settings::load_app_settings(&db.0).await?;
+    let params = detection_params(&universe, &config, scoring.unwrap_or_default())?;
+    let parse = |date: &str| {
+        chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|e| format!("Invalid date '{date}': {e}"))
+    };
+    let end = match end_date.as_deref() {
+        Some(d) => parse(d)?,
+        None => returns::market_today(),
+    };
+    let start = match start_date.as_deref() {
+        Some(d) => parse(d)?,
+        None => end - chrono::Duration::days(365),
+    };
+    if start > end {
+        return Err(format!("Backtest start {start} is after end {end}"));
+    }
+    backtest::run_backtest(&db.0, &universe.id, &params, start, end).await
+}
+
+/// Select the market data provider used by refreshes. Unknown provider names are rejected.
+/// The Alpha Vantage API key is only overwritten when one is supplied.
+#[tauri::command]
+pub async fn set_market_data_provider(
+    provider: String,
+    api_key: Option<String>,
+    db: State<'_, DbState>,
+) -> Result<(), String> {
+    let kind = ProviderKind::parse(&provider)
+        .ok_or_else(|| format!("Unknown market data provider '{provider}'"))?;
+    settings::set_setting(&db.0, provider::PROVIDER_SETTING, kind.as_str()).await?;
+
+    if let Some(key) = api_key {
+        settings::set_setting(&db.0, provider::ALPHA_VANTAGE_KEY_SETTING, key.trim()).await?;
+    }
+
+    Ok(())
 }
 
 /// Map a Yahoo Finance sector name to the matching DB sector name.
@@ -301,107 +789,210 @@ fn map_yahoo_sector_to_db(yahoo_sector: &str) -> Option<&'static str> {
     }
 }
 
-// -- Russell 2000 Universe Command --
+// -- Price History Commands --
 
+/// Backfill daily OHLCV bars for every current universe member (or one universe).
+/// Safe to re-run: each stock resumes from its last stored bar and fills any gaps.
 #[tauri::command]
-pub async fn refresh_russell_2000_data(
+pub async fn backfill_price_history(
     app: tauri::AppHandle,
+    years: Option<u32>,
+    universe: Option<String>,
     db: State<'_, DbState>,
-) -> Result<RefreshResult, String> {
-    let client = Client::new();
+    jobs: State<'_, JobRegistry>,
+) -> Result<BackfillResult, String> {
+    let years = years.unwrap_or(5).clamp(1, 30);
+    if let Some(id) = universe.as_deref() {
+        universes::resolve(&db.0, Some(id)).await?;
+    }
+    let pool = db.0.clone();
+    jobs.run("history", |job| run_backfill(app, pool, years, universe, job))
+        .await
+}
 
-    // Step 1: Discover Russell 2000 stocks from iShares IWM CSV
-    let _ = app.emit("refresh-progress", ProgressPayload {
-        current: 0,
-        total: 0,
-        phase: "discovery".to_string(),
-    });
+/// Backfill daily bars for the selected universe, resuming each stock where it left off.
+async fn run_backfill(
+    app: AppHandle,
+    pool: SqlitePool,
+    years: u32,
+    universe: Option<String>,
+    job: JobToken,
+) -> Result<BackfillResult, String> {
+    let today = chrono::Utc::now().date_naive();
+    let target_start = today - chrono::Duration::days(365 * years as i64);
+
+    let config = settings::load_app_settings(&pool).await?;
+    let provider = provider::connect_provider(&pool).await?;
+    let targets = price_history::backfill_targets(&pool, universe.as_deref()).await?;
+
+    let result = refresh_engine::backfill_history(
+        &pool,
+        provider.as_ref(),
+        &targets,
+        target_start,
+        today,
+        config.refresh_concurrency as usize,
+        &job,
+        |current, total| emit_progress(&app, &job, "history", current, total),
+    )
+    .await;
 
-    let discovery = match russell_discovery::discover_russell_2000(&db.0, &client).await {
-        Ok(result) => Some(result),
-        Err(e) => {
-            eprintln!("Russell 2000 discovery failed (non-fatal): {e}");
-            None
-        }
-    };
+    println!(
+        "History backfill: {} stocks, {} bars, {} errors",
+        result.stocks_processed,
+        result.bars_written,
+        result.errors.len()
+    );
+    job.checkpoint().await?;
 
-    // Step 2: Authenticate with Yahoo Finance
-    let session = market_data::YahooSession::new()
-        .await
-        .map_err(|e| format!("Yahoo Finance auth failed: {e}"))?;
+    Ok(result)
+}
 
-    // Step 3: Build sector name → id map for assigning sectors to unclassified stocks
-    let sector_rows: Vec<(i32, String)> = sqlx::query_as("SELECT id, name FROM sectors")
-        .fetch_all(&db.0)
-        .await
-        .map_err(|e| format!("Failed to fetch sectors: {e}"))?;
-    let sector_map: std::collections::HashMap<String, i32> =
-        sector_rows.into_iter().map(|(id, name)| (name, id)).collect();
+// -- Stock Drill-in Commands --
 
-    // Step 4: Fetch market data for all Russell 2000 stocks
-    let stocks: Vec<Stock> = sqlx::query_as(
-        "SELECT s.id, s.symbol, s.name, s.sector_id
-         FROM stocks s
-         JOIN stock_universe su ON su.stock_id = s.id
-         WHERE su.universe_type = 'russell2000' AND su.date_removed IS NULL
-         ORDER BY s.symbol",
+/// Price series for a stock: stored daily bars plus any newer quote snapshots.
+#[tauri::command]
+pub async fn get_stock_history(
+    symbol: String,
+    days: u32,
+    db: State<'_, DbState>,
+) -> Result<Vec<PricePoint>, String> {
+    let stock_id = stock_detail::find_stock_id(&db.0, &symbol).await?;
+    stock_detail::stock_history(&db.0, stock_id, days.clamp(1, 365 * 30)).await
+}
+
+/// Past outlier detections for a stock, newest first.
+#[tauri::command]
+pub async fn get_stock_detections(
+    symbol: String,
+    limit: Option<u32>,
+    db: State<'_, DbState>,
+) -> Result<Vec<DetectionRecord>, String> {
+    let stock_id = stock_detail::find_stock_id(&db.0, &symbol).await?;
+    stock_detail::stock_detections(&db.0, stock_id, limit.unwrap_or(100)).await
+}
+
+/// Current Z-scores for a stock against its sector, or its GICS group at
+/// `level`. `None` when there isn't enough data.
+#[tauri::command]
+pub async fn get_stock_z_scores(
+    symbol: String,
+    universe: Option<String>,
+    stats: Option<StatsOptions>,
+    horizon: Option<ReturnHorizon>,
+    level: Option<GicsLevel>,
+    db: State<'_, DbState>,
+) -> Result<Option<StockZScores>, String> {
+    let universe = universes::resolve(&db.0, universe.as_deref()).await?;
+    let stock_id = stock_detail::find_stock_id(&db.0, &symbol).await?;
+    let config = settings::load_app_settings(&db.0).await?;
+    let stats = stats_options(stats)?;
+    outlier_detection::stock_z_scores(
+        &db.0,
+        stock_id,
+        &config.composite_weights,
+        &stats,
+        horizon.unwrap_or_default(),
+        &universe.id,
+        level.unwrap_or_default(),
     )
-    .fetch_all(&db.0)
     .await
-    .map_err(|e| format!("Failed to fetch Russell 2000 stocks: {e}"))?;
-
-    let total = stocks.len() as u32;
-    let mut success_count = 0;
-    let mut error_count = 0;
-
-    for (i, stock) in stocks.iter().enumerate() {
-        let _ = app.emit("refresh-progress", ProgressPayload {
-            current: (i + 1) as u32,
-            total,
-            phase: "market-data".to_string(),
-        });
-
-        match market_data::fetch_stock_quote(&client, &session, stock.id, &stock.symbol).await {
-            Ok(quote) => {
-                // Assign sector_id from Yahoo Finance data for unclassified stocks
-                if stock.sector_id.is_none() {
-                    if let Some(ref yahoo_sector) = quote.yahoo_sector {
-                        if let Some(db_name) = map_yahoo_sector_to_db(yahoo_sector) {
-                            if let Some(&sector_id) = sector_map.get(db_name) {
-                                let _ = sqlx::query(
-                                    "UPDATE stocks SET sector_id = ? WHERE id = ? AND sector_id IS NULL",
-                                )
-                                .bind(sector_id)
-                                .bind(stock.id)
-                                .execute(&db.0)
-                                .await;
-                            }
-                        }
-                    }
-                }
-
-                if let Err(e) = market_data::save_quote(&db.0, &quote).await {
-                    eprintln!("Failed to save {}: {e}", stock.symbol);
-                    error_count += 1;
-                } else {
-                    success_count += 1;
-                }
-            }
-            Err(e) => {
-                eprintln!("Failed to fetch {}: {e}", stock.symbol);
-                error_count += 1;
-            }
-        }
+}
+
+/// Universe membership history for a stock.
+#[tauri::command]
+pub async fn get_stock_memberships(
+    symbol: String,
+    db: State<'_, DbState>,
+) -> Result<Vec<UniverseMembership>, String> {
+    let stock_id = stock_detail::find_stock_id(&db.0, &symbol).await?;
+    stock_detail::stock_memberships(&db.0, stock_id).await
+}
+
+// -- Background Job Commands --
 
-        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+/// Cancel a running or paused refresh job. Work already in flight finishes first.
+#[tauri::command]
+pub async fn cancel_refresh(
+    job_id: u64,
+    jobs: State<'_, JobRegistry>,
+) -> Result<JobStatus, String> {
+    jobs.cancel(job_id)
+}
+
+/// Pause a running refresh job before it starts on its next stock.
+#[tauri::command]
+pub async fn pause_refresh(
+    job_id: u64,
+    jobs: State<'_, JobRegistry>,
+) -> Result<JobStatus, String> {
+    jobs.pause(job_id)
+}
+
+#[tauri::command]
+pub async fn resume_refresh(
+    job_id: u64,
+    jobs: State<'_, JobRegistry>,
+) -> Result<JobStatus, String> {
+    jobs.resume(job_id)
+}
+
+/// Running jobs and recently finished ones, newest first.
+#[tauri::command]
+pub async fn list_jobs(jobs: State<'_, JobRegistry>) -> Result<Vec<JobInfo>, String> {
+    Ok(jobs.list())
+}
+
+// -- Scheduler Commands --
+
+/// Automatic refresh settings, plus when the last and next runs happen.
+#[tauri::command]
+pub async fn get_scheduler_status(
+    scheduler: State<'_, SchedulerState>,
+) -> Result<SchedulerStatus, String> {
+    scheduler
+        .status()
+        .ok_or_else(|| "Scheduler has not started yet".to_string())
+}
+
+/// Turn automatic refreshes on or off and set how often they run during market hours.
+#[tauri::command]
+pub async fn set_scheduler(
+    enabled: bool,
+    interval_minutes: Option<u32>,
+    db: State<'_, DbState>,
+    scheduler: State<'_, SchedulerState>,
+) -> Result<(), String> {
+    let mut config = settings::load_app_settings(&db.0).await?;
+    config.scheduler_enabled = enabled;
+    if let Some(minutes) = interval_minutes {
+        config.scheduler_interval_minutes = minutes;
     }
+    settings::save_app_settings(&db.0, &config).await?;
 
-    println!("Russell 2000 refresh: {success_count} succeeded, {error_count} failed");
+    scheduler.reschedule();
+    Ok(())
+}
 
-    let summaries = query_sector_summaries(&db.0, "russell2000").await?;
+// -- Settings Commands --
 
-    Ok(RefreshResult {
-        sectors: summaries,
-        discovery,
-    })
+#[tauri::command]
+pub async fn get_settings(db: State<'_, DbState>) -> Result<AppSettings, String> {
+    settings::load_app_settings(&db.0).await
+}
+
+/// Validate and save settings. Weights and request rates apply to the
+/// next detection or refresh; the cache lifetime and scheduler change immediately.
+#[tauri::command]
+pub async fn update_settings(
+    config: AppSettings,
+    db: State<'_, DbState>,
+    cache: State<'_, SectorCache>,
+    scheduler: State<'_, SchedulerState>,
+) -> Result<(), String> {
+    settings::save_app_settings(&db.0, &config).await?;
+
+    cache.set_ttl_minutes(config.cache_ttl_minutes);
+    scheduler.reschedule();
+    Ok(())
 }
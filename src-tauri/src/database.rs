@@ -35,8 +35,8 @@ pub async fn init_database(app: &AppHandle) -> Result<SqlitePool, String> {
     Ok(pool)
 }
 
-/// Run migrations by executing SQL files in order.
-async fn run_migrations(pool: &SqlitePool) -> Result<(), String> {
+/// Run migrations by executing SQL files in order, each in its own transaction.
+pub async fn run_migrations(pool: &SqlitePool) -> Result<(), String> {
     // Create migration tracking table
     sqlx::query(
         "CREATE TABLE IF NOT EXISTS _migrations (
@@ -67,6 +67,50 @@ async fn run_migrations(pool: &SqlitePool) -> Result<(), String> {
             "005_universe",
             include_str!("../migrations/005_universe.sql"),
         ),
+        (
+            "006_app_settings",
+            include_str!("../migrations/006_app_settings.sql"),
+        ),
+        (
+            "007_price_history",
+            include_str!("../migrations/007_price_history.sql"),
+        ),
+        (
+            "008_extended_metric_scores",
+            include_str!("../migrations/008_extended_metric_scores.sql"),
+        ),
+        (
+            "009_sector_benchmarks",
+            include_str!("../migrations/009_sector_benchmarks.sql"),
+        ),
+        (
+            "010_detection_runs",
+            include_str!("../migrations/010_detection_runs.sql"),
+        ),
+        (
+            "011_universes",
+            include_str!("../migrations/011_universes.sql"),
+        ),
+        (
+            "012_membership_spans",
+            include_str!("../migrations/012_membership_spans.sql"),
+        ),
+        (
+            "013_more_universes",
+            include_str!("../migrations/013_more_universes.sql"),
+        ),
+        (
+            "014_gics_hierarchy",
+            include_str!("../migrations/014_gics_hierarchy.sql"),
+        ),
+        (
+            "015_detection_run_inputs",
+            include_str!("../migrations/015_detection_run_inputs.sql"),
+        ),
+        (
+            "016_price_history_gaps",
+            include_str!("../migrations/016_price_history_gaps.sql"),
+        ),
     ];
 
     for (name, sql) in migrations {
@@ -79,11 +123,16 @@ async fn run_migrations(pool: &SqlitePool) -> Result<(), String> {
         .map_err(|e| format!("Failed to check migration {name}: {e}"))?;
 
         if !applied {
+            // All or nothing, so a failing statement can't leave a migration half applied
+            let mut tx = pool
+                .begin()
+                .await
+                .map_err(|e| format!("Failed to start migration {name}: {e}"))?;
             for statement in sql.split(';') {
                 let trimmed = statement.trim();
                 if !trimmed.is_empty() {
                     sqlx::query(trimmed)
-                        .execute(pool)
+                        .execute(&mut *tx)
                         .await
                         .map_err(|e| format!("Migration {name} failed: {e}"))?;
                 }
@@ -91,9 +140,12 @@ async fn run_migrations(pool: &SqlitePool) -> Result<(), String> {
 
             sqlx::query("INSERT INTO _migrations (name) VALUES (?)")
                 .bind(name)
-                .execute(pool)
+                .execute(&mut *tx)
                 .await
                 .map_err(|e| format!("Failed to record migration {name}: {e}"))?;
+            tx.commit()
+                .await
+                .map_err(|e| format!("Failed to commit migration {name}: {e}"))?;
 
             println!("Applied migration: {name}");
         }
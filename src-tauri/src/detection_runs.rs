@@ -0,0 +1,297 @@
+use crate::gics::PeerGroup;
+use crate::outlier_detection::DetectionParams;
+use crate::types::{CompositeWeights, GicsLevel, OutlierStock, ReturnHorizon, StatsOptions};
+use chrono::NaiveDate;
+use serde::Serialize;
+use sqlx::sqlite::{SqliteConnection, SqlitePool};
+
+/// What a run is keyed on besides universe, sector, threshold and inputs.
+#[derive(Serialize)]
+struct RunParams<'a> {
+    weights: &'a CompositeWeights,
+    stats: &'a StatsOptions,
+    horizon: ReturnHorizon,
+    /// Peer group below the sector. Left out for sector runs so their keys
+    /// match the runs recorded before there were other levels.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group: Option<(GicsLevel, i32)>,
+}
+
+/// FNV-1a, which unlike `DefaultHasher` is stable across Rust releases.
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// What a run scored: the newest quote among the members, and a digest of
+/// every member's inputs, so any change to them makes a new run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunInputs {
+    pub data_as_of: String,
+    pub digest: String,
+}
+
+impl RunInputs {
+    /// From one line per scored member, in any order.
+    pub fn new(data_as_of: &str, mut members: Vec<String>) -> Self {
+        members.sort();
+        let mut hash = FNV_OFFSET;
+        for byte in members.join("\n").bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+        RunInputs {
+            data_as_of: data_as_of.to_string(),
+            digest: format!("{}:{hash:016x}", members.len()),
+        }
+    }
+}
+
+/// A recorded detection run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectionRun {
+    pub id: i64,
+    /// Day the run's detections are dated, which for a reused run is the day
+    /// it first ran rather than today.
+    pub detection_date: NaiveDate,
+    /// `false` when an identical earlier run was reused and nothing was written.
+    pub created: bool,
+}
+
+/// Canonical text form of the scoring parameters for group `group_id` at
+/// `level`, stable across calls with equal inputs so it can be part of the
+/// run's unique key.
+pub fn params_key(
+    params: &DetectionParams,
+    level: GicsLevel,
+    group_id: i32,
+) -> Result<String, String> {
+    serde_json::to_string(&RunParams {
+        weights: &params.weights,
+        stats: &params.stats,
+        horizon: params.horizon,
+        group: (level != GicsLevel::Sector).then_some((level, group_id)),
+    })
+    .map_err(|e| format!("Failed to encode detection parameters: {e}"))
+}
+
+/// Record a peer group's detection run and its outliers, or reuse the existing
+/// run when the same parameters were already applied to the same inputs.
+///
+/// The run and its detections are written in one transaction, so a run never
+/// exists without them.
+pub async fn record_run(
+    pool: &SqlitePool,
+    universe: &str,
+    group: &PeerGroup,
+    params: &DetectionParams,
+    inputs: &RunInputs,
+    today: NaiveDate,
+    outliers: &[OutlierStock],
+) -> Result<DetectionRun, String> {
+    let key = params_key(params, group.level, group.id)?;
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start detection run: {e}"))?;
+
+    let inserted = sqlx::query(
+        "INSERT OR IGNORE INTO detection_runs
+            (universe_type, sector_id, threshold, params, inputs, data_as_of, detection_date)
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(universe)
+    .bind(group.sector_id)
+    .bind(params.threshold)
+    .bind(&key)
+    .bind(&inputs.digest)
+    .bind(&inputs.data_as_of)
+    .bind(today.to_string())
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to record detection run: {e}"))?
+    .rows_affected()
+        == 1;
+
+    let (id, date): (i64, String) = sqlx::query_as(
+        "SELECT id, detection_date FROM detection_runs
+         WHERE universe_type = ? AND sector_id = ? AND threshold = ?
+           AND params = ? AND inputs = ?",
+    )
+    .bind(universe)
+    .bind(group.sector_id)
+    .bind(params.threshold)
+    .bind(&key)
+    .bind(&inputs.digest)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to fetch detection run: {e}"))?;
+    let detection_date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|e| format!("Bad detection date '{date}' on run {id}: {e}"))?;
+
+    if inserted {
+        for outlier in outliers {
+            save_detection(
+                &mut tx,
+                id,
+                outlier,
+                group,
+                params,
+                universe,
+                detection_date,
+            )
+            .await?;
+        }
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save detection run: {e}"))?;
+
+    Ok(DetectionRun {
+        id,
+        detection_date,
+        created: inserted,
+    })
+}
+
+/// Day of the latest run before `before` for this peer group, universe,
+/// threshold and scoring, if any.
+pub async fn previous_run_date(
+    pool: &SqlitePool,
+    universe: &str,
+    group: &PeerGroup,
+    params: &DetectionParams,
+    before: NaiveDate,
+) -> Result<Option<NaiveDate>, String> {
+    let date: Option<String> = sqlx::query_scalar(
+        "SELECT MAX(detection_date) FROM detection_runs
+         WHERE universe_type = ? AND sector_id = ? AND threshold = ? AND params = ?
+           AND detection_date < ?",
+    )
+    .bind(universe)
+    .bind(group.sector_id)
+    .bind(params.threshold)
+    .bind(params_key(params, group.level, group.id)?)
+    .bind(before.to_string())
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch previous detection run: {e}"))?;
+    date.map(|d| {
+        NaiveDate::parse_from_str(&d, "%Y-%m-%d")
+            .map_err(|e| format!("Bad detection date '{d}': {e}"))
+    })
+    .transpose()
+}
+
+/// Save one outlier under its run, dated by the New York calendar so it lines
+/// up with the trading sessions episodes are counted in.
+async fn save_detection(
+    conn: &mut SqliteConnection,
+    run_id: i64,
+    outlier: &OutlierStock,
+    group: &PeerGroup,
+    params: &DetectionParams,
+    universe: &str,
+    date: NaiveDate,
+) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO outlier_detections (
+            run_id, stock_id, sector_id, detection_date, pe_z_score, pb_z_score,
+            price_z_score, volume_z_score, dividend_yield_z_score,
+            beta_z_score, eps_z_score, range_position_z_score, week52_position,
+            composite_score, outlier_type, significance_level, threshold_used, universe_type,
+            gics_level, group_id
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(run_id)
+    .bind(outlier.stock_id)
+    .bind(group.sector_id)
+    .bind(date.to_string())
+    .bind(outlier.z_scores.pe_z)
+    .bind(outlier.z_scores.pb_z)
+    .bind(outlier.z_scores.price_z)
+    .bind(outlier.z_scores.volume_z)
+    .bind(outlier.z_scores.dividend_yield_z)
+    .bind(outlier.z_scores.beta_z)
+    .bind(outlier.z_scores.eps_z)
+    .bind(outlier.z_scores.range_position_z)
+    .bind(outlier.week52_position)
+    .bind(outlier.composite_score)
+    .bind(outlier.outlier_type.to_string())
+    .bind(outlier.significance_level.to_string())
+    .bind(params.threshold)
+    .bind(universe)
+    .bind(group.level.as_str())
+    .bind(group.id)
+    .execute(conn)
+    .await
+    .map_err(|e| format!("Failed to save outlier detection: {e}"))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::outlier_detection::DEFAULT_WEIGHTS;
+
+    fn params(threshold: f64, horizon: ReturnHorizon) -> DetectionParams {
+        DetectionParams {
+            threshold,
+            weights: DEFAULT_WEIGHTS,
+            stats: StatsOptions::default(),
+            horizon,
+        }
+    }
+
+    #[test]
+    fn test_params_key_is_stable() {
+        let a = params_key(&params(1.5, ReturnHorizon::OneDay), GicsLevel::Sector, 3).unwrap();
+        let b = params_key(&params(1.5, ReturnHorizon::OneDay), GicsLevel::Sector, 3).unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_params_key_leaves_out_threshold() {
+        // Threshold has its own column
+        let a = params_key(&params(1.5, ReturnHorizon::OneDay), GicsLevel::Sector, 3).unwrap();
+        let b = params_key(&params(2.0, ReturnHorizon::OneDay), GicsLevel::Sector, 3).unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_params_key_tracks_scoring_inputs() {
+        let a = params_key(&params(1.5, ReturnHorizon::OneDay), GicsLevel::Sector, 3).unwrap();
+        let b = params_key(&params(1.5, ReturnHorizon::OneWeek), GicsLevel::Sector, 3).unwrap();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_params_key_tracks_group_below_sector() {
+        let p = params(1.5, ReturnHorizon::OneDay);
+        let sector_key = params_key(&p, GicsLevel::Sector, 3).unwrap();
+        assert!(!sector_key.contains("group"));
+
+        let industry = params_key(&p, GicsLevel::Industry, 7).unwrap();
+        let sub_industry = params_key(&p, GicsLevel::SubIndustry, 7).unwrap();
+        assert_ne!(industry, sector_key);
+        assert_ne!(industry, sub_industry);
+    }
+
+    #[test]
+    fn test_run_inputs_ignore_member_order() {
+        let a = RunInputs::new("t", vec!["1|a".to_string(), "2|b".to_string()]);
+        let b = RunInputs::new("t", vec!["2|b".to_string(), "1|a".to_string()]);
+        assert_eq!(a, b);
+        assert!(a.digest.starts_with("2:"));
+    }
+
+    #[test]
+    fn test_run_inputs_track_members_and_values() {
+        let base = RunInputs::new("t", vec!["1|a".to_string(), "2|b".to_string()]);
+        // Same newest quote, but a member dropped out or a value changed
+        let dropped = RunInputs::new("t", vec!["1|a".to_string()]);
+        let changed = RunInputs::new("t", vec!["1|a".to_string(), "2|c".to_string()]);
+        assert_ne!(base.digest, dropped.digest);
+        assert_ne!(base.digest, changed.digest);
+    }
+}
@@ -0,0 +1,263 @@
+use crate::returns;
+use crate::sector_aggregates;
+use crate::types::{GicsLevel, GroupSummary, PeriodReturns, ReturnHorizon};
+use sqlx::sqlite::SqlitePool;
+
+impl GicsLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GicsLevel::Sector => "sector",
+            GicsLevel::IndustryGroup => "industry_group",
+            GicsLevel::Industry => "industry",
+            GicsLevel::SubIndustry => "sub_industry",
+        }
+    }
+
+    /// Column of the `stock_gics` view holding a stock's group at this level.
+    pub fn member_column(&self) -> &'static str {
+        match self {
+            GicsLevel::Sector => "sector_id",
+            GicsLevel::IndustryGroup => "industry_group_id",
+            GicsLevel::Industry => "industry_id",
+            GicsLevel::SubIndustry => "sub_industry_id",
+        }
+    }
+
+    /// Every group at this level as `id, name, sector_id` rows.
+    fn groups_sql(&self) -> &'static str {
+        match self {
+            GicsLevel::Sector => "SELECT sec.id, sec.name, sec.id AS sector_id FROM sectors sec",
+            GicsLevel::IndustryGroup => {
+                "SELECT ig.id, ig.name, ig.sector_id FROM gics_industry_groups ig"
+            }
+            GicsLevel::Industry => {
+                "SELECT gi.id, gi.name, ig.sector_id FROM gics_industries gi
+                 JOIN gics_industry_groups ig ON ig.id = gi.industry_group_id"
+            }
+            GicsLevel::SubIndustry => {
+                "SELECT gsi.id, gsi.name, ig.sector_id FROM gics_sub_industries gsi
+                 JOIN gics_industries gi ON gi.id = gsi.industry_id
+                 JOIN gics_industry_groups ig ON ig.id = gi.industry_group_id"
+            }
+        }
+    }
+}
+
+/// Stocks compared with each other: a sector, or a GICS group within one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerGroup {
+    pub level: GicsLevel,
+    pub id: i32,
+    pub name: String,
+    pub sector_id: i32,
+    pub sector_name: String,
+    pub sector_symbol: String,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct PeerGroupRow {
+    id: i32,
+    name: String,
+    sector_id: i32,
+    sector_name: String,
+    sector_symbol: String,
+}
+
+impl PeerGroupRow {
+    fn at(self, level: GicsLevel) -> PeerGroup {
+        PeerGroup {
+            level,
+            id: self.id,
+            name: self.name,
+            sector_id: self.sector_id,
+            sector_name: self.sector_name,
+            sector_symbol: self.sector_symbol,
+        }
+    }
+}
+
+fn peer_groups_sql(level: GicsLevel) -> String {
+    format!(
+        "SELECT g.id, g.name, g.sector_id, sec.name AS sector_name, sec.symbol AS sector_symbol
+         FROM ({groups}) g
+         JOIN sectors sec ON sec.id = g.sector_id",
+        groups = level.groups_sql(),
+    )
+}
+
+/// Every group at `level`, ordered by sector and then by name.
+pub async fn peer_groups(pool: &SqlitePool, level: GicsLevel) -> Result<Vec<PeerGroup>, String> {
+    let sql = format!("{} ORDER BY sec.name, g.name", peer_groups_sql(level));
+    let rows: Vec<PeerGroupRow> = sqlx::query_as(&sql)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to fetch {} groups: {e}", level.as_str()))?;
+
+    Ok(rows.into_iter().map(|row| row.at(level)).collect())
+}
+
+/// The group with `id` at `level`.
+pub async fn peer_group(pool: &SqlitePool, level: GicsLevel, id: i32) -> Result<PeerGroup, String> {
+    let sql = format!("{} WHERE g.id = ?", peer_groups_sql(level));
+    let row: Option<PeerGroupRow> = sqlx::query_as(&sql)
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to fetch {} {id}: {e}", level.as_str()))?;
+
+    row.map(|row| row.at(level))
+        .ok_or_else(|| format!("Unknown {} {id}", level.as_str()))
+}
+
+/// The stock's group at `level`. `None` when it's unclassified there, as
+/// stocks without a known sub-industry are below the sector.
+pub async fn stock_group(
+    pool: &SqlitePool,
+    stock_id: i32,
+    level: GicsLevel,
+) -> Result<Option<PeerGroup>, String> {
+    let sql = format!(
+        "SELECT {column} FROM stock_gics WHERE stock_id = ?",
+        column = level.member_column(),
+    );
+    let id: Option<i32> = sqlx::query_scalar(&sql)
+        .bind(stock_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to fetch stock {stock_id}: {e}"))?
+        .flatten();
+
+    match id {
+        Some(id) => peer_group(pool, level, id).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct GroupSummaryRow {
+    group_id: i32,
+    name: String,
+    sector_id: i32,
+    avg_change_percent: f64,
+    avg_pe_ratio: Option<f64>,
+    total_market_cap: Option<i64>,
+    stock_count: i32,
+    avg_beta: Option<f64>,
+    return_1d: Option<f64>,
+    return_1w: Option<f64>,
+    return_1m: Option<f64>,
+    return_3m: Option<f64>,
+    return_ytd: Option<f64>,
+}
+
+/// Summaries of the groups at `level` that have current members of `universe`,
+/// from each member's latest quote.
+pub async fn group_summaries(
+    pool: &SqlitePool,
+    universe: &str,
+    level: GicsLevel,
+) -> Result<Vec<GroupSummary>, String> {
+    let today = returns::market_today();
+    let sql = format!(
+        "SELECT
+            g.id as group_id,
+            g.name,
+            g.sector_id,
+            COALESCE(AVG(md.price_change_percent), 0.0) as avg_change_percent,
+            AVG(md.pe_ratio) as avg_pe_ratio,
+            SUM(md.market_cap) as total_market_cap,
+            COUNT(DISTINCT s.id) as stock_count,
+            AVG(md.beta) as avg_beta,
+            AVG(md.price_change_percent) as return_1d,
+            AVG({week}) as return_1w,
+            AVG({month}) as return_1m,
+            AVG({quarter}) as return_3m,
+            AVG({ytd}) as return_ytd
+        FROM ({groups}) g
+        JOIN stock_gics sg ON sg.{column} = g.id
+        JOIN stock_universe su ON su.stock_id = sg.stock_id
+            AND su.universe_type = ?
+            AND su.date_removed IS NULL
+        JOIN stocks s ON s.id = sg.stock_id
+        LEFT JOIN market_data md ON md.stock_id = s.id
+            AND md.id = (
+                SELECT md2.id FROM market_data md2
+                WHERE md2.stock_id = s.id
+                ORDER BY md2.timestamp DESC LIMIT 1
+            )
+        GROUP BY g.id
+        ORDER BY g.name",
+        groups = level.groups_sql(),
+        column = level.member_column(),
+        week = returns::return_sql(ReturnHorizon::OneWeek, today),
+        month = returns::return_sql(ReturnHorizon::OneMonth, today),
+        quarter = returns::return_sql(ReturnHorizon::ThreeMonths, today),
+        ytd = returns::return_sql(ReturnHorizon::YearToDate, today),
+    );
+
+    let rows: Vec<GroupSummaryRow> = sqlx::query_as(&sql)
+        .bind(universe)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to query {} summaries: {e}", level.as_str()))?;
+
+    let members = sector_aggregates::fetch_member_quotes(pool, universe, level).await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| {
+            let agg = sector_aggregates::aggregate(
+                members
+                    .get(&r.group_id)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default(),
+            );
+            GroupSummary {
+                level,
+                group_id: r.group_id,
+                name: r.name,
+                sector_id: r.sector_id,
+                avg_change_percent: r.avg_change_percent,
+                avg_pe_ratio: r.avg_pe_ratio,
+                total_market_cap: r.total_market_cap,
+                stock_count: r.stock_count,
+                avg_beta: r.avg_beta,
+                returns: PeriodReturns {
+                    one_day: r.return_1d,
+                    one_week: r.return_1w,
+                    one_month: r.return_1m,
+                    three_months: r.return_3m,
+                    year_to_date: r.return_ytd,
+                },
+                cap_weighted_change_percent: agg.cap_weighted_change_percent,
+                change_quartiles: agg.change_quartiles,
+                cap_weighted_pe_ratio: agg.cap_weighted_pe_ratio,
+                pe_quartiles: agg.pe_quartiles,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_names_match_serde() {
+        for level in [
+            GicsLevel::Sector,
+            GicsLevel::IndustryGroup,
+            GicsLevel::Industry,
+            GicsLevel::SubIndustry,
+        ] {
+            let json = serde_json::to_string(&level).unwrap();
+            assert_eq!(json, format!("\"{}\"", level.as_str()));
+        }
+    }
+
+    #[test]
+    fn test_member_column_per_level() {
+        assert_eq!(GicsLevel::Sector.member_column(), "sector_id");
+        assert_eq!(GicsLevel::SubIndustry.member_column(), "sub_industry_id");
+    }
+}
@@ -0,0 +1,367 @@
+use crate::types::{JobInfo, JobStatus};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+
+/// Finished jobs kept around for `list_jobs` before the oldest are dropped.
+const MAX_FINISHED_JOBS: usize = 20;
+
+/// Control signal sent from the registry to a running job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Signal {
+    Run,
+    Pause,
+    Cancel,
+}
+
+struct JobEntry {
+    info: Mutex<JobInfo>,
+    signal: watch::Sender<Signal>,
+}
+
+impl JobEntry {
+    fn update(&self, f: impl FnOnce(&mut JobInfo)) {
+        if let Ok(mut info) = self.info.lock() {
+            f(&mut info);
+        }
+    }
+
+    fn snapshot(&self) -> Option<JobInfo> {
+        self.info.lock().ok().map(|info| info.clone())
+    }
+}
+
+fn now_timestamp() -> String {
+    chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// Handle a running job uses to report progress and honor pause/cancel requests.
+#[derive(Clone)]
+pub struct JobToken {
+    id: u64,
+    entry: Arc<JobEntry>,
+    signal: watch::Receiver<Signal>,
+}
+
+impl JobToken {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Wait here while the job is paused. Returns `Err` once it has been cancelled,
+    /// so callers can bail out with `?` between units of work.
+    pub async fn checkpoint(&self) -> Result<(), String> {
+        let mut signal = self.signal.clone();
+        loop {
+            let current = *signal.borrow_and_update();
+            match current {
+                Signal::Run => return Ok(()),
+                Signal::Cancel => return Err(format!("Job {} was cancelled", self.id)),
+                Signal::Pause => {
+                    // Sender lives in the registry entry we hold, so this only fails on shutdown
+                    if signal.changed().await.is_err() {
+                        return Err(format!("Job {} was dropped", self.id));
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.signal.borrow() == Signal::Cancel
+    }
+
+    /// Record the job's current phase and progress for `list_jobs`.
+    pub fn report(&self, phase: &str, current: u32, total: u32) {
+        self.entry.update(|info| {
+            if info.phase != phase {
+                info.phase = phase.to_string();
+            }
+            info.current = current;
+            info.total = total;
+        });
+    }
+
+    fn finish(&self, error: Option<&str>) {
+        let cancelled = self.is_cancelled();
+        self.entry.update(|info| {
+            info.finished_at = Some(now_timestamp());
+            info.status = match (cancelled, error) {
+                (true, _) => JobStatus::Cancelled,
+                (false, None) => JobStatus::Completed,
+                (false, Some(e)) => {
+                    info.error = Some(e.to_string());
+                    JobStatus::Failed
+                }
+            };
+        });
+    }
+}
+
+/// Registry of background refresh jobs, managed as Tauri state.
+#[derive(Default)]
+pub struct JobRegistry {
+    next_id: AtomicU64,
+    jobs: Mutex<HashMap<u64, Arc<JobEntry>>>,
+}
+
+impl JobRegistry {
+    /// Register a new running job and return its token.
+    pub fn start(&self, kind: &str) -> JobToken {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let (tx, rx) = watch::channel(Signal::Run);
+        let entry = Arc::new(JobEntry {
+            info: Mutex::new(JobInfo {
+                id,
+                kind: kind.to_string(),
+                status: JobStatus::Running,
+                phase: String::new(),
+                current: 0,
+                total: 0,
+                started_at: now_timestamp(),
+                finished_at: None,
+                error: None,
+            }),
+            signal: tx,
+        });
+
+        if let Ok(mut jobs) = self.jobs.lock() {
+            prune_finished(&mut jobs);
+            jobs.insert(id, entry.clone());
+        }
+
+        JobToken {
+            id,
+            entry,
+            signal: rx,
+        }
+    }
+
+    /// Run `work` as a background task registered under `kind`, and wait for it.
+    ///
+    /// The task is spawned on the async runtime, so it keeps running (and can
+    /// still be paused or cancelled) even if the invoking command goes away.
+    pub async fn run<T, Fut>(
+        &self,
+        kind: &str,
+        work: impl FnOnce(JobToken) -> Fut,
+    ) -> Result<T, String>
+    where
+        T: Send + 'static,
+        Fut: Future<Output = Result<T, String>> + Send + 'static,
+    {
+        let token = self.start(kind);
+        let id = token.id();
+        let fut = work(token.clone());
+
+        tauri::async_runtime::spawn(async move {
+            let result = fut.await;
+            token.finish(result.as_ref().err().map(String::as_str));
+            result
+        })
+        .await
+        .map_err(|e| format!("Job {id} aborted: {e}"))?
+    }
+
+    fn control(&self, id: u64, signal: Signal) -> Result<JobStatus, String> {
+        let entry = self
+            .jobs
+            .lock()
+            .map_err(|_| "Job registry is unavailable".to_string())?
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| format!("No job with id {id}"))?;
+
+        // Check and write under one lock, so a job finishing in between can't
+        // be put back into a live state
+        let mut info = entry
+            .info
+            .lock()
+            .map_err(|_| "Job registry is unavailable".to_string())?;
+        let status = info.status;
+        let next = transition(status, signal)
+            .filter(|_| info.finished_at.is_none())
+            .ok_or_else(|| format!("Job {id} is {status:?} and can't be {}", signal_verb(signal)))?;
+
+        info.status = next;
+        entry.signal.send_replace(signal);
+        Ok(next)
+    }
+
+    pub fn pause(&self, id: u64) -> Result<JobStatus, String> {
+        self.control(id, Signal::Pause)
+    }
+
+    pub fn resume(&self, id: u64) -> Result<JobStatus, String> {
+        self.control(id, Signal::Run)
+    }
+
+    pub fn cancel(&self, id: u64) -> Result<JobStatus, String> {
+        self.control(id, Signal::Cancel)
+    }
+
+    /// True while any job is running or paused.
+    pub fn has_active(&self) -> bool {
+        self.list()
+            .iter()
+            .any(|job| matches!(job.status, JobStatus::Running | JobStatus::Paused))
+    }
+
+    /// All known jobs, newest first.
+    pub fn list(&self) -> Vec<JobInfo> {
+        let mut jobs: Vec<JobInfo> = match self.jobs.lock() {
+            Ok(jobs) => jobs.values().filter_map(|e| e.snapshot()).collect(),
+            Err(_) => Vec::new(),
+        };
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.id));
+        jobs
+    }
+}
+
+/// Status a job moves to when sent `signal`, or `None` if the request makes no sense.
+fn transition(status: JobStatus, signal: Signal) -> Option<JobStatus> {
+    match (status, signal) {
+        (JobStatus::Running, Signal::Pause) => Some(JobStatus::Paused),
+        (JobStatus::Paused, Signal::Run) => Some(JobStatus::Running),
+        (JobStatus::Running | JobStatus::Paused, Signal::Cancel) => Some(JobStatus::Cancelled),
+        _ => None,
+    }
+}
+
+fn signal_verb(signal: Signal) -> &'static str {
+    match signal {
+        Signal::Run => "resumed",
+        Signal::Pause => "paused",
+        Signal::Cancel => "cancelled",
+    }
+}
+
+/// Drop the oldest finished jobs beyond `MAX_FINISHED_JOBS`.
+fn prune_finished(jobs: &mut HashMap<u64, Arc<JobEntry>>) {
+    let mut finished: Vec<u64> = jobs
+        .iter()
+        .filter(|(_, e)| e.snapshot().is_some_and(|info| info.finished_at.is_some()))
+        .map(|(id, _)| *id)
+        .collect();
+    if finished.len() <= MAX_FINISHED_JOBS {
+        return;
+    }
+    finished.sort_unstable();
+    for id in &finished[..finished.len() - MAX_FINISHED_JOBS] {
+        jobs.remove(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ---- transition ----
+
+    #[test]
+    fn test_transition_pause_and_resume() {
+        assert_eq!(transition(JobStatus::Running, Signal::Pause), Some(JobStatus::Paused));
+        assert_eq!(transition(JobStatus::Paused, Signal::Run), Some(JobStatus::Running));
+    }
+
+    #[test]
+    fn test_transition_cancel_from_running_or_paused() {
+        assert_eq!(transition(JobStatus::Running, Signal::Cancel), Some(JobStatus::Cancelled));
+        assert_eq!(transition(JobStatus::Paused, Signal::Cancel), Some(JobStatus::Cancelled));
+    }
+
+    #[test]
+    fn test_transition_rejects_finished_jobs() {
+        for status in [JobStatus::Completed, JobStatus::Cancelled, JobStatus::Failed] {
+            assert_eq!(transition(status, Signal::Pause), None);
+            assert_eq!(transition(status, Signal::Run), None);
+            assert_eq!(transition(status, Signal::Cancel), None);
+        }
+    }
+
+    #[test]
+    fn test_transition_rejects_redundant_requests() {
+        assert_eq!(transition(JobStatus::Running, Signal::Run), None);
+        assert_eq!(transition(JobStatus::Paused, Signal::Pause), None);
+    }
+
+    // ---- JobRegistry ----
+
+    #[test]
+    fn test_registry_assigns_increasing_ids_and_lists_newest_first() {
+        let registry = JobRegistry::default();
+        let a = registry.start("market-data");
+        let b = registry.start("history");
+        assert!(b.id() > a.id());
+        let ids: Vec<u64> = registry.list().iter().map(|j| j.id).collect();
+        assert_eq!(ids, vec![b.id(), a.id()]);
+    }
+
+    #[test]
+    fn test_registry_cancel_marks_token_and_status() {
+        let registry = JobRegistry::default();
+        let token = registry.start("russell2000");
+        assert!(!token.is_cancelled());
+        assert_eq!(registry.cancel(token.id()), Ok(JobStatus::Cancelled));
+        assert!(token.is_cancelled());
+        token.finish(None);
+        assert_eq!(registry.list()[0].status, JobStatus::Cancelled);
+        assert!(registry.cancel(token.id()).is_err());
+    }
+
+    #[test]
+    fn test_has_active_until_finished() {
+        let registry = JobRegistry::default();
+        assert!(!registry.has_active());
+        let token = registry.start("scheduled");
+        assert!(registry.has_active());
+        token.finish(None);
+        assert!(!registry.has_active());
+    }
+
+    #[test]
+    fn test_control_after_finish_keeps_final_status() {
+        let registry = JobRegistry::default();
+        let token = registry.start("market-data");
+        token.finish(None);
+        assert!(registry.pause(token.id()).is_err());
+        assert!(registry.cancel(token.id()).is_err());
+        assert_eq!(registry.list()[0].status, JobStatus::Completed);
+        assert!(!token.is_cancelled());
+        assert!(!registry.has_active());
+    }
+
+    #[test]
+    fn test_registry_unknown_job_is_an_error() {
+        let registry = JobRegistry::default();
+        assert!(registry.pause(42).is_err());
+    }
+
+    #[test]
+    fn test_finish_records_failure() {
+        let registry = JobRegistry::default();
+        let token = registry.start("history");
+        token.finish(Some("network down"));
+        let info = &registry.list()[0];
+        assert_eq!(info.status, JobStatus::Failed);
+        assert_eq!(info.error.as_deref(), Some("network down"));
+        assert!(info.finished_at.is_some());
+    }
+
+    #[test]
+    fn test_prune_keeps_recent_finished_and_all_running() {
+        let registry = JobRegistry::default();
+        let running = registry.start("market-data");
+        for _ in 0..MAX_FINISHED_JOBS + 5 {
+            registry.start("sector").finish(None);
+        }
+        let survivor = registry.start("history");
+        let jobs = registry.list();
+        assert!(jobs.iter().any(|j| j.id == running.id()));
+        assert!(jobs.iter().any(|j| j.id == survivor.id()));
+        let finished = jobs.iter().filter(|j| j.finished_at.is_some()).count();
+        assert_eq!(finished, MAX_FINISHED_JOBS);
+    }
+}
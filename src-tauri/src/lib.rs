@@ -1,13 +1,36 @@
+mod alpha_vantage;
+mod backtest;
+mod benchmarks;
+mod breadth;
 mod cache;
 mod commands;
 mod database;
+mod detection_runs;
+mod gics;
+mod jobs;
 mod market_data;
+mod market_hours;
 mod outlier_detection;
+mod outlier_history;
+mod price_history;
+mod provider;
+mod refresh_engine;
+mod returns;
+mod rotation;
 mod russell_discovery;
+mod scheduler;
+mod sector_aggregates;
+mod settings;
+mod stats;
+mod stock_detail;
 mod stock_discovery;
+mod throttle;
 mod types;
+mod universes;
 
 use cache::SectorCache;
+use jobs::JobRegistry;
+use scheduler::SchedulerState;
 use sqlx::sqlite::SqlitePool;
 use tauri::Manager;
 
@@ -19,23 +42,58 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .invoke_handler(tauri::generate_handler![
             commands::get_sectors,
+            commands::get_universes,
+            commands::update_universe,
+            commands::create_watchlist,
+            commands::rename_watchlist,
+            commands::delete_watchlist,
+            commands::add_watchlist_symbol,
+            commands::remove_watchlist_symbol,
             commands::get_stocks_by_sector,
             commands::get_sector_performance,
+            commands::get_group_performance,
+            commands::get_market_breadth,
+            commands::get_sector_rotation,
             commands::refresh_market_data,
             commands::refresh_sector_data,
             commands::detect_outliers,
+            commands::replay_outliers,
             commands::get_sector_outliers,
-            commands::refresh_russell_2000_data,
+            commands::get_outlier_episodes,
+            commands::get_outlier_backtest,
+            commands::set_market_data_provider,
+            commands::backfill_price_history,
+            commands::get_stock_history,
+            commands::get_stock_detections,
+            commands::get_stock_z_scores,
+            commands::get_stock_memberships,
+            commands::cancel_refresh,
+            commands::pause_refresh,
+            commands::resume_refresh,
+            commands::list_jobs,
+            commands::get_scheduler_status,
+            commands::set_scheduler,
+            commands::get_settings,
+            commands::update_settings,
         ])
         .setup(|app| {
             let handle = app.handle().clone();
 
             // Initialize cache
             handle.manage(SectorCache::new());
+            handle.manage(JobRegistry::default());
+            handle.manage(SchedulerState::default());
 
             tauri::async_runtime::block_on(async move {
                 match database::init_database(&handle).await {
                     Ok(pool) => {
+                        match settings::load_app_settings(&pool).await {
+                            Ok(config) => handle
+                                .state::<SectorCache>()
+                                .set_ttl_minutes(config.cache_ttl_minutes),
+                            Err(e) => eprintln!("Failed to load settings, using defaults: {e}"),
+                        }
+                        scheduler::start(handle.clone(), pool.clone());
                         handle.manage(DbState(pool));
                         println!("Database initialized successfully");
                     }
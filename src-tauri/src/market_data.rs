@@ -1,3 +1,7 @@
+use crate::provider::MarketDataProvider;
+use crate::throttle::{RetryPolicy, Throttle};
+use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveTime};
 use reqwest::Client;
 use serde::Deserialize;
 use sqlx::sqlite::SqlitePool;
@@ -16,6 +20,9 @@ struct ChartResult {
 #[derive(Debug, Deserialize)]
 struct ChartData {
     meta: ChartMeta,
+    /// Bar open times (UNIX seconds); only present for multi-bar history requests.
+    timestamp: Option<Vec<i64>>,
+    indicators: Option<ChartIndicators>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -24,6 +31,29 @@ struct ChartMeta {
     regular_market_price: Option<f64>,
     chart_previous_close: Option<f64>,
     regular_market_volume: Option<i64>,
+    /// Exchange offset from UTC in seconds, used to turn bar timestamps into trading dates.
+    gmtoffset: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChartIndicators {
+    quote: Option<Vec<ChartQuoteSeries>>,
+    adjclose: Option<Vec<ChartAdjCloseSeries>>,
+}
+
+/// Per-bar OHLCV arrays, parallel to `ChartData::timestamp`. Yahoo uses nulls for missing bars.
+#[derive(Debug, Deserialize)]
+struct ChartQuoteSeries {
+    open: Option<Vec<Option<f64>>>,
+    high: Option<Vec<Option<f64>>>,
+    low: Option<Vec<Option<f64>>>,
+    close: Option<Vec<Option<f64>>>,
+    volume: Option<Vec<Option<i64>>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChartAdjCloseSeries {
+    adjclose: Option<Vec<Option<f64>>>,
 }
 
 /// Response structures for Yahoo Finance quoteSummary API (v10)
@@ -78,6 +108,39 @@ struct AssetProfile {
     sector: Option<String>,
 }
 
+/// Response structures for Yahoo Finance multi-symbol quote API (v7)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QuoteResponse {
+    quote_response: Option<QuoteResponseResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct QuoteResponseResult {
+    result: Option<Vec<QuoteData>>,
+}
+
+/// One symbol from a v7 quote response. Values are plain numbers (`formatted=false`).
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QuoteData {
+    symbol: String,
+    regular_market_price: Option<f64>,
+    regular_market_previous_close: Option<f64>,
+    regular_market_volume: Option<f64>,
+    #[serde(rename = "averageDailyVolume10Day")]
+    average_daily_volume_10_day: Option<f64>,
+    market_cap: Option<f64>,
+    #[serde(rename = "trailingPE")]
+    trailing_pe: Option<f64>,
+    price_to_book: Option<f64>,
+    eps_trailing_twelve_months: Option<f64>,
+    /// Forward yield, like quoteSummary's `dividendYield`, but as a percent.
+    dividend_yield: Option<f64>,
+    fifty_two_week_high: Option<f64>,
+    fifty_two_week_low: Option<f64>,
+}
+
 /// Yahoo Finance wraps many values in {"raw": 123.45, "fmt": "123.45"}
 #[derive(Debug, Deserialize)]
 struct YahooValue {
@@ -86,6 +149,13 @@ struct YahooValue {
 
 const USER_AGENT: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
 
+/// Default sustained Yahoo request rate shared by all concurrent fetches in a session.
+pub const YAHOO_REQUESTS_PER_SEC: f64 = 10.0;
+/// Requests allowed back-to-back before the rate limit kicks in.
+const YAHOO_BURST: u32 = 20;
+/// Symbols per multi-symbol quote request. Yahoo accepts more, but long URLs start failing.
+const YAHOO_BATCH_SIZE: usize = 50;
+
 /// Build the Yahoo Finance chart API URL for a given symbol.
 fn build_chart_url(symbol: &str) -> String {
     format!(
@@ -94,6 +164,15 @@ fn build_chart_url(symbol: &str) -> String {
     )
 }
 
+/// Build the Yahoo Finance chart API URL for daily bars between two UNIX timestamps.
+/// `period2` is exclusive.
+fn build_history_url(symbol: &str, period1: i64, period2: i64) -> String {
+    format!(
+        "https://query1.finance.yahoo.com/v8/finance/chart/{}?period1={}&period2={}&interval=1d&events=div%2Csplit",
+        symbol, period1, period2
+    )
+}
+
 /// Build the Yahoo Finance quoteSummary API URL for a given symbol and crumb.
 fn build_fundamentals_url(symbol: &str, crumb: &str) -> String {
     format!(
@@ -102,8 +181,17 @@ fn build_fundamentals_url(symbol: &str, crumb: &str) -> String {
     )
 }
 
+/// Build the Yahoo Finance multi-symbol quote API URL for a batch of symbols and a crumb.
+fn build_batch_quote_url(symbols: &[String], crumb: &str) -> String {
+    format!(
+        "https://query1.finance.yahoo.com/v7/finance/quote?symbols={}&formatted=false&crumb={}",
+        symbols.join(","),
+        crumb
+    )
+}
+
 /// Calculate price change and percent change from current price and previous close.
-fn calculate_price_change(price: f64, prev_close: f64) -> (f64, f64) {
+pub fn calculate_price_change(price: f64, prev_close: f64) -> (f64, f64) {
     let change = price - prev_close;
     let percent = if prev_close != 0.0 {
         (change / prev_close) * 100.0
@@ -114,15 +202,17 @@ fn calculate_price_change(price: f64, prev_close: f64) -> (f64, f64) {
 }
 
 /// Authenticated Yahoo Finance session with cookie jar + crumb.
-/// Created once per refresh cycle and reused for all quoteSummary calls.
+/// Created once per refresh cycle and reused for all chart and quoteSummary calls.
 pub struct YahooSession {
     client: Client,
     crumb: String,
+    throttle: Throttle,
 }
 
 impl YahooSession {
     /// Establish a Yahoo Finance session by fetching a cookie + crumb pair.
-    pub async fn new() -> Result<Self, String> {
+    /// All requests made through the session share `requests_per_sec`.
+    pub async fn new(requests_per_sec: f64) -> Result<Self, String> {
         let client = Client::builder()
             .cookie_store(true)
             .user_agent(USER_AGENT)
@@ -150,10 +240,59 @@ impl YahooSession {
             return Err(format!("Yahoo crumb fetch rejected: {crumb}"));
         }
 
-        Ok(Self { client, crumb })
+        Ok(Self {
+            client,
+            crumb,
+            throttle: Throttle::new(requests_per_sec, YAHOO_BURST, RetryPolicy::default()),
+        })
     }
 }
 
+/// Latest price snapshot for a symbol, as returned by a provider's quote call.
+#[derive(Debug, Clone)]
+pub struct PriceQuote {
+    pub price: f64,
+    pub prev_close: f64,
+    pub volume: Option<i64>,
+}
+
+/// Valuation, risk and classification data for a symbol.
+/// Every field is optional: providers leave out whatever they don't report.
+#[derive(Debug, Clone, Default)]
+pub struct Fundamentals {
+    pub pe_ratio: Option<f64>,
+    pub pb_ratio: Option<f64>,
+    pub market_cap: Option<i64>,
+    pub eps: Option<f64>,
+    pub dividend_yield: Option<f64>,
+    pub beta: Option<f64>,
+    pub avg_volume_10d: Option<i64>,
+    pub week52_high: Option<f64>,
+    pub week52_low: Option<f64>,
+    /// Sector label in Yahoo Finance vocabulary (e.g. "Healthcare").
+    pub sector: Option<String>,
+}
+
+/// One daily OHLCV bar. `adj_close` is adjusted for splits and dividends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyBar {
+    pub date: NaiveDate,
+    pub open: Option<f64>,
+    pub high: Option<f64>,
+    pub low: Option<f64>,
+    pub close: f64,
+    pub adj_close: f64,
+    pub volume: Option<i64>,
+}
+
+/// Price and fundamentals for one symbol from a multi-symbol quote request.
+#[derive(Debug, Clone)]
+pub struct BatchQuote {
+    pub symbol: String,
+    pub quote: PriceQuote,
+    pub fundamentals: Fundamentals,
+}
+
 /// Combined stock quote with all metrics
 #[derive(Debug)]
 pub struct StockQuote {
@@ -178,15 +317,14 @@ pub struct StockQuote {
 
 /// Fetch price data from Yahoo Finance chart API.
 async fn fetch_chart_data(
-    client: &Client,
+    session: &YahooSession,
     symbol: &str,
 ) -> Result<(f64, f64, Option<i64>), String> {
     let url = build_chart_url(symbol);
 
-    let resp = client
-        .get(&url)
-        .header("User-Agent", USER_AGENT)
-        .send()
+    let resp = session
+        .throttle
+        .send(session.client.get(&url))
         .await
         .map_err(|e| format!("Network error fetching {symbol}: {e}"))?;
 
@@ -218,37 +356,172 @@ async fn fetch_chart_data(
     Ok((price, prev_close, volume))
 }
 
-/// Fetch fundamental data from Yahoo Finance quoteSummary API.
-/// Returns (pe_ratio, pb_ratio, market_cap, eps, dividend_yield, beta, avg_volume_10d, week52_high, week52_low, sector).
-async fn fetch_fundamentals(
+/// Convert a chart API result into daily bars.
+/// Bars without a close (Yahoo emits nulls for halted or partial days) are dropped.
+fn parse_daily_bars(data: ChartData) -> Vec<DailyBar> {
+    let offset = data.meta.gmtoffset.unwrap_or(0);
+    let timestamps = data.timestamp.unwrap_or_default();
+    let indicators = match data.indicators {
+        Some(i) => i,
+        None => return Vec::new(),
+    };
+    let quote = match indicators.quote.and_then(|q| q.into_iter().next()) {
+        Some(q) => q,
+        None => return Vec::new(),
+    };
+    let adjclose = indicators
+        .adjclose
+        .and_then(|a| a.into_iter().next())
+        .and_then(|a| a.adjclose)
+        .unwrap_or_default();
+
+    let at = |series: &Option<Vec<Option<f64>>>, i: usize| {
+        series.as_ref().and_then(|s| s.get(i).copied().flatten())
+    };
+
+    let mut bars = Vec::with_capacity(timestamps.len());
+    for (i, ts) in timestamps.iter().enumerate() {
+        let close = match at(&quote.close, i) {
+            Some(c) => c,
+            None => continue,
+        };
+        let date = match chrono::DateTime::from_timestamp(ts + offset, 0) {
+            Some(dt) => dt.date_naive(),
+            None => continue,
+        };
+        bars.push(DailyBar {
+            date,
+            open: at(&quote.open, i),
+            high: at(&quote.high, i),
+            low: at(&quote.low, i),
+            close,
+            adj_close: adjclose.get(i).copied().flatten().unwrap_or(close),
+            volume: quote
+                .volume
+                .as_ref()
+                .and_then(|v| v.get(i).copied().flatten()),
+        });
+    }
+
+    bars
+}
+
+/// Fetch daily bars for `start..=end` from the Yahoo Finance chart API.
+async fn fetch_daily_bars(
     session: &YahooSession,
     symbol: &str,
-) -> (
-    Option<f64>,
-    Option<f64>,
-    Option<i64>,
-    Option<f64>,
-    Option<f64>,
-    Option<f64>,
-    Option<i64>,
-    Option<f64>,
-    Option<f64>,
-    Option<String>,
-) {
-    let url = build_fundamentals_url(symbol, &session.crumb);
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<Vec<DailyBar>, String> {
+    let period1 = start.and_time(NaiveTime::MIN).and_utc().timestamp();
+    let period2 = (end + chrono::Duration::days(1))
+        .and_time(NaiveTime::MIN)
+        .and_utc()
+        .timestamp();
+    let url = build_history_url(symbol, period1, period2);
+
+    let resp = session
+        .throttle
+        .send(session.client.get(&url))
+        .await
+        .map_err(|e| format!("Network error fetching history for {symbol}: {e}"))?;
+
+    if !resp.status().is_success() {
+        return Err(format!(
+            "Yahoo chart API returned {} for {symbol} history",
+            resp.status()
+        ));
+    }
+
+    let data: ChartResponse = resp
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse history for {symbol}: {e}"))?;
+
+    let result = data
+        .chart
+        .result
+        .and_then(|r| r.into_iter().next())
+        .ok_or_else(|| format!("No history for {symbol}"))?;
+
+    Ok(parse_daily_bars(result))
+}
+
+/// Convert a v7 quote response into batch quotes. Symbols without a price are dropped
+/// so the caller falls back to the per-symbol path for them.
+fn parse_batch_quotes(data: QuoteResponse) -> Vec<BatchQuote> {
+    let results = data
+        .quote_response
+        .and_then(|qr| qr.result)
+        .unwrap_or_default();
+
+    results
+        .into_iter()
+        .filter_map(|q| {
+            let price = q.regular_market_price?;
+            Some(BatchQuote {
+                quote: PriceQuote {
+                    price,
+                    prev_close: q.regular_market_previous_close.unwrap_or(price),
+                    volume: q.regular_market_volume.map(|v| v as i64),
+                },
+                fundamentals: Fundamentals {
+                    pe_ratio: q.trailing_pe,
+                    pb_ratio: q.price_to_book,
+                    market_cap: q.market_cap.map(|v| v as i64),
+                    eps: q.eps_trailing_twelve_months,
+                    dividend_yield: q.dividend_yield.map(|y| y / 100.0),
+                    // v7 quotes don't report beta
+                    beta: None,
+                    avg_volume_10d: q.average_daily_volume_10_day.map(|v| v as i64),
+                    week52_high: q.fifty_two_week_high,
+                    week52_low: q.fifty_two_week_low,
+                    sector: None,
+                },
+                symbol: q.symbol,
+            })
+        })
+        .collect()
+}
+
+/// Fetch one batch of symbols from the Yahoo Finance multi-symbol quote API.
+async fn fetch_batch_quotes(
+    session: &YahooSession,
+    symbols: &[String],
+) -> Result<Vec<BatchQuote>, String> {
+    let url = build_batch_quote_url(symbols, &session.crumb);
 
-    let resp = match session.client
-        .get(&url)
-        .send()
+    let resp = session
+        .throttle
+        .send(session.client.get(&url))
         .await
-    {
+        .map_err(|e| format!("Network error fetching quote batch: {e}"))?;
+
+    if !resp.status().is_success() {
+        return Err(format!("Yahoo quote API returned {}", resp.status()));
+    }
+
+    let data: QuoteResponse = resp
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse quote batch: {e}"))?;
+
+    Ok(parse_batch_quotes(data))
+}
+
+/// Fetch fundamental data from Yahoo Finance quoteSummary API.
+/// Any field Yahoo doesn't report (or a failed request) comes back as `None`.
+async fn fetch_fundamentals(session: &YahooSession, symbol: &str) -> Fundamentals {
+    let url = build_fundamentals_url(symbol, &session.crumb);
+
+    let resp = match session.throttle.send(session.client.get(&url)).await {
         Ok(r) if r.status().is_success() => r,
-        _ => return (None, None, None, None, None, None, None, None, None, None),
+        _ => return Fundamentals::default(),
     };
 
     let data: QuoteSummaryResponse = match resp.json().await {
         Ok(d) => d,
-        Err(_) => return (None, None, None, None, None, None, None, None, None, None),
+        Err(_) => return Fundamentals::default(),
     };
 
     let result = match data
@@ -257,7 +530,7 @@ async fn fetch_fundamentals(
         .and_then(|r| r.into_iter().next())
     {
         Some(r) => r,
-        None => return (None, None, None, None, None, None, None, None, None, None),
+        None => return Fundamentals::default(),
     };
 
     let pe_ratio = result
@@ -320,7 +593,7 @@ async fn fetch_fundamentals(
         .as_ref()
         .and_then(|ap| ap.sector.clone());
 
-    (
+    Fundamentals {
         pe_ratio,
         pb_ratio,
         market_cap,
@@ -331,40 +604,99 @@ async fn fetch_fundamentals(
         week52_high,
         week52_low,
         sector,
-    )
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for YahooSession {
+    fn name(&self) -> &'static str {
+        "yahoo"
+    }
+
+    async fn fetch_quote(&self, symbol: &str) -> Result<PriceQuote, String> {
+        let (price, prev_close, volume) = fetch_chart_data(self, symbol).await?;
+        Ok(PriceQuote {
+            price,
+            prev_close,
+            volume,
+        })
+    }
+
+    async fn fetch_fundamentals(&self, symbol: &str) -> Fundamentals {
+        fetch_fundamentals(self, symbol).await
+    }
+
+    async fn fetch_quote_batch(&self, symbols: &[String]) -> Vec<BatchQuote> {
+        let mut quotes = Vec::with_capacity(symbols.len());
+        for chunk in symbols.chunks(YAHOO_BATCH_SIZE) {
+            match fetch_batch_quotes(self, chunk).await {
+                Ok(batch) => quotes.extend(batch),
+                Err(e) => eprintln!("Quote batch of {} failed, falling back: {e}", chunk.len()),
+            }
+        }
+        quotes
+    }
+
+    async fn fetch_daily_bars(
+        &self,
+        symbol: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<DailyBar>, String> {
+        fetch_daily_bars(self, symbol, start, end).await
+    }
 }
 
-/// Fetch quote for a single stock, combining chart + fundamentals.
+/// Fetch quote for a single stock, combining price + fundamentals from the given provider.
 pub async fn fetch_stock_quote(
-    client: &Client,
-    session: &YahooSession,
+    provider: &dyn MarketDataProvider,
     stock_id: i32,
     symbol: &str,
 ) -> Result<StockQuote, String> {
-    let (price, prev_close, volume) = fetch_chart_data(client, symbol).await?;
+    let quote = provider.fetch_quote(symbol).await?;
+    let fundamentals = provider.fetch_fundamentals(symbol).await;
+    Ok(build_stock_quote(stock_id, quote, fundamentals))
+}
 
+/// Combine a price snapshot and fundamentals into a `StockQuote` for saving.
+pub fn build_stock_quote(
+    stock_id: i32,
+    quote: PriceQuote,
+    fundamentals: Fundamentals,
+) -> StockQuote {
+    let PriceQuote { price, prev_close, volume } = quote;
     let (price_change, price_change_percent) = calculate_price_change(price, prev_close);
 
-    let (pe_ratio, pb_ratio, market_cap, eps, dividend_yield, beta, avg_volume_10d, week52_high, week52_low, yahoo_sector) =
-        fetch_fundamentals(session, symbol).await;
-
-    Ok(StockQuote {
+    StockQuote {
         stock_id,
         price,
         price_change,
         price_change_percent,
         volume,
-        avg_volume_10d,
-        market_cap,
-        pe_ratio,
-        pb_ratio,
-        eps,
-        dividend_yield,
-        beta,
-        week52_high,
-        week52_low,
-        yahoo_sector,
-    })
+        avg_volume_10d: fundamentals.avg_volume_10d,
+        market_cap: fundamentals.market_cap,
+        pe_ratio: fundamentals.pe_ratio,
+        pb_ratio: fundamentals.pb_ratio,
+        eps: fundamentals.eps,
+        dividend_yield: fundamentals.dividend_yield,
+        beta: fundamentals.beta,
+        week52_high: fundamentals.week52_high,
+        week52_low: fundamentals.week52_low,
+        yahoo_sector: fundamentals.sector,
+    }
+}
+
+/// Most recent beta saved for a stock, if any quote carried one.
+pub async fn stored_beta(pool: &SqlitePool, stock_id: i32) -> Result<Option<f64>, String> {
+    sqlx::query_scalar(
+        "SELECT beta FROM market_data
+         WHERE stock_id = ? AND beta IS NOT NULL
+         ORDER BY timestamp DESC, id DESC LIMIT 1",
+    )
+    .bind(stock_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch stored beta: {e}"))
 }
 
 /// Save a stock quote to the market_data table.
@@ -471,6 +803,26 @@ mod tests {
         assert_ne!(url_msft, url_goog);
     }
 
+    #[test]
+    fn test_build_history_url_contains_period_and_interval() {
+        let url = build_history_url("AAPL", 1_600_000_000, 1_700_000_000);
+        assert!(url.contains("/chart/AAPL?"), "Missing symbol: {url}");
+        assert!(url.contains("period1=1600000000"), "Missing period1: {url}");
+        assert!(url.contains("period2=1700000000"), "Missing period2: {url}");
+        assert!(url.contains("interval=1d"), "Missing interval: {url}");
+        assert!(!url.contains("range="), "History URL must not use range: {url}");
+    }
+
+    #[test]
+    fn test_build_batch_quote_url_joins_symbols() {
+        let symbols = vec!["AAPL".to_string(), "BRK.B".to_string(), "MSFT".to_string()];
+        let url = build_batch_quote_url(&symbols, "my-crumb");
+        assert!(url.starts_with("https://query1.finance.yahoo.com/v7/finance/quote?"));
+        assert!(url.contains("symbols=AAPL,BRK.B,MSFT"), "Missing symbols: {url}");
+        assert!(url.contains("crumb=my-crumb"), "Missing crumb: {url}");
+        assert!(url.contains("formatted=false"), "Missing formatted param: {url}");
+    }
+
     // ---- Price change calculation ----
 
     #[test]
@@ -599,6 +951,161 @@ mod tests {
         assert!(meta.regular_market_volume.is_none());
     }
 
+    // ---- JSON parsing: daily bars ----
+
+    fn parse_history_json(json: &str) -> Vec<DailyBar> {
+        let parsed: ChartResponse = serde_json::from_str(json).unwrap();
+        let data = parsed.chart.result.unwrap().into_iter().next().unwrap();
+        parse_daily_bars(data)
+    }
+
+    #[test]
+    fn test_parse_daily_bars_full_response() {
+        // 2024-01-02 and 2024-01-03 at 09:30 New York (14:30 UTC), gmtoffset -18000
+        let json = r#"{
+            "chart": {"result": [{
+                "meta": {"regularMarketPrice": 185.0, "gmtoffset": -18000},
+                "timestamp": [1704205800, 1704292200],
+                "indicators": {
+                    "quote": [{
+                        "open": [187.15, 184.22],
+                        "high": [188.44, 185.88],
+                        "low": [183.89, 183.43],
+                        "close": [185.64, 184.25],
+                        "volume": [82488700, 58414500]
+                    }],
+                    "adjclose": [{"adjclose": [184.94, 183.55]}]
+                }
+            }]}
+        }"#;
+        let bars = parse_history_json(json);
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].date, NaiveDate::from_ymd_opt(2024, 1, 2).unwrap());
+        assert_eq!(bars[1].date, NaiveDate::from_ymd_opt(2024, 1, 3).unwrap());
+        assert!(approx_eq(bars[0].open.unwrap(), 187.15));
+        assert!(approx_eq(bars[0].close, 185.64));
+        assert!(approx_eq(bars[0].adj_close, 184.94));
+        assert_eq!(bars[1].volume, Some(58_414_500));
+    }
+
+    #[test]
+    fn test_parse_daily_bars_skips_null_close() {
+        let json = r#"{
+            "chart": {"result": [{
+                "meta": {"gmtoffset": -18000},
+                "timestamp": [1704205800, 1704292200],
+                "indicators": {
+                    "quote": [{
+                        "open": [null, 184.22],
+                        "high": [null, 185.88],
+                        "low": [null, 183.43],
+                        "close": [null, 184.25],
+                        "volume": [null, 58414500]
+                    }]
+                }
+            }]}
+        }"#;
+        let bars = parse_history_json(json);
+        assert_eq!(bars.len(), 1);
+        assert_eq!(bars[0].date, NaiveDate::from_ymd_opt(2024, 1, 3).unwrap());
+    }
+
+    #[test]
+    fn test_parse_daily_bars_missing_adjclose_falls_back_to_close() {
+        let json = r#"{
+            "chart": {"result": [{
+                "meta": {},
+                "timestamp": [1704205800],
+                "indicators": {"quote": [{"close": [185.64]}]}
+            }]}
+        }"#;
+        let bars = parse_history_json(json);
+        assert_eq!(bars.len(), 1);
+        assert!(approx_eq(bars[0].adj_close, 185.64));
+        assert!(bars[0].open.is_none());
+        assert!(bars[0].volume.is_none());
+    }
+
+    #[test]
+    fn test_parse_daily_bars_no_indicators_returns_empty() {
+        let json = r#"{"chart": {"result": [{"meta": {"regularMarketPrice": 1.0}}]}}"#;
+        assert!(parse_history_json(json).is_empty());
+    }
+
+    // ---- JSON parsing: QuoteResponse (batch) ----
+
+    #[test]
+    fn test_batch_quote_json_full_response() {
+        let json = r#"{
+            "quoteResponse": {
+                "result": [{
+                    "symbol": "AAPL",
+                    "regularMarketPrice": 189.5,
+                    "regularMarketPreviousClose": 187.0,
+                    "regularMarketVolume": 51234567,
+                    "averageDailyVolume10Day": 48000000,
+                    "marketCap": 2950000000000,
+                    "trailingPE": 29.4,
+                    "priceToBook": 47.1,
+                    "epsTrailingTwelveMonths": 6.44,
+                    "trailingAnnualDividendYield": 0.0049,
+                    "dividendYield": 0.51,
+                    "fiftyTwoWeekHigh": 199.62,
+                    "fiftyTwoWeekLow": 164.08
+                }],
+                "error": null
+            }
+        }"#;
+        let parsed: QuoteResponse = serde_json::from_str(json).unwrap();
+        let quotes = parse_batch_quotes(parsed);
+        assert_eq!(quotes.len(), 1);
+        let q = &quotes[0];
+        assert_eq!(q.symbol, "AAPL");
+        assert!(approx_eq(q.quote.price, 189.5));
+        assert!(approx_eq(q.quote.prev_close, 187.0));
+        assert_eq!(q.quote.volume, Some(51_234_567));
+        assert_eq!(q.fundamentals.market_cap, Some(2_950_000_000_000));
+        assert_eq!(q.fundamentals.avg_volume_10d, Some(48_000_000));
+        assert!(approx_eq(q.fundamentals.pe_ratio.unwrap(), 29.4));
+        assert!(approx_eq(q.fundamentals.eps.unwrap(), 6.44));
+        assert!(approx_eq(q.fundamentals.dividend_yield.unwrap(), 0.0051));
+        assert!(approx_eq(q.fundamentals.week52_low.unwrap(), 164.08));
+        assert!(q.fundamentals.beta.is_none());
+        assert!(q.fundamentals.sector.is_none());
+    }
+
+    #[test]
+    fn test_batch_quote_json_drops_symbols_without_price() {
+        let json = r#"{
+            "quoteResponse": {
+                "result": [
+                    {"symbol": "AAPL", "regularMarketPrice": 189.5},
+                    {"symbol": "DELISTED"}
+                ]
+            }
+        }"#;
+        let parsed: QuoteResponse = serde_json::from_str(json).unwrap();
+        let quotes = parse_batch_quotes(parsed);
+        assert_eq!(quotes.len(), 1);
+        assert_eq!(quotes[0].symbol, "AAPL");
+        // Missing previous close falls back to price, like the chart path
+        assert!(approx_eq(quotes[0].quote.prev_close, 189.5));
+    }
+
+    #[test]
+    fn test_batch_quote_json_null_result() {
+        let json = r#"{"quoteResponse": {"result": null, "error": null}}"#;
+        let parsed: QuoteResponse = serde_json::from_str(json).unwrap();
+        assert!(parse_batch_quotes(parsed).is_empty());
+    }
+
+    #[test]
+    fn test_batch_quote_json_missing_quote_response() {
+        let json = r#"{"finance": {"error": {"code": "Unauthorized"}}}"#;
+        let parsed: QuoteResponse = serde_json::from_str(json).unwrap();
+        assert!(parse_batch_quotes(parsed).is_empty());
+    }
+
     // ---- JSON parsing: QuoteSummaryResponse ----
 
     #[test]
@@ -0,0 +1,344 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc, Weekday};
+
+/// Unscheduled full-day closures the rule-based calendar can't know about.
+const SPECIAL_CLOSURES: [(i32, u32, u32); 4] = [
+    (2012, 10, 29), // Hurricane Sandy
+    (2012, 10, 30), // Hurricane Sandy
+    (2018, 12, 5),  // National day of mourning, George H.W. Bush
+    (2025, 1, 9),   // National day of mourning, Jimmy Carter
+];
+
+fn ymd(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).expect("valid calendar date")
+}
+
+fn regular_open() -> NaiveTime {
+    NaiveTime::from_hms_opt(9, 30, 0).expect("valid time")
+}
+
+fn regular_close() -> NaiveTime {
+    NaiveTime::from_hms_opt(16, 0, 0).expect("valid time")
+}
+
+fn early_close() -> NaiveTime {
+    NaiveTime::from_hms_opt(13, 0, 0).expect("valid time")
+}
+
+/// The `n`th (1-based) `weekday` of a month.
+fn nth_weekday(year: i32, month: u32, weekday: Weekday, n: u32) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, n as u8)
+        .expect("valid nth weekday")
+}
+
+fn last_weekday(year: i32, month: u32, weekday: Weekday) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, 5)
+        .unwrap_or_else(|| nth_weekday(year, month, weekday, 4))
+}
+
+/// Western (Gregorian) Easter Sunday, via the anonymous Gregorian algorithm.
+fn easter_sunday(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    ymd(year, month as u32, day as u32)
+}
+
+/// Weekend holidays move to Friday (Saturday) or Monday (Sunday).
+fn observed(date: NaiveDate) -> NaiveDate {
+    match date.weekday() {
+        Weekday::Sat => date - Duration::days(1),
+        Weekday::Sun => date + Duration::days(1),
+        _ => date,
+    }
+}
+
+/// NYSE full-day holidays for a year, as observed.
+fn holidays(year: i32) -> Vec<NaiveDate> {
+    let mut days = Vec::with_capacity(10);
+
+    // New Year's Day: a Saturday holiday is not moved back into the prior year
+    let new_year = ymd(year, 1, 1);
+    if new_year.weekday() != Weekday::Sat {
+        days.push(observed(new_year));
+    }
+    days.push(nth_weekday(year, 1, Weekday::Mon, 3)); // Martin Luther King Jr. Day
+    days.push(nth_weekday(year, 2, Weekday::Mon, 3)); // Washington's Birthday
+    days.push(easter_sunday(year) - Duration::days(2)); // Good Friday
+    days.push(last_weekday(year, 5, Weekday::Mon)); // Memorial Day
+    if year >= 2022 {
+        days.push(observed(ymd(year, 6, 19))); // Juneteenth
+    }
+    days.push(observed(ymd(year, 7, 4))); // Independence Day
+    days.push(nth_weekday(year, 9, Weekday::Mon, 1)); // Labor Day
+    days.push(nth_weekday(year, 11, Weekday::Thu, 4)); // Thanksgiving
+    days.push(observed(ymd(year, 12, 25))); // Christmas
+
+    days
+}
+
+/// Days the NYSE closes at 1:00 PM ET.
+fn early_closes(year: i32) -> Vec<NaiveDate> {
+    let mut days = Vec::with_capacity(3);
+
+    // July 3rd, when it's a weekday that isn't itself the observed holiday
+    let july_3 = ymd(year, 7, 3);
+    if !matches!(july_3.weekday(), Weekday::Fri | Weekday::Sat | Weekday::Sun) {
+        days.push(july_3);
+    }
+    days.push(nth_weekday(year, 11, Weekday::Thu, 4) + Duration::days(1)); // Day after Thanksgiving
+    let christmas_eve = ymd(year, 12, 24);
+    if !matches!(christmas_eve.weekday(), Weekday::Fri | Weekday::Sat | Weekday::Sun) {
+        days.push(christmas_eve);
+    }
+
+    days
+}
+
+/// True when the NYSE is closed all day for a holiday or special closure.
+pub fn is_holiday(date: NaiveDate) -> bool {
+    holidays(date.year()).contains(&date)
+        || SPECIAL_CLOSURES
+            .iter()
+            .any(|&(y, m, d)| ymd(y, m, d) == date)
+}
+
+pub fn is_trading_day(date: NaiveDate) -> bool {
+    !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !is_holiday(date)
+}
+
+/// Regular-session open and close (Eastern time) for a date, or `None` if closed.
+pub fn session_hours(date: NaiveDate) -> Option<(NaiveTime, NaiveTime)> {
+    if !is_trading_day(date) {
+        return None;
+    }
+    let close = if early_closes(date.year()).contains(&date) {
+        early_close()
+    } else {
+        regular_close()
+    };
+    Some((regular_open(), close))
+}
+
+/// US daylight saving time runs from the second Sunday in March to the first Sunday
+/// in November. Transitions happen at 2 AM, well outside trading hours, so a
+/// date-level check is enough for session times.
+fn is_eastern_dst(date: NaiveDate) -> bool {
+    let start = nth_weekday(date.year(), 3, Weekday::Sun, 2);
+    let end = nth_weekday(date.year(), 11, Weekday::Sun, 1);
+    date >= start && date < end
+}
+
+fn eastern_offset_hours(date: NaiveDate) -> i64 {
+    if is_eastern_dst(date) {
+        -4
+    } else {
+        -5
+    }
+}
+
+/// Convert an Eastern-time wall clock reading to UTC.
+fn eastern_to_utc(date: NaiveDate, time: NaiveTime) -> DateTime<Utc> {
+    (date.and_time(time) - Duration::hours(eastern_offset_hours(date))).and_utc()
+}
+
+/// The last second of an Eastern-time calendar day, in UTC.
+pub fn eastern_day_end(date: NaiveDate) -> DateTime<Utc> {
+    eastern_to_utc(date, NaiveTime::from_hms_opt(23, 59, 59).expect("valid time"))
+}
+
+/// The Eastern-time calendar date for a UTC instant.
+pub fn eastern_date(now: DateTime<Utc>) -> NaiveDate {
+    let utc_date = now.date_naive();
+    // Offset is decided by the UTC date; at most off by one around the 2 AM switch
+    (now + Duration::hours(eastern_offset_hours(utc_date))).date_naive()
+}
+
+/// True during the NYSE regular session (open inclusive, close exclusive).
+pub fn is_market_open(now: DateTime<Utc>) -> bool {
+    let date = eastern_date(now);
+    match session_hours(date) {
+        Some((open, close)) => {
+            now >= eastern_to_utc(date, open) && now < eastern_to_utc(date, close)
+        }
+        None => false,
+    }
+}
+
+/// `now` if the market is open, otherwise the start of the next regular session.
+pub fn next_open(now: DateTime<Utc>) -> DateTime<Utc> {
+    let today = eastern_date(now);
+    // Longest closure on record is a few days; two weeks is a generous bound
+    for offset in 0..14 {
+        let date = today + Duration::days(offset);
+        if let Some((open, close)) = session_hours(date) {
+            let open_at = eastern_to_utc(date, open);
+            if now < open_at {
+                return open_at;
+            }
+            if now < eastern_to_utc(date, close) {
+                return now;
+            }
+        }
+    }
+    now + Duration::days(14)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    // ---- holiday calendar ----
+
+    #[test]
+    fn test_easter_known_years() {
+        assert_eq!(easter_sunday(2024), ymd(2024, 3, 31));
+        assert_eq!(easter_sunday(2025), ymd(2025, 4, 20));
+        assert_eq!(easter_sunday(2026), ymd(2026, 4, 5));
+    }
+
+    #[test]
+    fn test_holidays_2025() {
+        let expected = [
+            ymd(2025, 1, 1),
+            ymd(2025, 1, 20),
+            ymd(2025, 2, 17),
+            ymd(2025, 4, 18),
+            ymd(2025, 5, 26),
+            ymd(2025, 6, 19),
+            ymd(2025, 7, 4),
+            ymd(2025, 9, 1),
+            ymd(2025, 11, 27),
+            ymd(2025, 12, 25),
+        ];
+        assert_eq!(holidays(2025), expected);
+    }
+
+    #[test]
+    fn test_weekend_holidays_are_observed() {
+        // 2021: July 4th on Sunday, Christmas on Saturday
+        assert!(is_holiday(ymd(2021, 7, 5)));
+        assert!(is_holiday(ymd(2021, 12, 24)));
+        // 2022: Juneteenth on Sunday, Christmas on Sunday
+        assert!(is_holiday(ymd(2022, 6, 20)));
+        assert!(is_holiday(ymd(2022, 12, 26)));
+    }
+
+    #[test]
+    fn test_saturday_new_year_is_not_observed() {
+        // Jan 1 2022 was a Saturday; NYSE stayed open on Friday Dec 31 2021
+        assert!(is_trading_day(ymd(2021, 12, 31)));
+    }
+
+    #[test]
+    fn test_juneteenth_only_from_2022() {
+        assert!(is_trading_day(ymd(2021, 6, 18)));
+        assert!(!is_trading_day(ymd(2024, 6, 19)));
+    }
+
+    #[test]
+    fn test_special_closure() {
+        assert!(!is_trading_day(ymd(2025, 1, 9)));
+    }
+
+    #[test]
+    fn test_weekends_are_not_trading_days() {
+        assert!(!is_trading_day(ymd(2025, 3, 8)));
+        assert!(!is_trading_day(ymd(2025, 3, 9)));
+        assert!(is_trading_day(ymd(2025, 3, 10)));
+    }
+
+    // ---- half days ----
+
+    #[test]
+    fn test_early_closes_2025() {
+        assert_eq!(
+            early_closes(2025),
+            vec![ymd(2025, 7, 3), ymd(2025, 11, 28), ymd(2025, 12, 24)]
+        );
+        let (_, close) = session_hours(ymd(2025, 11, 28)).unwrap();
+        assert_eq!(close, early_close());
+    }
+
+    #[test]
+    fn test_no_july_3rd_early_close_when_it_is_the_holiday() {
+        // 2020: July 4th on Saturday, so Friday July 3rd was a full holiday
+        assert!(session_hours(ymd(2020, 7, 3)).is_none());
+        assert!(!early_closes(2020).contains(&ymd(2020, 7, 3)));
+    }
+
+    #[test]
+    fn test_regular_day_closes_at_four() {
+        assert_eq!(
+            session_hours(ymd(2025, 3, 12)),
+            Some((regular_open(), regular_close()))
+        );
+    }
+
+    // ---- time zones ----
+
+    #[test]
+    fn test_open_shifts_with_dst() {
+        // Friday before DST starts: 9:30 EST = 14:30 UTC
+        assert!(!is_market_open(utc(2025, 3, 7, 14, 29)));
+        assert!(is_market_open(utc(2025, 3, 7, 14, 30)));
+        // Monday after: 9:30 EDT = 13:30 UTC
+        assert!(is_market_open(utc(2025, 3, 10, 13, 30)));
+        assert!(!is_market_open(utc(2025, 3, 10, 20, 0)));
+    }
+
+    #[test]
+    fn test_closed_after_early_close() {
+        // Day after Thanksgiving 2025 (EST): closes 13:00 = 18:00 UTC
+        assert!(is_market_open(utc(2025, 11, 28, 17, 59)));
+        assert!(!is_market_open(utc(2025, 11, 28, 18, 0)));
+    }
+
+    #[test]
+    fn test_eastern_date_before_midnight_utc() {
+        // 01:00 UTC is still the previous evening in New York
+        assert_eq!(eastern_date(utc(2025, 6, 11, 1, 0)), ymd(2025, 6, 10));
+    }
+
+    #[test]
+    fn test_eastern_day_end_follows_dst() {
+        // EDT in June, EST in January
+        let second = Duration::seconds(59);
+        assert_eq!(eastern_day_end(ymd(2025, 6, 10)), utc(2025, 6, 11, 3, 59) + second);
+        assert_eq!(eastern_day_end(ymd(2025, 1, 10)), utc(2025, 1, 11, 4, 59) + second);
+    }
+
+    // ---- next_open ----
+
+    #[test]
+    fn test_next_open_while_open_is_now() {
+        let now = utc(2025, 6, 10, 15, 0);
+        assert_eq!(next_open(now), now);
+    }
+
+    #[test]
+    fn test_next_open_skips_weekend_and_holiday() {
+        // Friday evening before Memorial Day 2025 → Tuesday 9:30 EDT
+        assert_eq!(next_open(utc(2025, 5, 23, 21, 0)), utc(2025, 5, 27, 13, 30));
+    }
+
+    #[test]
+    fn test_next_open_before_bell_same_day() {
+        assert_eq!(next_open(utc(2025, 6, 10, 12, 0)), utc(2025, 6, 10, 13, 30));
+    }
+}
@@ -1,6 +1,112 @@
-use crate::types::{OutlierStock, OutlierType, SectorOutliers, SignificanceLevel, ZScores};
+use crate::benchmarks;
+use crate::detection_runs::{self, RunInputs};
+use crate::gics::{self, PeerGroup};
+use crate::market_hours;
+use crate::outlier_history;
+use crate::returns;
+use crate::stats::{mean_std, percentile, sorted};
+use crate::types::{
+    CompositeWeights, EpisodeStatus, GicsLevel, OutlierStock, OutlierType, ReturnHorizon,
+    ScoreMetric, ScoringPreset, SectorOutliers, SignificanceLevel, StatsOptions, StockZScores,
+    WinsorLimits, ZScoreMethod, ZScoreMethods, ZScores,
+};
+use chrono::{DateTime, Utc};
 use sqlx::sqlite::SqlitePool;
 
+/// Price change and P/E dominate; P/B and volume confirm. The remaining
+/// metrics only count once a preset, the user's weights or a metric selection
+/// brings them in.
+pub const DEFAULT_WEIGHTS: CompositeWeights = CompositeWeights {
+    price: 0.3,
+    pe: 0.3,
+    pb: 0.2,
+    volume: 0.2,
+    dividend_yield: 0.0,
+    beta: 0.0,
+    eps: 0.0,
+    range_position: 0.0,
+};
+
+/// Weight a selected metric gets when the base weights leave it out.
+const SELECTED_METRIC_WEIGHT: f64 = 0.2;
+
+/// Positions within this many percent of either end of the 52-week range
+/// count as "near" the low or high.
+const NEAR_EXTREME_PCT: f64 = 10.0;
+
+/// Weights for a named preset.
+pub fn preset_weights(preset: ScoringPreset) -> CompositeWeights {
+    match preset {
+        ScoringPreset::Balanced => DEFAULT_WEIGHTS,
+        ScoringPreset::ValuationOnly => CompositeWeights {
+            price: 0.0,
+            pe: 0.4,
+            pb: 0.4,
+            volume: 0.0,
+            dividend_yield: 0.2,
+            beta: 0.0,
+            eps: 0.0,
+            range_position: 0.0,
+        },
+        ScoringPreset::MomentumOnly => CompositeWeights {
+            price: 0.5,
+            pe: 0.0,
+            pb: 0.0,
+            volume: 0.3,
+            dividend_yield: 0.0,
+            beta: 0.0,
+            eps: 0.0,
+            range_position: 0.2,
+        },
+    }
+}
+
+/// Zero the weight of every metric not in `metrics`. Selected metrics the
+/// base weights leave out get `SELECTED_METRIC_WEIGHT`, so selecting one always
+/// brings it into the score.
+pub fn select_metrics(weights: CompositeWeights, metrics: &[ScoreMetric]) -> CompositeWeights {
+    let keep = |metric: ScoreMetric, weight: f64| {
+        if !metrics.contains(&metric) {
+            0.0
+        } else if weight > 0.0 {
+            weight
+        } else {
+            SELECTED_METRIC_WEIGHT
+        }
+    };
+    CompositeWeights {
+        price: keep(ScoreMetric::Price, weights.price),
+        pe: keep(ScoreMetric::PeRatio, weights.pe),
+        pb: keep(ScoreMetric::PbRatio, weights.pb),
+        volume: keep(ScoreMetric::Volume, weights.volume),
+        dividend_yield: keep(ScoreMetric::DividendYield, weights.dividend_yield),
+        beta: keep(ScoreMetric::Beta, weights.beta),
+        eps: keep(ScoreMetric::Eps, weights.eps),
+        range_position: keep(ScoreMetric::RangePosition, weights.range_position),
+    }
+}
+
+/// Weights must be non-negative and leave at least one metric in the score.
+pub fn validate_weights(weights: &CompositeWeights) -> Result<(), String> {
+    let values = [
+        weights.price,
+        weights.pe,
+        weights.pb,
+        weights.volume,
+        weights.dividend_yield,
+        weights.beta,
+        weights.eps,
+        weights.range_position,
+    ];
+    if values.iter().any(|v| !v.is_finite() || *v < 0.0) {
+        return Err("Composite weights must be zero or positive".to_string());
+    }
+    if values.iter().sum::<f64>() <= 0.0 {
+        return Err("At least one composite weight must be positive".to_string());
+    }
+    Ok(())
+}
+
 /// Raw market data for a single stock (latest entry).
 #[derive(Debug, sqlx::FromRow)]
 struct StockMarketRow {
@@ -8,235 +114,471 @@ struct StockMarketRow {
     symbol: String,
     name: String,
     sector_id: i32,
+    price: f64,
     price_change_percent: f64,
     pe_ratio: Option<f64>,
     pb_ratio: Option<f64>,
     volume: Option<i64>,
     avg_volume_10d: Option<i64>,
+    dividend_yield: Option<f64>,
+    beta: Option<f64>,
+    eps: Option<f64>,
+    week52_high: Option<f64>,
+    week52_low: Option<f64>,
+    excess_return_1d_percent: Option<f64>,
+    quote_timestamp: String,
+}
+
+impl StockMarketRow {
+    /// Today's volume relative to the 10-day average.
+    fn vol_ratio(&self) -> Option<f64> {
+        match (self.volume, self.avg_volume_10d) {
+            (Some(v), Some(av)) if av > 0 => Some(v as f64 / av as f64),
+            _ => None,
+        }
+    }
+
+    /// Where the price sits in its 52-week range: 0 at the low, 100 at the high.
+    fn range_position(&self) -> Option<f64> {
+        week52_position(self.price, self.week52_low?, self.week52_high?)
+    }
+}
+
+/// Percent of the 52-week range, clamped since the range can lag the latest quote.
+fn week52_position(price: f64, low: f64, high: f64) -> Option<f64> {
+    (high - low > 0.0).then(|| ((price - low) / (high - low) * 100.0).clamp(0.0, 100.0))
+}
+
+/// Spreads at or below this are treated as zero: every peer is (nearly) identical.
+const MIN_SCALE: f64 = 0.001;
+
+/// Consistency constant for modified z-scores (the 0.75 quantile of the standard normal).
+const MAD_CONSISTENCY: f64 = 0.6745;
+
+/// Everything that shapes a detection run besides the universe.
+#[derive(Debug, Clone)]
+pub struct DetectionParams {
+    pub threshold: f64,
+    pub weights: CompositeWeights,
+    pub stats: StatsOptions,
+    /// Window the price metric measures returns over.
+    pub horizon: ReturnHorizon,
+}
+
+/// Center and spread of one metric across a sector.
+#[derive(Debug, Clone, Copy)]
+struct Estimate {
+    center: f64,
+    scale: f64,
+    method: ZScoreMethod,
 }
 
 /// Sector-level statistics for Z-score calculation.
 struct SectorStats {
-    pe_mean: Option<f64>,
-    pe_std: Option<f64>,
-    pb_mean: Option<f64>,
-    pb_std: Option<f64>,
-    price_mean: f64,
-    price_std: f64,
-    vol_ratio_mean: Option<f64>,
-    vol_ratio_std: Option<f64>,
+    price: Estimate,
+    pe: Option<Estimate>,
+    pb: Option<Estimate>,
+    vol_ratio: Option<Estimate>,
+    dividend_yield: Option<Estimate>,
+    beta: Option<Estimate>,
+    eps: Option<Estimate>,
+    range_position: Option<Estimate>,
+    winsorized: bool,
 }
 
-/// Detect outliers across all sectors for a given universe.
+/// Detect outliers across every group at `level` for a given universe, each
+/// stock scored against the other members of its group. Every sector is
+/// reported; below the sector, only groups with outliers or resolved flags.
+///
+/// With `as_of`, detection is replayed from the quotes and universe membership
+/// that existed at that instant. Replays write nothing: streaks come from the
+/// detections stored before that day.
 pub async fn detect_all_outliers(
     pool: &SqlitePool,
-    threshold: f64,
+    params: &DetectionParams,
     universe: &str,
+    as_of: Option<DateTime<Utc>>,
+    level: GicsLevel,
 ) -> Result<Vec<SectorOutliers>, String> {
-    let sectors: Vec<(i32, String, String)> =
-        sqlx::query_as("SELECT id, name, symbol FROM sectors ORDER BY name")
-            .fetch_all(pool)
-            .await
-            .map_err(|e| format!("Failed to fetch sectors: {e}"))?;
+    let groups = gics::peer_groups(pool, level).await?;
 
     let mut results = Vec::new();
 
-    for (sector_id, sector_name, sector_symbol) in &sectors {
-        let outliers = detect_sector_outliers(pool, *sector_id, threshold, universe).await?;
+    for group in groups {
+        let detection = detect_group_outliers(pool, &group, params, universe, as_of).await?;
+        if level != GicsLevel::Sector
+            && detection.outliers.is_empty()
+            && detection.resolved.is_empty()
+        {
+            continue;
+        }
         results.push(SectorOutliers {
-            sector_id: *sector_id,
-            sector_name: sector_name.clone(),
-            sector_symbol: sector_symbol.clone(),
-            outlier_count: outliers.len(),
-            outliers,
+            sector_id: group.sector_id,
+            sector_name: group.sector_name,
+            sector_symbol: group.sector_symbol,
+            level,
+            group_id: group.id,
+            group_name: group.name,
+            outlier_count: detection.outliers.len(),
+            outliers: detection.outliers,
+            resolved: detection.resolved,
         });
     }
 
     Ok(results)
 }
 
-/// Detect outliers within a single sector, filtered by universe.
-pub async fn detect_sector_outliers(
+/// One group's detection run: today's outliers, and the stocks that dropped
+/// out since the previous session.
+#[derive(Debug, Default)]
+pub struct SectorDetection {
+    pub outliers: Vec<OutlierStock>,
+    pub resolved: Vec<OutlierStock>,
+}
+
+/// Detect outliers within a single sector or GICS group, filtered by
+/// universe, optionally as of a past instant.
+pub async fn detect_group_outliers(
     pool: &SqlitePool,
-    sector_id: i32,
-    threshold: f64,
+    group: &PeerGroup,
+    params: &DetectionParams,
     universe: &str,
-) -> Result<Vec<OutlierStock>, String> {
-    // Get latest market data for stocks in this sector that belong to the given universe
-    let rows: Vec<StockMarketRow> = sqlx::query_as(
-        "SELECT s.id as stock_id, s.symbol, s.name, s.sector_id,
-                md.price_change_percent,
-                md.pe_ratio, md.pb_ratio,
-                md.volume, md.avg_volume_10d
-         FROM stocks s
-         JOIN stock_universe su ON su.stock_id = s.id
-            AND su.universe_type = ?
-            AND su.date_removed IS NULL
-         JOIN market_data md ON md.stock_id = s.id
-            AND md.id = (
-                SELECT md2.id FROM market_data md2
-                WHERE md2.stock_id = s.id
-                ORDER BY md2.timestamp DESC LIMIT 1
-            )
-         WHERE s.sector_id = ?",
-    )
-    .bind(universe)
-    .bind(sector_id)
-    .fetch_all(pool)
-    .await
-    .map_err(|e| format!("Failed to fetch sector market data: {e}"))?;
+    as_of: Option<DateTime<Utc>>,
+) -> Result<SectorDetection, String> {
+    let rows =
+        fetch_group_rows(pool, group.level, group.id, params.horizon, universe, as_of).await?;
 
     if rows.len() < 3 {
         // Not enough data for meaningful statistics
-        return Ok(Vec::new());
+        return Ok(SectorDetection::default());
     }
 
-    let stats = calculate_stats(&rows);
+    let stats = calculate_stats(&rows, &params.stats);
     let mut outliers = Vec::new();
+    let mut below_threshold = Vec::new();
 
     for row in &rows {
         let z_scores = calculate_z_scores(row, &stats);
-        let composite = calculate_composite_score(&z_scores);
-
-        if composite >= threshold {
-            let outlier_type = classify_outlier(&z_scores);
-            let significance = classify_significance(composite);
-
-            outliers.push(OutlierStock {
-                stock_id: row.stock_id,
-                symbol: row.symbol.clone(),
-                name: row.name.clone(),
-                z_scores,
-                composite_score: (composite * 100.0).round() / 100.0,
-                outlier_type,
-                significance_level: significance,
-            });
+        let composite = calculate_composite_score(&z_scores, &params.weights);
+        let week52_position = row.range_position();
+
+        let stock = OutlierStock {
+            stock_id: row.stock_id,
+            symbol: row.symbol.clone(),
+            name: row.name.clone(),
+            outlier_type: classify_outlier(&z_scores, week52_position),
+            significance_level: classify_significance(composite),
+            z_scores,
+            week52_position,
+            excess_return_1d_percent: row.excess_return_1d_percent,
+            composite_score: (composite * 100.0).round() / 100.0,
+            status: EpisodeStatus::New,
+            streak_days: 1,
+            first_seen: None,
+        };
+        if composite >= params.threshold {
+            outliers.push(stock);
+        } else {
+            below_threshold.push(stock);
         }
     }
 
     // Sort by composite score descending (strongest outliers first)
     outliers.sort_by(|a, b| b.composite_score.partial_cmp(&a.composite_score).unwrap_or(std::cmp::Ordering::Equal));
 
-    // Save detections to database
-    for outlier in &outliers {
-        save_detection(pool, outlier, sector_id, threshold, universe).await.ok();
-    }
+    // Save detections to database, once per distinct set of inputs. A replay
+    // only reads the history, so it's never recorded.
+    let inputs = run_inputs(&rows);
+    let today = as_of.map_or_else(returns::market_today, market_hours::eastern_date);
+    let detection_date = match as_of {
+        Some(_) => today,
+        None => {
+            let run = detection_runs::record_run(
+                pool, universe, group, params, &inputs, today, &outliers,
+            )
+            .await;
+            match run {
+                Ok(run) => run.detection_date,
+                Err(e) => {
+                    eprintln!(
+                        "Detection run for {} {}: {e}",
+                        group.level.as_str(),
+                        group.id
+                    );
+                    today
+                }
+            }
+        }
+    };
+
+    // Streaks are a nicety on top of the scores; don't fail detection over them
+    let resolved = outlier_history::track_group(
+        pool,
+        universe,
+        group,
+        params,
+        detection_date,
+        &mut outliers,
+        below_threshold,
+    )
+    .await
+    .unwrap_or_else(|e| {
+        eprintln!(
+            "Outlier history for {} {}: {e}",
+            group.level.as_str(),
+            group.id
+        );
+        Vec::new()
+    });
 
-    Ok(outliers)
+    Ok(SectorDetection { outliers, resolved })
 }
 
-/// Calculate sector statistics (mean and std dev for each metric).
-fn calculate_stats(rows: &[StockMarketRow]) -> SectorStats {
-    // Price change
-    let prices: Vec<f64> = rows.iter().map(|r| r.price_change_percent).collect();
-    let (price_mean, price_std) = mean_std(&prices);
+/// What a group's scores were computed from, to key its run. Returns go in as
+/// computed, so a backfill or a new benchmark quote counts as new data even
+/// when no member has a newer quote.
+fn run_inputs(rows: &[StockMarketRow]) -> RunInputs {
+    let data_as_of = rows
+        .iter()
+        .map(|r| r.quote_timestamp.as_str())
+        .max()
+        .unwrap_or_default();
+    let members = rows
+        .iter()
+        .map(|r| {
+            format!(
+                "{}|{}|{}|{:?}",
+                r.stock_id, r.quote_timestamp, r.price_change_percent, r.excess_return_1d_percent
+            )
+        })
+        .collect();
+    RunInputs::new(data_as_of, members)
+}
 
-    // P/E ratio (skip nulls)
-    let pes: Vec<f64> = rows.iter().filter_map(|r| r.pe_ratio).collect();
-    let (pe_mean, pe_std) = if pes.len() >= 2 {
-        let (m, s) = mean_std(&pes);
-        (Some(m), Some(s))
-    } else {
-        (None, None)
+/// Timestamps in the form SQLite's `CURRENT_TIMESTAMP` stores them.
+fn sql_timestamp(t: DateTime<Utc>) -> String {
+    t.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// Latest market data for the stocks in group `group_id` at `level` that
+/// belong to the given universe. `price_change_percent` holds the return over
+/// `horizon`; stocks without enough stored history for it are left out.
+///
+/// With `as_of`, only quotes stored by then count, and membership is the span
+/// that covered that day. The embedded values are formatted from chrono types.
+async fn fetch_group_rows(
+    pool: &SqlitePool,
+    level: GicsLevel,
+    group_id: i32,
+    horizon: ReturnHorizon,
+    universe: &str,
+    as_of: Option<DateTime<Utc>>,
+) -> Result<Vec<StockMarketRow>, String> {
+    let cutoff = as_of.map(sql_timestamp);
+    let quote_cutoff = cutoff
+        .as_ref()
+        .map(|t| format!("AND md2.timestamp <= '{t}'"))
+        .unwrap_or_default();
+    // Membership dates are UTC calendar days
+    let membership = match as_of.map(|t| t.date_naive()) {
+        None => "su.date_removed IS NULL".to_string(),
+        Some(day) => format!(
+            "su.date_added <= '{day}' AND (su.date_removed IS NULL OR su.date_removed > '{day}')"
+        ),
     };
+    let today = as_of.map_or_else(returns::market_today, market_hours::eastern_date);
+    let sql = format!(
+        "SELECT * FROM (
+            SELECT s.id as stock_id, s.symbol, s.name, s.sector_id,
+                   md.price, {change} as price_change_percent,
+                   md.pe_ratio, md.pb_ratio,
+                   md.volume, md.avg_volume_10d,
+                   md.dividend_yield, md.beta, md.eps,
+                   md.week52_high, md.week52_low,
+                   md.price_change_percent - {benchmark} as excess_return_1d_percent,
+                   md.timestamp as quote_timestamp
+            FROM stocks s
+            JOIN stock_gics sg ON sg.stock_id = s.id
+            JOIN stock_universe su ON su.stock_id = s.id
+               AND su.universe_type = ?
+               AND {membership}
+            JOIN market_data md ON md.stock_id = s.id
+               AND md.id = (
+                   SELECT md2.id FROM market_data md2
+                   WHERE md2.stock_id = s.id {quote_cutoff}
+                   ORDER BY md2.timestamp DESC LIMIT 1
+               )
+            WHERE sg.{column} = ?
+         ) WHERE price_change_percent IS NOT NULL",
+        change = returns::return_sql(horizon, today),
+        benchmark = benchmarks::change_sql(),
+        column = level.member_column(),
+    );
+
+    sqlx::query_as(&sql)
+        .bind(universe)
+        .bind(group_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to fetch {} market data: {e}", level.as_str()))
+}
 
-    // P/B ratio (skip nulls)
-    let pbs: Vec<f64> = rows.iter().filter_map(|r| r.pb_ratio).collect();
-    let (pb_mean, pb_std) = if pbs.len() >= 2 {
-        let (m, s) = mean_std(&pbs);
-        (Some(m), Some(s))
-    } else {
-        (None, None)
+/// Current Z-scores for one stock against its peers at `level` in the given
+/// universe. Returns `None` if the stock has no group at that level, no market
+/// data, or too few peers.
+pub async fn stock_z_scores(
+    pool: &SqlitePool,
+    stock_id: i32,
+    weights: &CompositeWeights,
+    stats_options: &StatsOptions,
+    horizon: ReturnHorizon,
+    universe: &str,
+    level: GicsLevel,
+) -> Result<Option<StockZScores>, String> {
+    let group = match gics::stock_group(pool, stock_id, level).await? {
+        Some(group) => group,
+        None => return Ok(None),
     };
 
-    // Volume ratio (volume / avg_volume_10d)
-    let vol_ratios: Vec<f64> = rows
-        .iter()
-        .filter_map(|r| {
-            match (r.volume, r.avg_volume_10d) {
-                (Some(v), Some(av)) if av > 0 => Some(v as f64 / av as f64),
-                _ => None,
-            }
-        })
-        .collect();
-    let (vol_mean, vol_std) = if vol_ratios.len() >= 2 {
-        let (m, s) = mean_std(&vol_ratios);
-        (Some(m), Some(s))
-    } else {
-        (None, None)
+    let rows = fetch_group_rows(pool, level, group.id, horizon, universe, None).await?;
+    if rows.len() < 3 {
+        return Ok(None);
+    }
+
+    let row = match rows.iter().find(|r| r.stock_id == stock_id) {
+        Some(r) => r,
+        None => return Ok(None),
     };
 
+    let stats = calculate_stats(&rows, stats_options);
+    let z_scores = calculate_z_scores(row, &stats);
+    let composite = calculate_composite_score(&z_scores, weights);
+
+    Ok(Some(StockZScores {
+        stock_id,
+        sector_id: group.sector_id,
+        level,
+        group_id: group.id,
+        universe: universe.to_string(),
+        peer_count: rows.len(),
+        outlier_type: classify_outlier(&z_scores, row.range_position()),
+        significance_level: classify_significance(composite),
+        composite_score: (composite * 100.0).round() / 100.0,
+        z_scores,
+        week52_position: row.range_position(),
+        excess_return_1d_percent: row.excess_return_1d_percent,
+    }))
+}
+
+/// Calculate sector statistics (center and spread for each metric).
+fn calculate_stats(rows: &[StockMarketRow], options: &StatsOptions) -> SectorStats {
+    // Price change
+    let prices: Vec<f64> = rows.iter().map(|r| r.price_change_percent).collect();
+    let price = estimate(&prices, options);
+
+    // Everything else skips nulls and needs at least two values
+    let optional = |values: Vec<f64>| (values.len() >= 2).then(|| estimate(&values, options));
+
     SectorStats {
-        pe_mean,
-        pe_std,
-        pb_mean,
-        pb_std,
-        price_mean,
-        price_std,
-        vol_ratio_mean: vol_mean,
-        vol_ratio_std: vol_std,
+        price,
+        pe: optional(rows.iter().filter_map(|r| r.pe_ratio).collect()),
+        pb: optional(rows.iter().filter_map(|r| r.pb_ratio).collect()),
+        vol_ratio: optional(rows.iter().filter_map(|r| r.vol_ratio()).collect()),
+        dividend_yield: optional(rows.iter().filter_map(|r| r.dividend_yield).collect()),
+        beta: optional(rows.iter().filter_map(|r| r.beta).collect()),
+        eps: optional(rows.iter().filter_map(|r| r.eps).collect()),
+        range_position: optional(rows.iter().filter_map(|r| r.range_position()).collect()),
+        winsorized: options.winsorize.is_some(),
     }
 }
 
-/// Calculate Z-scores for a single stock relative to sector stats.
-fn calculate_z_scores(row: &StockMarketRow, stats: &SectorStats) -> ZScores {
-    let price_z = if stats.price_std > 0.001 {
-        (row.price_change_percent - stats.price_mean) / stats.price_std
-    } else {
-        0.0
+/// Estimate center and spread for one metric. Winsorizing only tames the
+/// estimates; each stock is still scored on its raw value.
+fn estimate(values: &[f64], options: &StatsOptions) -> Estimate {
+    let sample = match options.winsorize {
+        Some(limits) => winsorize(values, limits),
+        None => values.to_vec(),
     };
 
-    let pe_z = match (row.pe_ratio, stats.pe_mean, stats.pe_std) {
-        (Some(pe), Some(mean), Some(std)) if std > 0.001 => Some((pe - mean) / std),
-        _ => None,
-    };
+    if options.method == ZScoreMethod::MedianMad {
+        let (median, mad) = median_mad(&sample);
+        let scale = mad / MAD_CONSISTENCY;
+        if scale > MIN_SCALE {
+            return Estimate {
+                center: median,
+                scale,
+                method: ZScoreMethod::MedianMad,
+            };
+        }
+        // Over half the peers share one value, so the MAD is zero: fall back
+    }
 
-    let pb_z = match (row.pb_ratio, stats.pb_mean, stats.pb_std) {
-        (Some(pb), Some(mean), Some(std)) if std > 0.001 => Some((pb - mean) / std),
-        _ => None,
-    };
+    let (mean, std) = mean_std(&sample);
+    Estimate {
+        center: mean,
+        scale: std,
+        method: ZScoreMethod::MeanStd,
+    }
+}
 
-    let volume_z = match (row.volume, row.avg_volume_10d, stats.vol_ratio_mean, stats.vol_ratio_std) {
-        (Some(v), Some(av), Some(mean), Some(std)) if av > 0 && std > 0.001 => {
-            let ratio = v as f64 / av as f64;
-            Some((ratio - mean) / std)
-        }
+/// (value - center) / scale, or `None` when the sector has no spread.
+fn z_score(value: f64, estimate: &Estimate) -> Option<f64> {
+    (estimate.scale > MIN_SCALE).then(|| (value - estimate.center) / estimate.scale)
+}
+
+/// Calculate Z-scores for a single stock relative to sector stats.
+fn calculate_z_scores(row: &StockMarketRow, stats: &SectorStats) -> ZScores {
+    let price_z = z_score(row.price_change_percent, &stats.price).unwrap_or(0.0);
+    let optional_z = |value: Option<f64>, est: &Option<Estimate>| match (value, est) {
+        (Some(v), Some(est)) => z_score(v, est),
         _ => None,
     };
+    let method_of = |est: &Option<Estimate>| est.map_or(ZScoreMethod::MeanStd, |e| e.method);
 
     ZScores {
-        pe_z,
-        pb_z,
+        pe_z: optional_z(row.pe_ratio, &stats.pe),
+        pb_z: optional_z(row.pb_ratio, &stats.pb),
         price_z,
-        volume_z,
+        volume_z: optional_z(row.vol_ratio(), &stats.vol_ratio),
+        dividend_yield_z: optional_z(row.dividend_yield, &stats.dividend_yield),
+        beta_z: optional_z(row.beta, &stats.beta),
+        eps_z: optional_z(row.eps, &stats.eps),
+        range_position_z: optional_z(row.range_position(), &stats.range_position),
+        methods: ZScoreMethods {
+            price: stats.price.method,
+            pe: method_of(&stats.pe),
+            pb: method_of(&stats.pb),
+            volume: method_of(&stats.vol_ratio),
+            dividend_yield: method_of(&stats.dividend_yield),
+            beta: method_of(&stats.beta),
+            eps: method_of(&stats.eps),
+            range_position: method_of(&stats.range_position),
+            winsorized: stats.winsorized,
+        },
     }
 }
 
 /// Calculate composite outlier score from Z-scores (weighted RMS).
-fn calculate_composite_score(z: &ZScores) -> f64 {
+/// Missing metrics drop out along with their weight.
+fn calculate_composite_score(z: &ZScores, weights: &CompositeWeights) -> f64 {
     let mut weighted_sum = 0.0;
     let mut total_weight = 0.0;
 
-    // Price change: weight 0.3
-    weighted_sum += 0.3 * z.price_z * z.price_z;
-    total_weight += 0.3;
-
-    // P/E: weight 0.3
-    if let Some(pe) = z.pe_z {
-        weighted_sum += 0.3 * pe * pe;
-        total_weight += 0.3;
-    }
-
-    // P/B: weight 0.2
-    if let Some(pb) = z.pb_z {
-        weighted_sum += 0.2 * pb * pb;
-        total_weight += 0.2;
-    }
-
-    // Volume: weight 0.2
-    if let Some(vol) = z.volume_z {
-        weighted_sum += 0.2 * vol * vol;
-        total_weight += 0.2;
+    let metrics = [
+        (Some(z.price_z), weights.price),
+        (z.pe_z, weights.pe),
+        (z.pb_z, weights.pb),
+        (z.volume_z, weights.volume),
+        (z.dividend_yield_z, weights.dividend_yield),
+        (z.beta_z, weights.beta),
+        (z.eps_z, weights.eps),
+        (z.range_position_z, weights.range_position),
+    ];
+    for (value, weight) in metrics {
+        if let Some(v) = value {
+            weighted_sum += weight * v * v;
+            total_weight += weight;
+        }
     }
 
     if total_weight > 0.0 {
@@ -246,8 +588,9 @@ fn calculate_composite_score(z: &ZScores) -> f64 {
     }
 }
 
-/// Classify the type of outlier based on Z-score directions.
-fn classify_outlier(z: &ZScores) -> OutlierType {
+/// Classify the type of outlier based on Z-score directions, falling back to
+/// where the price sits in its 52-week range.
+fn classify_outlier(z: &ZScores, week52_position: Option<f64>) -> OutlierType {
     let pe_low = z.pe_z.map_or(false, |v| v < -1.0);
     let pe_high = z.pe_z.map_or(false, |v| v > 1.0);
     let pb_low = z.pb_z.map_or(false, |v| v < -1.0);
@@ -255,6 +598,10 @@ fn classify_outlier(z: &ZScores) -> OutlierType {
     let price_high = z.price_z > 1.0;
     let price_low = z.price_z < -1.0;
     let vol_high = z.volume_z.map_or(false, |v| v > 1.0);
+    let yield_high = z.dividend_yield_z.is_some_and(|v| v > 1.0);
+    let eps_low = z.eps_z.is_some_and(|v| v < -1.0);
+    let near_low = week52_position.is_some_and(|p| p <= NEAR_EXTREME_PCT);
+    let near_high = week52_position.is_some_and(|p| p >= 100.0 - NEAR_EXTREME_PCT);
 
     if pe_low && pb_low {
         OutlierType::Undervalued
@@ -266,6 +613,12 @@ fn classify_outlier(z: &ZScores) -> OutlierType {
         OutlierType::ValueTrap
     } else if pe_high && price_high {
         OutlierType::GrowthPremium
+    } else if yield_high && (price_low || eps_low) {
+        OutlierType::YieldTrap
+    } else if near_low {
+        OutlierType::NearYearLow
+    } else if near_high {
+        OutlierType::NearYearHigh
     } else {
         OutlierType::Mixed
     }
@@ -282,51 +635,32 @@ fn classify_significance(score: f64) -> SignificanceLevel {
     }
 }
 
-/// Save an outlier detection to the database.
-async fn save_detection(
-    pool: &SqlitePool,
-    outlier: &OutlierStock,
-    sector_id: i32,
-    threshold: f64,
-    universe: &str,
-) -> Result<(), String> {
-    sqlx::query(
-        "INSERT INTO outlier_detections (
-            stock_id, sector_id, pe_z_score, pb_z_score,
-            price_z_score, volume_z_score, composite_score,
-            outlier_type, significance_level, threshold_used, universe_type
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
-    )
-    .bind(outlier.stock_id)
-    .bind(sector_id)
-    .bind(outlier.z_scores.pe_z)
-    .bind(outlier.z_scores.pb_z)
-    .bind(outlier.z_scores.price_z)
-    .bind(outlier.z_scores.volume_z)
-    .bind(outlier.composite_score)
-    .bind(outlier.outlier_type.to_string())
-    .bind(outlier.significance_level.to_string())
-    .bind(threshold)
-    .bind(universe)
-    .execute(pool)
-    .await
-    .map_err(|e| format!("Failed to save outlier detection: {e}"))?;
+/// Median and median absolute deviation (unscaled).
+fn median_mad(values: &[f64]) -> (f64, f64) {
+    let median = percentile(&sorted(values), 50.0);
+    let deviations: Vec<f64> = values.iter().map(|v| (v - median).abs()).collect();
+    (median, percentile(&sorted(&deviations), 50.0))
+}
 
-    Ok(())
+/// Clip values to the given percentiles of the sample.
+fn winsorize(values: &[f64], limits: WinsorLimits) -> Vec<f64> {
+    let sorted = sorted(values);
+    let lo = percentile(&sorted, limits.lower_percentile);
+    let hi = percentile(&sorted, limits.upper_percentile);
+    values.iter().map(|v| v.clamp(lo, hi)).collect()
 }
 
-/// Calculate mean and standard deviation of a slice.
-fn mean_std(values: &[f64]) -> (f64, f64) {
-    let n = values.len() as f64;
-    if n < 1.0 {
-        return (0.0, 0.0);
-    }
-    let mean = values.iter().sum::<f64>() / n;
-    if n < 2.0 {
-        return (mean, 0.0);
+/// Winsorization bounds must be ordered percentiles.
+pub fn validate_stats(options: &StatsOptions) -> Result<(), String> {
+    if let Some(limits) = options.winsorize {
+        let (lo, hi) = (limits.lower_percentile, limits.upper_percentile);
+        if !(0.0..=100.0).contains(&lo) || !(0.0..=100.0).contains(&hi) || lo >= hi {
+            return Err(format!(
+                "Winsorization percentiles must satisfy 0 <= lower < upper <= 100 (got {lo}, {hi})"
+            ));
+        }
     }
-    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
-    (mean, variance.sqrt())
+    Ok(())
 }
 
 #[cfg(test)]
@@ -339,6 +673,25 @@ mod tests {
         (a - b).abs() < EPSILON
     }
 
+    const ZERO_WEIGHTS: CompositeWeights = CompositeWeights {
+        price: 0.0,
+        pe: 0.0,
+        pb: 0.0,
+        volume: 0.0,
+        dividend_yield: 0.0,
+        beta: 0.0,
+        eps: 0.0,
+        range_position: 0.0,
+    };
+
+    fn est(center: f64, scale: f64) -> Estimate {
+        Estimate {
+            center,
+            scale,
+            method: ZScoreMethod::MeanStd,
+        }
+    }
+
     fn make_row(
         stock_id: i32,
         symbol: &str,
@@ -353,63 +706,22 @@ mod tests {
             symbol: symbol.to_string(),
             name: format!("Company {symbol}"),
             sector_id: 1,
+            price: 100.0,
             price_change_percent,
             pe_ratio,
             pb_ratio,
             volume,
             avg_volume_10d,
+            dividend_yield: None,
+            beta: None,
+            eps: None,
+            week52_high: None,
+            week52_low: None,
+            excess_return_1d_percent: None,
+            quote_timestamp: "2025-06-10 15:00:00".to_string(),
         }
     }
 
-    // ---- mean_std ----
-
-    #[test]
-    fn test_mean_std_empty() {
-        let (mean, std) = mean_std(&[]);
-        assert_eq!(mean, 0.0);
-        assert_eq!(std, 0.0);
-    }
-
-    #[test]
-    fn test_mean_std_single_value() {
-        let (mean, std) = mean_std(&[5.0]);
-        assert!(approx_eq(mean, 5.0));
-        assert_eq!(std, 0.0);
-    }
-
-    #[test]
-    fn test_mean_std_two_values() {
-        // mean = (1+3)/2 = 2.0
-        // variance = ((1-2)² + (3-2)²) / (2-1) = 2.0, std = sqrt(2)
-        let (mean, std) = mean_std(&[1.0, 3.0]);
-        assert!(approx_eq(mean, 2.0));
-        assert!(approx_eq(std, 2.0_f64.sqrt()));
-    }
-
-    #[test]
-    fn test_mean_std_known_dataset() {
-        // [1, 2, 3]: mean=2, variance=((1-2)²+(2-2)²+(3-2)²)/2=1.0, std=1.0
-        let (mean, std) = mean_std(&[1.0, 2.0, 3.0]);
-        assert!(approx_eq(mean, 2.0));
-        assert!(approx_eq(std, 1.0));
-    }
-
-    #[test]
-    fn test_mean_std_all_same_values() {
-        // Zero std dev when all values are equal
-        let (mean, std) = mean_std(&[5.0, 5.0, 5.0, 5.0]);
-        assert!(approx_eq(mean, 5.0));
-        assert!(approx_eq(std, 0.0));
-    }
-
-    #[test]
-    fn test_mean_std_negative_values() {
-        // [-1, 0, 1]: mean=0.0, variance=(1+0+1)/2=1.0, std=1.0
-        let (mean, std) = mean_std(&[-1.0, 0.0, 1.0]);
-        assert!(approx_eq(mean, 0.0));
-        assert!(approx_eq(std, 1.0));
-    }
-
     // ---- calculate_stats ----
 
     #[test]
@@ -419,23 +731,23 @@ mod tests {
             make_row(2, "B", 2.0, Some(20.0), Some(2.0), Some(2000), Some(1000)),
             make_row(3, "C", 3.0, Some(30.0), Some(3.0), Some(3000), Some(1500)),
         ];
-        let stats = calculate_stats(&rows);
+        let stats = calculate_stats(&rows, &StatsOptions::default());
 
         // price: [1,2,3] → mean=2.0, std=1.0
-        assert!(approx_eq(stats.price_mean, 2.0));
-        assert!(approx_eq(stats.price_std, 1.0));
+        assert!(approx_eq(stats.price.center, 2.0));
+        assert!(approx_eq(stats.price.scale, 1.0));
 
         // pe: [10,20,30] → mean=20.0, std=10.0
-        assert!(approx_eq(stats.pe_mean.unwrap(), 20.0));
-        assert!(approx_eq(stats.pe_std.unwrap(), 10.0));
+        assert!(approx_eq(stats.pe.unwrap().center, 20.0));
+        assert!(approx_eq(stats.pe.unwrap().scale, 10.0));
 
         // pb: [1,2,3] → mean=2.0, std=1.0
-        assert!(approx_eq(stats.pb_mean.unwrap(), 2.0));
-        assert!(approx_eq(stats.pb_std.unwrap(), 1.0));
+        assert!(approx_eq(stats.pb.unwrap().center, 2.0));
+        assert!(approx_eq(stats.pb.unwrap().scale, 1.0));
 
         // vol_ratio: [2.0, 2.0, 2.0] (each stock trades at 2x avg) → mean=2.0, std=0.0
-        assert!(approx_eq(stats.vol_ratio_mean.unwrap(), 2.0));
-        assert!(approx_eq(stats.vol_ratio_std.unwrap(), 0.0));
+        assert!(approx_eq(stats.vol_ratio.unwrap().center, 2.0));
+        assert!(approx_eq(stats.vol_ratio.unwrap().scale, 0.0));
     }
 
     #[test]
@@ -446,9 +758,8 @@ mod tests {
             make_row(2, "B", 2.0, None, None, None, None),
             make_row(3, "C", 3.0, None, None, None, None),
         ];
-        let stats = calculate_stats(&rows);
-        assert!(stats.pe_mean.is_none());
-        assert!(stats.pe_std.is_none());
+        let stats = calculate_stats(&rows, &StatsOptions::default());
+        assert!(stats.pe.is_none());
     }
 
     #[test]
@@ -458,9 +769,100 @@ mod tests {
             make_row(2, "B", 2.0, None, None, None, None),
             make_row(3, "C", 3.0, None, None, None, None),
         ];
-        let stats = calculate_stats(&rows);
-        assert!(stats.vol_ratio_mean.is_none());
-        assert!(stats.vol_ratio_std.is_none());
+        let stats = calculate_stats(&rows, &StatsOptions::default());
+        assert!(stats.vol_ratio.is_none());
+    }
+
+    // ---- robust statistics ----
+
+    fn robust() -> StatsOptions {
+        StatsOptions {
+            method: ZScoreMethod::MedianMad,
+            winsorize: None,
+        }
+    }
+
+    /// Ten peers with P/Es 10..=19 plus one at 900.
+    fn rows_with_pe_spike() -> Vec<StockMarketRow> {
+        let mut rows: Vec<StockMarketRow> = (0..10)
+            .map(|i| make_row(i, "P", i as f64 * 0.1, Some(10.0 + i as f64), None, None, None))
+            .collect();
+        rows.push(make_row(10, "SPIKE", 0.5, Some(900.0), None, None, None));
+        rows
+    }
+
+    #[test]
+    fn test_median_mad_known_dataset() {
+        // median = 3, |deviations| = [2, 1, 0, 1, 97] → MAD = 1
+        let (median, mad) = median_mad(&[1.0, 2.0, 3.0, 4.0, 100.0]);
+        assert!(approx_eq(median, 3.0));
+        assert!(approx_eq(mad, 1.0));
+    }
+
+    #[test]
+    fn test_winsorize_clips_to_percentiles() {
+        let values = [1.0, 2.0, 3.0, 4.0, 100.0];
+        let limits = WinsorLimits { lower_percentile: 25.0, upper_percentile: 75.0 };
+        let clipped = winsorize(&values, limits);
+        assert_eq!(clipped, vec![2.0, 2.0, 3.0, 4.0, 4.0]);
+    }
+
+    #[test]
+    fn test_robust_z_uses_modified_z_formula() {
+        let est = estimate(&[1.0, 2.0, 3.0, 4.0, 100.0], &robust());
+        assert_eq!(est.method, ZScoreMethod::MedianMad);
+        // 0.6745 * (5 - 3) / 1
+        assert!(approx_eq(z_score(5.0, &est).unwrap(), 0.6745 * 2.0));
+    }
+
+    #[test]
+    fn test_robust_falls_back_when_mad_is_zero() {
+        // Majority identical → MAD = 0, so mean/std is used instead
+        let est = estimate(&[5.0, 5.0, 5.0, 6.0], &robust());
+        assert_eq!(est.method, ZScoreMethod::MeanStd);
+        assert!(est.scale > 0.0);
+    }
+
+    #[test]
+    fn test_single_extreme_pe_masks_peers_under_mean_std() {
+        let rows = rows_with_pe_spike();
+        let stats = calculate_stats(&rows, &StatsOptions::default());
+        // The cheapest peer (P/E 10) sits well inside the inflated spread
+        assert!(calculate_z_scores(&rows[0], &stats).pe_z.unwrap().abs() < 1.0);
+    }
+
+    #[test]
+    fn test_robust_stats_flag_spike_and_spread_peers() {
+        let rows = rows_with_pe_spike();
+        let stats = calculate_stats(&rows, &robust());
+        let spike = calculate_z_scores(&rows[10], &stats);
+        assert!(spike.pe_z.unwrap() > 100.0);
+        assert!(calculate_z_scores(&rows[0], &stats).pe_z.unwrap() < -1.0);
+        assert_eq!(spike.methods.pe, ZScoreMethod::MedianMad);
+        assert!(!spike.methods.winsorized);
+    }
+
+    #[test]
+    fn test_winsorized_mean_std_shrinks_spread() {
+        let rows = rows_with_pe_spike();
+        let options = StatsOptions {
+            method: ZScoreMethod::MeanStd,
+            winsorize: Some(WinsorLimits { lower_percentile: 5.0, upper_percentile: 90.0 }),
+        };
+        let plain = calculate_stats(&rows, &StatsOptions::default());
+        let clipped = calculate_stats(&rows, &options);
+        assert!(clipped.pe.unwrap().scale < plain.pe.unwrap().scale / 10.0);
+        assert!(calculate_z_scores(&rows[10], &clipped).methods.winsorized);
+    }
+
+    #[test]
+    fn test_validate_stats_rejects_inverted_limits() {
+        let options = StatsOptions {
+            method: ZScoreMethod::MeanStd,
+            winsorize: Some(WinsorLimits { lower_percentile: 95.0, upper_percentile: 5.0 }),
+        };
+        assert!(validate_stats(&options).is_err());
+        assert!(validate_stats(&StatsOptions::default()).is_ok());
     }
 
     // ---- calculate_z_scores ----
@@ -468,14 +870,15 @@ mod tests {
     #[test]
     fn test_z_scores_all_present() {
         let stats = SectorStats {
-            price_mean: 0.0,
-            price_std: 1.0,
-            pe_mean: Some(20.0),
-            pe_std: Some(5.0),
-            pb_mean: Some(3.0),
-            pb_std: Some(1.0),
-            vol_ratio_mean: Some(1.0),
-            vol_ratio_std: Some(0.5),
+            price: est(0.0, 1.0),
+            pe: Some(est(20.0, 5.0)),
+            pb: Some(est(3.0, 1.0)),
+            vol_ratio: Some(est(1.0, 0.5)),
+            dividend_yield: None,
+            beta: None,
+            eps: None,
+            range_position: None,
+            winsorized: false,
         };
         // vol ratio = 2_000_000 / 1_000_000 = 2.0
         let row = make_row(1, "AAPL", 2.0, Some(30.0), Some(5.0), Some(2_000_000), Some(1_000_000));
@@ -494,14 +897,15 @@ mod tests {
     #[test]
     fn test_z_scores_negative_deviation() {
         let stats = SectorStats {
-            price_mean: 0.0,
-            price_std: 2.0,
-            pe_mean: Some(20.0),
-            pe_std: Some(5.0),
-            pb_mean: None,
-            pb_std: None,
-            vol_ratio_mean: None,
-            vol_ratio_std: None,
+            price: est(0.0, 2.0),
+            pe: Some(est(20.0, 5.0)),
+            pb: None,
+            vol_ratio: None,
+            dividend_yield: None,
+            beta: None,
+            eps: None,
+            range_position: None,
+            winsorized: false,
         };
         let row = make_row(1, "X", -4.0, Some(10.0), None, None, None);
         let z = calculate_z_scores(&row, &stats);
@@ -514,16 +918,17 @@ mod tests {
 
     #[test]
     fn test_z_scores_zero_price_std_returns_zero() {
-        // price_std <= 0.001 → price_z must be 0.0
+        // price scale <= 0.001 → price_z must be 0.0
         let stats = SectorStats {
-            price_mean: 1.0,
-            price_std: 0.0,
-            pe_mean: None,
-            pe_std: None,
-            pb_mean: None,
-            pb_std: None,
-            vol_ratio_mean: None,
-            vol_ratio_std: None,
+            price: est(1.0, 0.0),
+            pe: None,
+            pb: None,
+            vol_ratio: None,
+            dividend_yield: None,
+            beta: None,
+            eps: None,
+            range_position: None,
+            winsorized: false,
         };
         let row = make_row(1, "A", 5.0, None, None, None, None);
         let z = calculate_z_scores(&row, &stats);
@@ -533,14 +938,15 @@ mod tests {
     #[test]
     fn test_z_scores_missing_pe_ratio_gives_none() {
         let stats = SectorStats {
-            price_mean: 0.0,
-            price_std: 1.0,
-            pe_mean: Some(20.0),
-            pe_std: Some(5.0),
-            pb_mean: None,
-            pb_std: None,
-            vol_ratio_mean: None,
-            vol_ratio_std: None,
+            price: est(0.0, 1.0),
+            pe: Some(est(20.0, 5.0)),
+            pb: None,
+            vol_ratio: None,
+            dividend_yield: None,
+            beta: None,
+            eps: None,
+            range_position: None,
+            winsorized: false,
         };
         let row = make_row(1, "A", 1.0, None, None, None, None);
         let z = calculate_z_scores(&row, &stats);
@@ -549,16 +955,17 @@ mod tests {
 
     #[test]
     fn test_z_scores_zero_pe_std_gives_none() {
-        // pe_std <= 0.001 → pe_z must be None even if pe_ratio is present
+        // pe scale <= 0.001 → pe_z must be None even if pe_ratio is present
         let stats = SectorStats {
-            price_mean: 0.0,
-            price_std: 1.0,
-            pe_mean: Some(20.0),
-            pe_std: Some(0.0),
-            pb_mean: None,
-            pb_std: None,
-            vol_ratio_mean: None,
-            vol_ratio_std: None,
+            price: est(0.0, 1.0),
+            pe: Some(est(20.0, 0.0)),
+            pb: None,
+            vol_ratio: None,
+            dividend_yield: None,
+            beta: None,
+            eps: None,
+            range_position: None,
+            winsorized: false,
         };
         let row = make_row(1, "A", 1.0, Some(25.0), None, None, None);
         let z = calculate_z_scores(&row, &stats);
@@ -569,14 +976,15 @@ mod tests {
     fn test_z_scores_zero_avg_volume_gives_none() {
         // avg_volume_10d = 0 → volume_z must be None (avoid division by zero)
         let stats = SectorStats {
-            price_mean: 0.0,
-            price_std: 1.0,
-            pe_mean: None,
-            pe_std: None,
-            pb_mean: None,
-            pb_std: None,
-            vol_ratio_mean: Some(1.0),
-            vol_ratio_std: Some(0.5),
+            price: est(0.0, 1.0),
+            pe: None,
+            pb: None,
+            vol_ratio: Some(est(1.0, 0.5)),
+            dividend_yield: None,
+            beta: None,
+            eps: None,
+            range_position: None,
+            winsorized: false,
         };
         let row = make_row(1, "A", 1.0, None, None, Some(1_000_000), Some(0));
         let z = calculate_z_scores(&row, &stats);
@@ -589,22 +997,22 @@ mod tests {
     fn test_composite_score_all_present() {
         // All z = 2.0: weighted_sum = 0.3*4+0.3*4+0.2*4+0.2*4 = 4.0, weight = 1.0
         // score = sqrt(4.0/1.0) = 2.0
-        let z = ZScores { price_z: 2.0, pe_z: Some(2.0), pb_z: Some(2.0), volume_z: Some(2.0) };
-        assert!(approx_eq(calculate_composite_score(&z), 2.0));
+        let z = ZScores { price_z: 2.0, pe_z: Some(2.0), pb_z: Some(2.0), volume_z: Some(2.0), ..Default::default() };
+        assert!(approx_eq(calculate_composite_score(&z, &DEFAULT_WEIGHTS), 2.0));
     }
 
     #[test]
     fn test_composite_score_price_only() {
         // price_z=2.0, others None: weighted_sum=0.3*4=1.2, weight=0.3
         // score = sqrt(1.2/0.3) = sqrt(4.0) = 2.0
-        let z = ZScores { price_z: 2.0, pe_z: None, pb_z: None, volume_z: None };
-        assert!(approx_eq(calculate_composite_score(&z), 2.0));
+        let z = ZScores { price_z: 2.0, pe_z: None, pb_z: None, volume_z: None, ..Default::default() };
+        assert!(approx_eq(calculate_composite_score(&z, &DEFAULT_WEIGHTS), 2.0));
     }
 
     #[test]
     fn test_composite_score_all_zero() {
-        let z = ZScores { price_z: 0.0, pe_z: Some(0.0), pb_z: Some(0.0), volume_z: Some(0.0) };
-        assert!(approx_eq(calculate_composite_score(&z), 0.0));
+        let z = ZScores { price_z: 0.0, pe_z: Some(0.0), pb_z: Some(0.0), volume_z: Some(0.0), ..Default::default() };
+        assert!(approx_eq(calculate_composite_score(&z, &DEFAULT_WEIGHTS), 0.0));
     }
 
     #[test]
@@ -612,16 +1020,88 @@ mod tests {
         // price_z=1.0, pe_z=3.0, others None
         // weighted_sum = 0.3*1 + 0.3*9 = 3.0, weight = 0.6
         // score = sqrt(3.0/0.6) = sqrt(5.0)
-        let z = ZScores { price_z: 1.0, pe_z: Some(3.0), pb_z: None, volume_z: None };
-        assert!(approx_eq(calculate_composite_score(&z), 5.0_f64.sqrt()));
+        let z = ZScores { price_z: 1.0, pe_z: Some(3.0), pb_z: None, volume_z: None, ..Default::default() };
+        assert!(approx_eq(calculate_composite_score(&z, &DEFAULT_WEIGHTS), 5.0_f64.sqrt()));
     }
 
     #[test]
     fn test_composite_score_negative_z_uses_squares() {
         // Negative z-scores → same composite as positive (squaring removes sign)
-        let pos = ZScores { price_z: 2.0, pe_z: Some(2.0), pb_z: Some(2.0), volume_z: Some(2.0) };
-        let neg = ZScores { price_z: -2.0, pe_z: Some(-2.0), pb_z: Some(-2.0), volume_z: Some(-2.0) };
-        assert!(approx_eq(calculate_composite_score(&pos), calculate_composite_score(&neg)));
+        let pos = ZScores { price_z: 2.0, pe_z: Some(2.0), pb_z: Some(2.0), volume_z: Some(2.0), ..Default::default() };
+        let neg = ZScores { price_z: -2.0, pe_z: Some(-2.0), pb_z: Some(-2.0), volume_z: Some(-2.0), ..Default::default() };
+        assert!(approx_eq(
+            calculate_composite_score(&pos, &DEFAULT_WEIGHTS),
+            calculate_composite_score(&neg, &DEFAULT_WEIGHTS)
+        ));
+    }
+
+    #[test]
+    fn test_composite_score_zero_weight_ignores_metric() {
+        // Volume switched off: a huge volume_z must not move the score
+        let weights = CompositeWeights { volume: 0.0, ..DEFAULT_WEIGHTS };
+        let z = ZScores { price_z: 2.0, pe_z: Some(2.0), pb_z: Some(2.0), volume_z: Some(10.0), ..Default::default() };
+        assert!(approx_eq(calculate_composite_score(&z, &weights), 2.0));
+    }
+
+    #[test]
+    fn test_composite_score_no_weighted_metrics_is_zero() {
+        let weights = CompositeWeights { pe: 1.0, ..ZERO_WEIGHTS };
+        let z = ZScores { price_z: 3.0, pe_z: None, pb_z: None, volume_z: None, ..Default::default() };
+        assert!(approx_eq(calculate_composite_score(&z, &weights), 0.0));
+    }
+
+    // ---- presets and metric selection ----
+
+    #[test]
+    fn test_every_preset_is_valid() {
+        for preset in [
+            ScoringPreset::Balanced,
+            ScoringPreset::ValuationOnly,
+            ScoringPreset::MomentumOnly,
+        ] {
+            assert!(validate_weights(&preset_weights(preset)).is_ok(), "{preset:?}");
+        }
+    }
+
+    #[test]
+    fn test_valuation_preset_ignores_price_move() {
+        let z = ZScores { price_z: 5.0, pe_z: Some(1.0), pb_z: Some(1.0), volume_z: Some(5.0), ..Default::default() };
+        let weights = preset_weights(ScoringPreset::ValuationOnly);
+        assert!(approx_eq(calculate_composite_score(&z, &weights), 1.0));
+    }
+
+    #[test]
+    fn test_momentum_preset_ignores_valuation() {
+        let z = ZScores { price_z: 2.0, pe_z: Some(9.0), pb_z: Some(9.0), volume_z: Some(2.0), ..Default::default() };
+        let weights = preset_weights(ScoringPreset::MomentumOnly);
+        assert!(approx_eq(calculate_composite_score(&z, &weights), 2.0));
+    }
+
+    #[test]
+    fn test_select_metrics_zeroes_excluded() {
+        let weights = select_metrics(DEFAULT_WEIGHTS, &[ScoreMetric::Price, ScoreMetric::PbRatio]);
+        assert_eq!(weights, CompositeWeights { price: 0.3, pb: 0.2, ..ZERO_WEIGHTS });
+    }
+
+    #[test]
+    fn test_select_metrics_brings_in_unweighted_metric() {
+        let weights = select_metrics(DEFAULT_WEIGHTS, &[ScoreMetric::Beta]);
+        assert_eq!(weights, CompositeWeights { beta: SELECTED_METRIC_WEIGHT, ..ZERO_WEIGHTS });
+        assert!(validate_weights(&weights).is_ok());
+    }
+
+    #[test]
+    fn test_selecting_beta_changes_composite() {
+        let z = ZScores { price_z: 1.0, beta_z: Some(3.0), ..Default::default() };
+        let price_only = select_metrics(DEFAULT_WEIGHTS, &[ScoreMetric::Price]);
+        let with_beta = select_metrics(DEFAULT_WEIGHTS, &[ScoreMetric::Price, ScoreMetric::Beta]);
+        assert!(approx_eq(calculate_composite_score(&z, &price_only), 1.0));
+        assert!(calculate_composite_score(&z, &with_beta) > 1.0);
+    }
+
+    #[test]
+    fn test_select_no_metrics_is_invalid() {
+        assert!(validate_weights(&select_metrics(DEFAULT_WEIGHTS, &[])).is_err());
     }
 
     // ---- classify_outlier ----
@@ -629,57 +1109,128 @@ mod tests {
     #[test]
     fn test_classify_undervalued() {
         // pe_z < -1 AND pb_z < -1
-        let z = ZScores { price_z: 0.0, pe_z: Some(-2.0), pb_z: Some(-2.0), volume_z: None };
-        assert!(matches!(classify_outlier(&z), OutlierType::Undervalued));
+        let z = ZScores { price_z: 0.0, pe_z: Some(-2.0), pb_z: Some(-2.0), volume_z: None, ..Default::default() };
+        assert!(matches!(classify_outlier(&z, None), OutlierType::Undervalued));
     }
 
     #[test]
     fn test_classify_overvalued() {
         // pe_z > 1 AND pb_z > 1
-        let z = ZScores { price_z: 0.0, pe_z: Some(2.0), pb_z: Some(2.0), volume_z: None };
-        assert!(matches!(classify_outlier(&z), OutlierType::Overvalued));
+        let z = ZScores { price_z: 0.0, pe_z: Some(2.0), pb_z: Some(2.0), volume_z: None, ..Default::default() };
+        assert!(matches!(classify_outlier(&z, None), OutlierType::Overvalued));
     }
 
     #[test]
     fn test_classify_momentum() {
         // price_z > 1 AND volume_z > 1, with pe/pb absent so earlier conditions don't fire
-        let z = ZScores { price_z: 2.0, pe_z: None, pb_z: None, volume_z: Some(2.0) };
-        assert!(matches!(classify_outlier(&z), OutlierType::Momentum));
+        let z = ZScores { price_z: 2.0, pe_z: None, pb_z: None, volume_z: Some(2.0), ..Default::default() };
+        assert!(matches!(classify_outlier(&z, None), OutlierType::Momentum));
     }
 
     #[test]
     fn test_classify_value_trap() {
         // pe_z < -1 AND price_z < -1, but pb_z absent so Undervalued doesn't trigger
-        let z = ZScores { price_z: -2.0, pe_z: Some(-2.0), pb_z: None, volume_z: None };
-        assert!(matches!(classify_outlier(&z), OutlierType::ValueTrap));
+        let z = ZScores { price_z: -2.0, pe_z: Some(-2.0), pb_z: None, volume_z: None, ..Default::default() };
+        assert!(matches!(classify_outlier(&z, None), OutlierType::ValueTrap));
     }
 
     #[test]
     fn test_classify_growth_premium() {
         // pe_z > 1 AND price_z > 1, but pb_z absent so Overvalued doesn't trigger
-        let z = ZScores { price_z: 2.0, pe_z: Some(2.0), pb_z: None, volume_z: None };
-        assert!(matches!(classify_outlier(&z), OutlierType::GrowthPremium));
+        let z = ZScores { price_z: 2.0, pe_z: Some(2.0), pb_z: None, volume_z: None, ..Default::default() };
+        assert!(matches!(classify_outlier(&z, None), OutlierType::GrowthPremium));
     }
 
     #[test]
     fn test_classify_mixed() {
         // No condition met
-        let z = ZScores { price_z: 0.5, pe_z: None, pb_z: None, volume_z: None };
-        assert!(matches!(classify_outlier(&z), OutlierType::Mixed));
+        let z = ZScores { price_z: 0.5, pe_z: None, pb_z: None, volume_z: None, ..Default::default() };
+        assert!(matches!(classify_outlier(&z, None), OutlierType::Mixed));
     }
 
     #[test]
     fn test_classify_boundary_exactly_one_not_triggered() {
         // pe_z = 1.0 uses strict >, so pe_high = false → Mixed
-        let z = ZScores { price_z: 0.0, pe_z: Some(1.0), pb_z: Some(1.0), volume_z: None };
-        assert!(matches!(classify_outlier(&z), OutlierType::Mixed));
+        let z = ZScores { price_z: 0.0, pe_z: Some(1.0), pb_z: Some(1.0), volume_z: None, ..Default::default() };
+        assert!(matches!(classify_outlier(&z, None), OutlierType::Mixed));
     }
 
     #[test]
     fn test_classify_boundary_exactly_neg_one_not_triggered() {
         // pe_z = -1.0 uses strict <, so pe_low = false → Mixed
-        let z = ZScores { price_z: 0.0, pe_z: Some(-1.0), pb_z: Some(-1.0), volume_z: None };
-        assert!(matches!(classify_outlier(&z), OutlierType::Mixed));
+        let z = ZScores { price_z: 0.0, pe_z: Some(-1.0), pb_z: Some(-1.0), volume_z: None, ..Default::default() };
+        assert!(matches!(classify_outlier(&z, None), OutlierType::Mixed));
+    }
+
+    #[test]
+    fn test_classify_yield_trap() {
+        // Yield far above peers while the price slides
+        let z = ZScores { price_z: -1.5, dividend_yield_z: Some(2.0), ..Default::default() };
+        assert!(matches!(classify_outlier(&z, None), OutlierType::YieldTrap));
+    }
+
+    #[test]
+    fn test_classify_yield_trap_on_weak_earnings() {
+        let z = ZScores { price_z: 0.0, dividend_yield_z: Some(2.0), eps_z: Some(-1.5), ..Default::default() };
+        assert!(matches!(classify_outlier(&z, None), OutlierType::YieldTrap));
+    }
+
+    #[test]
+    fn test_classify_high_yield_alone_is_not_a_trap() {
+        let z = ZScores { price_z: 0.5, dividend_yield_z: Some(2.0), ..Default::default() };
+        assert!(matches!(classify_outlier(&z, None), OutlierType::Mixed));
+    }
+
+    #[test]
+    fn test_classify_near_year_low_and_high() {
+        let z = ZScores { price_z: 0.5, ..Default::default() };
+        assert!(matches!(classify_outlier(&z, Some(4.0)), OutlierType::NearYearLow));
+        assert!(matches!(classify_outlier(&z, Some(95.0)), OutlierType::NearYearHigh));
+        assert!(matches!(classify_outlier(&z, Some(50.0)), OutlierType::Mixed));
+    }
+
+    #[test]
+    fn test_classify_valuation_signal_beats_range_position() {
+        let z = ZScores { price_z: 0.0, pe_z: Some(-2.0), pb_z: Some(-2.0), ..Default::default() };
+        assert!(matches!(classify_outlier(&z, Some(2.0)), OutlierType::Undervalued));
+    }
+
+    // ---- 52-week position and extended metrics ----
+
+    #[test]
+    fn test_week52_position() {
+        assert!(approx_eq(week52_position(75.0, 50.0, 150.0).unwrap(), 25.0));
+        // Stale range: price outside it is clamped
+        assert!(approx_eq(week52_position(160.0, 50.0, 150.0).unwrap(), 100.0));
+        assert!(week52_position(50.0, 50.0, 50.0).is_none());
+    }
+
+    #[test]
+    fn test_extended_metric_z_scores() {
+        let row = |id: i32, dy: f64, beta: f64, low: f64| StockMarketRow {
+            dividend_yield: Some(dy),
+            beta: Some(beta),
+            eps: Some(5.0),
+            week52_low: Some(low),
+            week52_high: Some(low + 100.0),
+            ..make_row(id, "S", 0.0, None, None, None, None)
+        };
+        // Price is 100 for every row, so range positions are 50, 25 and 0
+        let rows = vec![row(1, 0.01, 0.8, 50.0), row(2, 0.02, 1.0, 75.0), row(3, 0.03, 1.2, 100.0)];
+        let stats = calculate_stats(&rows, &StatsOptions::default());
+        let z = calculate_z_scores(&rows[2], &stats);
+
+        assert!(approx_eq(z.dividend_yield_z.unwrap(), 1.0));
+        assert!(approx_eq(z.beta_z.unwrap(), 1.0));
+        assert!(approx_eq(z.range_position_z.unwrap(), -1.0));
+        // Identical EPS across peers carries no signal
+        assert!(z.eps_z.is_none());
+    }
+
+    #[test]
+    fn test_default_weights_ignore_extended_metrics() {
+        let z = ZScores { price_z: 2.0, beta_z: Some(9.0), range_position_z: Some(9.0), ..Default::default() };
+        assert!(approx_eq(calculate_composite_score(&z, &DEFAULT_WEIGHTS), 2.0));
     }
 
     // ---- classify_significance ----
@@ -738,11 +1289,11 @@ mod tests {
             .collect();
 
         let start = Instant::now();
-        let stats = calculate_stats(&rows);
+        let stats = calculate_stats(&rows, &StatsOptions::default());
         for row in &rows {
             let z = calculate_z_scores(row, &stats);
-            let composite = calculate_composite_score(&z);
-            let _ = classify_outlier(&z);
+            let composite = calculate_composite_score(&z, &DEFAULT_WEIGHTS);
+            let _ = classify_outlier(&z, None);
             let _ = classify_significance(composite);
         }
         let elapsed = start.elapsed();
@@ -753,4 +1304,82 @@ mod tests {
             elapsed.as_millis()
         );
     }
+
+    // ---- replay ----
+
+    /// Migrated in-memory database with a quote for every S&P 500 member of
+    /// the first sector, one of them far from its peers.
+    async fn pool_with_quotes() -> (SqlitePool, PeerGroup) {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::database::run_migrations(&pool).await.unwrap();
+        let sector = gics::peer_groups(&pool, GicsLevel::Sector)
+            .await
+            .unwrap()
+            .remove(0);
+        let members: Vec<i32> = sqlx::query_scalar(
+            "SELECT s.id FROM stocks s
+             JOIN stock_universe su ON su.stock_id = s.id AND su.universe_type = 'sp500'
+             WHERE s.sector_id = ? ORDER BY s.id",
+        )
+        .bind(sector.id)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        for (i, id) in members.iter().enumerate() {
+            let change = if i == 0 { 25.0 } else { (i % 5) as f64 * 0.1 };
+            sqlx::query(
+                "INSERT INTO market_data
+                    (stock_id, price, price_change, price_change_percent, pe_ratio)
+                 VALUES (?, 100.0, ?, ?, 20.0)",
+            )
+            .bind(id)
+            .bind(change)
+            .bind(change)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        (pool, sector)
+    }
+
+    /// Rows in (outlier_detections, detection_runs).
+    async fn row_counts(pool: &SqlitePool) -> (i64, i64) {
+        sqlx::query_as(
+            "SELECT (SELECT COUNT(*) FROM outlier_detections),
+                    (SELECT COUNT(*) FROM detection_runs)",
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_replay_writes_nothing() {
+        let (pool, sector) = pool_with_quotes().await;
+        let params = DetectionParams {
+            threshold: 1.0,
+            weights: DEFAULT_WEIGHTS,
+            stats: StatsOptions::default(),
+            horizon: ReturnHorizon::OneDay,
+        };
+        let replay =
+            |pool| detect_group_outliers(pool, &sector, &params, "sp500", Some(Utc::now()));
+
+        let replayed = replay(&pool).await.unwrap();
+        assert!(!replayed.outliers.is_empty());
+        assert_eq!(row_counts(&pool).await, (0, 0));
+
+        let live = detect_group_outliers(&pool, &sector, &params, "sp500", None)
+            .await
+            .unwrap();
+        let after_live = row_counts(&pool).await;
+        assert_eq!(after_live.1, 1);
+        let replayed = replay(&pool).await.unwrap();
+        assert_eq!(row_counts(&pool).await, after_live);
+        assert_eq!(replayed.outliers.len(), live.outliers.len());
+    }
 }
@@ -0,0 +1,424 @@
+use crate::detection_runs;
+use crate::gics::PeerGroup;
+use crate::market_hours;
+use crate::outlier_detection::DetectionParams;
+use crate::types::{EpisodeStatus, GicsLevel, OutlierEpisode, OutlierStock};
+use chrono::{Duration, NaiveDate};
+use sqlx::sqlite::SqlitePool;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+/// Days in a row after which a flag counts as persistent rather than a blip.
+pub const PERSISTENT_DAYS: u32 = 5;
+
+#[derive(Debug, sqlx::FromRow)]
+struct DetectionRow {
+    stock_id: i32,
+    symbol: String,
+    sector_id: i32,
+    group_id: i32,
+    detection_date: String,
+    composite_score: f64,
+    outlier_type: String,
+    /// Scoring parameters of the run that wrote the detection.
+    params: String,
+}
+
+/// Episodes for `universe` among the detections scored at `level` that were
+/// still running on or after `since`, longest-running first. Narrowed to one
+/// sector, or one group at `level`, when given. Detections after `until` are
+/// ignored, so a replayed day sees its own past.
+///
+/// Only detections from runs with `params` (threshold and scoring) count, so
+/// a streak isn't extended or broken by detection run another way. Detections
+/// from before runs were recorded have no parameters and are left out.
+#[allow(clippy::too_many_arguments)]
+pub async fn outlier_episodes(
+    pool: &SqlitePool,
+    universe: &str,
+    params: &DetectionParams,
+    level: GicsLevel,
+    sector_id: Option<i32>,
+    group_id: Option<i32>,
+    since: NaiveDate,
+    until: NaiveDate,
+) -> Result<Vec<OutlierEpisode>, String> {
+    // Reach back a year so an episode already running at `since` is reported
+    // from its real start.
+    let rows: Vec<DetectionRow> = sqlx::query_as(
+        "SELECT od.stock_id, s.symbol, od.sector_id, od.group_id, od.detection_date,
+                od.composite_score, od.outlier_type, dr.params
+         FROM outlier_detections od
+         JOIN stocks s ON s.id = od.stock_id
+         JOIN detection_runs dr ON dr.id = od.run_id AND dr.threshold = ?
+         WHERE od.universe_type = ? AND od.gics_level = ?
+           AND (? IS NULL OR od.sector_id = ?)
+           AND (? IS NULL OR od.group_id = ?)
+           AND od.detection_date >= ? AND od.detection_date <= ?
+         ORDER BY od.stock_id, od.detection_date, od.detection_timestamp, od.id",
+    )
+    .bind(params.threshold)
+    .bind(universe)
+    .bind(level.as_str())
+    .bind(sector_id)
+    .bind(sector_id)
+    .bind(group_id)
+    .bind(group_id)
+    .bind((since - Duration::days(365)).to_string())
+    .bind(until.to_string())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch outlier detections: {e}"))?;
+
+    // Below the sector the key names the group, so it's checked row by row
+    let mut keys: HashMap<i32, String> = HashMap::new();
+    let mut matching = Vec::with_capacity(rows.len());
+    for row in rows {
+        let key = match keys.entry(row.group_id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                entry.insert(detection_runs::params_key(params, level, row.group_id)?)
+            }
+        };
+        if row.params == *key {
+            matching.push(row);
+        }
+    }
+
+    let since = since.to_string();
+    let mut episodes: Vec<OutlierEpisode> = collapse(&matching)
+        .into_iter()
+        .filter(|e| e.last_seen >= since)
+        .collect();
+    episodes.sort_by(|a, b| {
+        b.last_seen
+            .cmp(&a.last_seen)
+            .then(b.consecutive_days.cmp(&a.consecutive_days))
+            .then(a.symbol.cmp(&b.symbol))
+    });
+    Ok(episodes)
+}
+
+/// Annotate today's outliers in a peer group with their streaks, and pick out
+/// the stocks from `candidates` that were flagged by the group's previous run
+/// and no longer are.
+///
+/// Only detections from before `today` are read, so the result is the same
+/// whether or not today's were saved, as when replaying a past day.
+pub async fn track_group(
+    pool: &SqlitePool,
+    universe: &str,
+    group: &PeerGroup,
+    params: &DetectionParams,
+    today: NaiveDate,
+    outliers: &mut [OutlierStock],
+    candidates: Vec<OutlierStock>,
+) -> Result<Vec<OutlierStock>, String> {
+    // A run that flagged nothing still counts, so a stock resolves only once
+    let previous = detection_runs::previous_run_date(pool, universe, group, params, today).await?;
+    let episodes = match previous {
+        Some(previous) => {
+            outlier_episodes(
+                pool,
+                universe,
+                params,
+                group.level,
+                None,
+                Some(group.id),
+                previous,
+                previous,
+            )
+            .await?
+        }
+        None => Vec::new(),
+    };
+    let previous_day = previous.map(|d| d.to_string());
+    let ended: HashMap<i32, &OutlierEpisode> = episodes
+        .iter()
+        .filter(|e| Some(&e.last_seen) == previous_day.as_ref())
+        .map(|e| (e.stock_id, e))
+        .collect();
+    // Today's outliers extend the runs still open at the previous session
+    let continuing = previous.is_some_and(|d| is_next_session(d, today));
+
+    for outlier in outliers.iter_mut() {
+        let (streak_days, first_seen) = match ended.get(&outlier.stock_id) {
+            Some(episode) if continuing => {
+                (episode.consecutive_days + 1, episode.first_seen.clone())
+            }
+            _ => (1, today.to_string()),
+        };
+        outlier.streak_days = streak_days;
+        outlier.first_seen = Some(first_seen);
+        outlier.status = status(streak_days);
+    }
+
+    Ok(candidates
+        .into_iter()
+        .filter_map(|mut stock| {
+            let episode = ended.get(&stock.stock_id)?;
+            stock.status = EpisodeStatus::Resolved;
+            stock.streak_days = episode.consecutive_days;
+            stock.first_seen = Some(episode.first_seen.clone());
+            Some(stock)
+        })
+        .collect())
+}
+
+fn status(consecutive_days: u32) -> EpisodeStatus {
+    match consecutive_days {
+        0 | 1 => EpisodeStatus::New,
+        d if d >= PERSISTENT_DAYS => EpisodeStatus::Persistent,
+        _ => EpisodeStatus::Continuing,
+    }
+}
+
+/// Whether `later` follows `earlier` without a trading day in between.
+pub fn is_next_session(earlier: NaiveDate, later: NaiveDate) -> bool {
+    let mut day = earlier + Duration::days(1);
+    while day < later {
+        if market_hours::is_trading_day(day) {
+            return false;
+        }
+        day += Duration::days(1);
+    }
+    later > earlier
+}
+
+/// Collapse raw detections into episodes. `rows` must be ordered by stock,
+/// then date and time.
+fn collapse(rows: &[DetectionRow]) -> Vec<OutlierEpisode> {
+    let mut episodes: Vec<OutlierEpisode> = Vec::new();
+    let mut open: Option<(OutlierEpisode, NaiveDate)> = None;
+
+    for row in rows {
+        let date = match NaiveDate::parse_from_str(&row.detection_date, "%Y-%m-%d") {
+            Ok(d) => d,
+            Err(_) => continue,
+        };
+
+        if let Some((episode, last)) = open.as_mut() {
+            if episode.stock_id == row.stock_id && (date == *last || is_next_session(*last, date)) {
+                if date != *last {
+                    episode.consecutive_days += 1;
+                    episode.last_seen = row.detection_date.clone();
+                    *last = date;
+                }
+                if episode.outlier_type != row.outlier_type {
+                    episode.type_changes += 1;
+                    episode.outlier_type = row.outlier_type.clone();
+                }
+                episode.peak_composite_score = episode.peak_composite_score.max(row.composite_score);
+                continue;
+            }
+        }
+
+        if let Some((episode, _)) = open.take() {
+            episodes.push(episode);
+        }
+        open = Some((
+            OutlierEpisode {
+                stock_id: row.stock_id,
+                symbol: row.symbol.clone(),
+                sector_id: row.sector_id,
+                first_seen: row.detection_date.clone(),
+                last_seen: row.detection_date.clone(),
+                consecutive_days: 1,
+                peak_composite_score: row.composite_score,
+                outlier_type: row.outlier_type.clone(),
+                type_changes: 0,
+            },
+            date,
+        ));
+    }
+
+    episodes.extend(open.map(|(episode, _)| episode));
+    episodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detection_runs::RunInputs;
+    use crate::outlier_detection::DEFAULT_WEIGHTS;
+    use crate::types::{OutlierType, ReturnHorizon, SignificanceLevel, StatsOptions, ZScores};
+
+    fn d(y: i32, m: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, day).unwrap()
+    }
+
+    fn row(stock_id: i32, date: &str, score: f64, outlier_type: &str) -> DetectionRow {
+        DetectionRow {
+            stock_id,
+            symbol: format!("S{stock_id}"),
+            sector_id: 1,
+            group_id: 1,
+            detection_date: date.to_string(),
+            composite_score: score,
+            outlier_type: outlier_type.to_string(),
+            params: "{}".to_string(),
+        }
+    }
+
+    // ---- is_next_session ----
+
+    #[test]
+    fn test_next_session_skips_weekend_and_holiday() {
+        // Fri → Mon
+        assert!(is_next_session(d(2025, 6, 6), d(2025, 6, 9)));
+        // Thu before Good Friday → Mon
+        assert!(is_next_session(d(2025, 4, 17), d(2025, 4, 21)));
+        // Mon → Wed misses Tue
+        assert!(!is_next_session(d(2025, 6, 9), d(2025, 6, 11)));
+        assert!(!is_next_session(d(2025, 6, 9), d(2025, 6, 9)));
+    }
+
+    // ---- collapse ----
+
+    #[test]
+    fn test_collapse_merges_consecutive_sessions() {
+        let rows = [
+            row(1, "2025-06-05", 2.0, "Undervalued"),
+            row(1, "2025-06-06", 2.6, "Undervalued"),
+            row(1, "2025-06-06", 2.4, "Undervalued"),
+            row(1, "2025-06-09", 2.1, "ValueTrap"),
+        ];
+        let episodes = collapse(&rows);
+        assert_eq!(episodes.len(), 1);
+        let e = &episodes[0];
+        assert_eq!((e.first_seen.as_str(), e.last_seen.as_str()), ("2025-06-05", "2025-06-09"));
+        assert_eq!(e.consecutive_days, 3);
+        assert_eq!(e.peak_composite_score, 2.6);
+        assert_eq!(e.outlier_type, "ValueTrap");
+        assert_eq!(e.type_changes, 1);
+    }
+
+    #[test]
+    fn test_collapse_splits_on_missed_session() {
+        let rows = [
+            row(1, "2025-06-09", 2.0, "Momentum"),
+            row(1, "2025-06-11", 2.0, "Momentum"),
+        ];
+        let episodes = collapse(&rows);
+        assert_eq!(episodes.len(), 2);
+        assert!(episodes.iter().all(|e| e.consecutive_days == 1));
+    }
+
+    #[test]
+    fn test_collapse_keeps_stocks_apart() {
+        let rows = [
+            row(1, "2025-06-09", 2.0, "Momentum"),
+            row(2, "2025-06-10", 2.0, "Momentum"),
+        ];
+        let episodes = collapse(&rows);
+        assert_eq!(episodes.len(), 2);
+        assert_eq!(episodes[1].stock_id, 2);
+    }
+
+    #[test]
+    fn test_collapse_skips_unparseable_dates() {
+        assert!(collapse(&[row(1, "yesterday", 2.0, "Mixed")]).is_empty());
+    }
+
+    // ---- status ----
+
+    #[test]
+    fn test_status_by_streak_length() {
+        assert_eq!(status(1), EpisodeStatus::New);
+        assert_eq!(status(2), EpisodeStatus::Continuing);
+        assert_eq!(status(PERSISTENT_DAYS), EpisodeStatus::Persistent);
+    }
+
+    // ---- track_group ----
+
+    fn flagged(stock_id: i32) -> OutlierStock {
+        OutlierStock {
+            stock_id,
+            symbol: format!("S{stock_id}"),
+            name: String::new(),
+            z_scores: ZScores::default(),
+            week52_position: None,
+            excess_return_1d_percent: None,
+            composite_score: 2.5,
+            outlier_type: OutlierType::Momentum,
+            significance_level: SignificanceLevel::Strong,
+            status: EpisodeStatus::New,
+            streak_days: 1,
+            first_seen: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolved_only_after_the_previous_run() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::database::run_migrations(&pool).await.unwrap();
+        let group = crate::gics::peer_groups(&pool, GicsLevel::Sector)
+            .await
+            .unwrap()
+            .remove(0);
+        let stock_id: i32 = sqlx::query_scalar("SELECT id FROM stocks WHERE sector_id = ?")
+            .bind(group.sector_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let params = DetectionParams {
+            threshold: 2.0,
+            weights: DEFAULT_WEIGHTS,
+            stats: StatsOptions::default(),
+            horizon: ReturnHorizon::OneDay,
+        };
+        let record = |date: NaiveDate, outliers: Vec<OutlierStock>| {
+            let pool = &pool;
+            let group = &group;
+            let params = &params;
+            async move {
+                let inputs = RunInputs::new(&format!("{date} 20:00:00"), vec![date.to_string()]);
+                detection_runs::record_run(pool, "sp500", group, params, &inputs, date, &outliers)
+                    .await
+                    .unwrap();
+            }
+        };
+        // Flagged Monday, not by Tuesday's run
+        record(d(2025, 6, 2), vec![flagged(stock_id)]).await;
+        record(d(2025, 6, 3), Vec::new()).await;
+
+        let resolved = |today: NaiveDate| {
+            let (pool, group, params) = (&pool, &group, &params);
+            async move {
+                track_group(
+                    pool,
+                    "sp500",
+                    group,
+                    params,
+                    today,
+                    &mut [],
+                    vec![flagged(stock_id)],
+                )
+                .await
+                .unwrap()
+                .len()
+            }
+        };
+        assert_eq!(resolved(d(2025, 6, 3)).await, 1);
+        assert_eq!(resolved(d(2025, 6, 4)).await, 0);
+
+        // Flagged again Wednesday after the gap, so a new streak
+        let mut today = [flagged(stock_id)];
+        track_group(
+            &pool,
+            "sp500",
+            &group,
+            &params,
+            d(2025, 6, 4),
+            &mut today,
+            Vec::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(today[0].streak_days, 1);
+    }
+}
@@ -0,0 +1,392 @@
+use crate::market_data::DailyBar;
+use crate::provider::MarketDataProvider;
+use chrono::{Duration, NaiveDate};
+use sqlx::sqlite::SqlitePool;
+
+/// Longest run of calendar days between two consecutive trading days that we
+/// treat as normal (a long weekend plus a holiday). Anything wider is a gap.
+const MAX_TRADING_GAP_DAYS: i64 = 5;
+
+/// Relative change in a complete stored bar's close beyond which the provider
+/// is taken to have re-adjusted the series for a split or dividend.
+const ADJUSTMENT_TOLERANCE: f64 = 1e-5;
+
+/// A stock to backfill, as selected from `stock_universe`.
+#[derive(Debug, sqlx::FromRow)]
+pub struct BackfillTarget {
+    pub stock_id: i32,
+    pub symbol: String,
+}
+
+/// Work out which date ranges still need fetching for one stock.
+///
+/// `stored` must be sorted ascending. `history_start` is the earliest date we
+/// have already asked the provider for (if any), so stocks that listed after
+/// the target start aren't re-requested on every run. `empty` holds interior
+/// ranges the provider has already returned nothing for, which are skipped. The
+/// two most recent stored bars are always re-fetched: the last may have been a
+/// partial intraday bar, and the one before is a complete bar to check
+/// adjustments against.
+fn plan_backfill(
+    stored: &[NaiveDate],
+    history_start: Option<NaiveDate>,
+    empty: &[(NaiveDate, NaiveDate)],
+    target_start: NaiveDate,
+    today: NaiveDate,
+) -> Vec<(NaiveDate, NaiveDate)> {
+    let (first, last) = match (stored.first(), stored.last()) {
+        (Some(&f), Some(&l)) => (f, l),
+        _ => return vec![(target_start, today)],
+    };
+
+    let mut ranges = Vec::new();
+
+    // Head: extend further back than we've ever asked for
+    let requested_from = history_start.map_or(first, |h| h.min(first));
+    if target_start < requested_from {
+        ranges.push((target_start, requested_from - Duration::days(1)));
+    }
+
+    // Interior: holes wider than a holiday weekend that haven't come back empty
+    for pair in stored.windows(2) {
+        if (pair[1] - pair[0]).num_days() > MAX_TRADING_GAP_DAYS {
+            let gap = (pair[0] + Duration::days(1), pair[1] - Duration::days(1));
+            if !empty.iter().any(|&(from, to)| from <= gap.0 && to >= gap.1) {
+                ranges.push(gap);
+            }
+        }
+    }
+
+    // Tail: from the bar before the last stored one (inclusive) up to today
+    let tail_from = stored.len().checked_sub(2).map_or(last, |i| stored[i]);
+    if last <= today {
+        ranges.push((tail_from, today));
+    }
+
+    ranges
+}
+
+/// Whether a re-fetched copy of a complete stored bar (`close`, `adj_close`)
+/// differs from it, i.e. a split or dividend since has re-adjusted history.
+fn readjusted(stored: (f64, f64), fetched: &DailyBar) -> bool {
+    let moved = |old: f64, new: f64| (new - old).abs() > old.abs() * ADJUSTMENT_TOLERANCE;
+    moved(stored.0, fetched.close) || moved(stored.1, fetched.adj_close)
+}
+
+/// Stocks to backfill: current members of `universe`, or of any universe when `None`.
+pub async fn backfill_targets(
+    pool: &SqlitePool,
+    universe: Option<&str>,
+) -> Result<Vec<BackfillTarget>, String> {
+    sqlx::query_as(
+        "SELECT DISTINCT s.id as stock_id, s.symbol
+         FROM stocks s
+         JOIN stock_universe su ON su.stock_id = s.id
+         WHERE su.date_removed IS NULL
+           AND (? IS NULL OR su.universe_type = ?)
+         ORDER BY s.symbol",
+    )
+    .bind(universe)
+    .bind(universe)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch backfill targets: {e}"))
+}
+
+/// Upsert daily bars for a stock. Returns the number of rows written.
+async fn save_bars(pool: &SqlitePool, stock_id: i32, bars: &[DailyBar]) -> Result<u32, String> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {e}"))?;
+
+    for bar in bars {
+        sqlx::query(
+            "INSERT INTO price_history (stock_id, date, open, high, low, close, adj_close, volume)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(stock_id, date) DO UPDATE SET
+                open = excluded.open,
+                high = excluded.high,
+                low = excluded.low,
+                close = excluded.close,
+                adj_close = excluded.adj_close,
+                volume = excluded.volume",
+        )
+        .bind(stock_id)
+        .bind(bar.date.to_string())
+        .bind(bar.open)
+        .bind(bar.high)
+        .bind(bar.low)
+        .bind(bar.close)
+        .bind(bar.adj_close)
+        .bind(bar.volume)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save bar {} for stock {stock_id}: {e}", bar.date))?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit price history: {e}"))?;
+
+    Ok(bars.len() as u32)
+}
+
+/// Backfill one stock up to `today`, going back to `target_start`. When the
+/// provider has re-adjusted bars we already hold, the whole stored range is
+/// fetched again so `adj_close` stays on one basis. Returns the number of bars
+/// written.
+pub async fn backfill_stock(
+    pool: &SqlitePool,
+    provider: &dyn MarketDataProvider,
+    target: &BackfillTarget,
+    target_start: NaiveDate,
+    today: NaiveDate,
+) -> Result<u32, String> {
+    let rows: Vec<(String, f64, f64)> = sqlx::query_as(
+        "SELECT date, close, adj_close FROM price_history WHERE stock_id = ? ORDER BY date",
+    )
+    .bind(target.stock_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to read history for {}: {e}", target.symbol))?;
+    let rows: Vec<(NaiveDate, f64, f64)> = rows
+        .into_iter()
+        .filter_map(|(d, close, adj_close)| Some((d.parse().ok()?, close, adj_close)))
+        .collect();
+    let stored: Vec<NaiveDate> = rows.iter().map(|r| r.0).collect();
+    // The latest bar that was complete when stored
+    let reference = rows.len().checked_sub(2).map(|i| rows[i]);
+
+    let history_start: Option<NaiveDate> = sqlx::query_scalar::<_, String>(
+        "SELECT history_start FROM price_history_status WHERE stock_id = ?",
+    )
+    .bind(target.stock_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to read backfill status for {}: {e}", target.symbol))?
+    .and_then(|d| d.parse().ok());
+
+    let empty: Vec<(String, String)> =
+        sqlx::query_as("SELECT start_date, end_date FROM price_history_gaps WHERE stock_id = ?")
+            .bind(target.stock_id)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| format!("Failed to read history gaps for {}: {e}", target.symbol))?;
+    let empty: Vec<(NaiveDate, NaiveDate)> = empty
+        .into_iter()
+        .filter_map(|(from, to)| Some((from.parse().ok()?, to.parse().ok()?)))
+        .collect();
+
+    let mut written = 0;
+    let mut stale = false;
+    for (start, end) in plan_backfill(&stored, history_start, &empty, target_start, today) {
+        let bars = provider.fetch_daily_bars(&target.symbol, start, end).await?;
+        // An interior hole the provider has nothing for stays a hole; don't ask again
+        let interior =
+            stored.first().is_some_and(|&f| start > f) && stored.last().is_some_and(|&l| end < l);
+        if bars.is_empty() && interior {
+            sqlx::query(
+                "INSERT OR REPLACE INTO price_history_gaps (stock_id, start_date, end_date)
+                 VALUES (?, ?, ?)",
+            )
+            .bind(target.stock_id)
+            .bind(start.to_string())
+            .bind(end.to_string())
+            .execute(pool)
+            .await
+            .map_err(|e| format!("Failed to save history gap for {}: {e}", target.symbol))?;
+        }
+        if let Some((date, close, adj_close)) = reference {
+            stale |= bars
+                .iter()
+                .any(|bar| bar.date == date && readjusted((close, adj_close), bar));
+        }
+        written += save_bars(pool, target.stock_id, &bars).await?;
+    }
+
+    if let (true, Some(&first)) = (stale, stored.first()) {
+        let bars = provider.fetch_daily_bars(&target.symbol, first, today).await?;
+        written += save_bars(pool, target.stock_id, &bars).await?;
+    }
+
+    // Remember how far back we've asked, so the head isn't re-requested next run
+    let requested_from = history_start.map_or(target_start, |h| h.min(target_start));
+    sqlx::query(
+        "INSERT INTO price_history_status (stock_id, history_start, last_backfill_at)
+         VALUES (?, ?, CURRENT_TIMESTAMP)
+         ON CONFLICT(stock_id) DO UPDATE SET
+            history_start = excluded.history_start,
+            last_backfill_at = excluded.last_backfill_at",
+    )
+    .bind(target.stock_id)
+    .bind(requested_from.to_string())
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save backfill status for {}: {e}", target.symbol))?;
+
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(y: i32, m: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, day).unwrap()
+    }
+
+    #[test]
+    fn test_plan_empty_history_fetches_full_range() {
+        let ranges = plan_backfill(&[], None, &[], d(2020, 1, 1), d(2025, 1, 1));
+        assert_eq!(ranges, vec![(d(2020, 1, 1), d(2025, 1, 1))]);
+    }
+
+    #[test]
+    fn test_plan_up_to_date_refetches_only_last_bar() {
+        let stored = [d(2024, 12, 30), d(2024, 12, 31)];
+        let ranges = plan_backfill(
+            &stored,
+            Some(d(2024, 12, 30)),
+            &[],
+            d(2024, 12, 30),
+            d(2024, 12, 31),
+        );
+        assert_eq!(ranges, vec![(d(2024, 12, 30), d(2024, 12, 31))]);
+    }
+
+    #[test]
+    fn test_plan_resumes_from_last_complete_bar() {
+        let stored = [d(2024, 6, 3), d(2024, 6, 4)];
+        let ranges = plan_backfill(
+            &stored,
+            Some(d(2024, 6, 1)),
+            &[],
+            d(2024, 6, 1),
+            d(2024, 6, 20),
+        );
+        assert_eq!(ranges, vec![(d(2024, 6, 3), d(2024, 6, 20))]);
+    }
+
+    #[test]
+    fn test_plan_single_bar_refetches_it() {
+        let stored = [d(2024, 6, 3)];
+        let ranges = plan_backfill(
+            &stored,
+            Some(d(2024, 6, 3)),
+            &[],
+            d(2024, 6, 3),
+            d(2024, 6, 5),
+        );
+        assert_eq!(ranges, vec![(d(2024, 6, 3), d(2024, 6, 5))]);
+    }
+
+    #[test]
+    fn test_plan_extends_head_when_target_moves_back() {
+        let stored = [d(2023, 1, 3), d(2023, 1, 4)];
+        let ranges = plan_backfill(
+            &stored,
+            Some(d(2023, 1, 1)),
+            &[],
+            d(2020, 1, 1),
+            d(2023, 1, 4),
+        );
+        assert_eq!(
+            ranges,
+            vec![
+                (d(2020, 1, 1), d(2022, 12, 31)),
+                (d(2023, 1, 3), d(2023, 1, 4))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_does_not_rerequest_head_for_late_listings() {
+        // Stock listed in 2022; we already asked from 2020 and got nothing earlier
+        let stored = [d(2022, 3, 1), d(2022, 3, 2)];
+        let ranges = plan_backfill(
+            &stored,
+            Some(d(2020, 1, 1)),
+            &[],
+            d(2020, 1, 1),
+            d(2022, 3, 2),
+        );
+        assert_eq!(ranges, vec![(d(2022, 3, 1), d(2022, 3, 2))]);
+    }
+
+    #[test]
+    fn test_plan_fills_interior_gap() {
+        let stored = [d(2024, 1, 2), d(2024, 1, 3), d(2024, 2, 1), d(2024, 2, 2)];
+        let ranges = plan_backfill(
+            &stored,
+            Some(d(2024, 1, 1)),
+            &[],
+            d(2024, 1, 1),
+            d(2024, 2, 2),
+        );
+        assert_eq!(
+            ranges,
+            vec![
+                (d(2024, 1, 4), d(2024, 1, 31)),
+                (d(2024, 2, 1), d(2024, 2, 2))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_ignores_holiday_weekend() {
+        // Thu 2024-03-28 → Mon 2024-04-01 (Good Friday + weekend): 4 days, not a gap
+        let stored = [d(2024, 3, 28), d(2024, 4, 1)];
+        let ranges = plan_backfill(
+            &stored,
+            Some(d(2024, 3, 28)),
+            &[],
+            d(2024, 3, 28),
+            d(2024, 4, 1),
+        );
+        assert_eq!(ranges, vec![(d(2024, 3, 28), d(2024, 4, 1))]);
+    }
+
+    #[test]
+    fn test_plan_skips_gap_that_came_back_empty() {
+        // Trading halted through most of January; the provider had nothing for it
+        let stored = [d(2024, 1, 2), d(2024, 1, 3), d(2024, 2, 1), d(2024, 2, 2)];
+        let empty = [(d(2024, 1, 4), d(2024, 1, 31))];
+        let ranges = plan_backfill(
+            &stored,
+            Some(d(2024, 1, 1)),
+            &empty,
+            d(2024, 1, 1),
+            d(2024, 2, 2),
+        );
+        assert_eq!(ranges, vec![(d(2024, 2, 1), d(2024, 2, 2))]);
+    }
+
+    fn bar(close: f64, adj_close: f64) -> DailyBar {
+        DailyBar {
+            date: d(2024, 6, 3),
+            open: None,
+            high: None,
+            low: None,
+            close,
+            adj_close,
+            volume: None,
+        }
+    }
+
+    #[test]
+    fn test_readjusted_unchanged_bar() {
+        assert!(!readjusted((100.0, 98.5), &bar(100.0, 98.5)));
+    }
+
+    #[test]
+    fn test_readjusted_after_dividend() {
+        // Only the adjusted close moves when a dividend goes ex
+        assert!(readjusted((100.0, 98.5), &bar(100.0, 98.1)));
+    }
+
+    #[test]
+    fn test_readjusted_after_split() {
+        assert!(readjusted((100.0, 98.5), &bar(25.0, 24.625)));
+    }
+}
@@ -0,0 +1,179 @@
+use crate::alpha_vantage::AlphaVantageProvider;
+use crate::market_data::{BatchQuote, DailyBar, Fundamentals, PriceQuote, YahooSession};
+use crate::settings;
+use crate::types::AppSettings;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use sqlx::sqlite::SqlitePool;
+
+/// Settings key holding the preferred provider name (see `ProviderKind::as_str`).
+pub const PROVIDER_SETTING: &str = "market_data_provider";
+/// Settings key holding the Alpha Vantage API key.
+pub const ALPHA_VANTAGE_KEY_SETTING: &str = "alpha_vantage_api_key";
+
+/// A source of quotes, fundamentals and sector classifications.
+///
+/// A provider is connected once per refresh cycle (any cookie/crumb/API-key
+/// handshake happens up front) and then shared across every symbol fetch.
+#[async_trait]
+pub trait MarketDataProvider: Send + Sync {
+    /// Short identifier used in settings and log output.
+    fn name(&self) -> &'static str;
+
+    /// Latest price, previous close and session volume.
+    async fn fetch_quote(&self, symbol: &str) -> Result<PriceQuote, String>;
+
+    /// Valuation and risk metrics. Failures degrade to `Fundamentals::default()`
+    /// so a missing P/E never costs us the price data.
+    async fn fetch_fundamentals(&self, symbol: &str) -> Fundamentals;
+
+    /// Quotes and fundamentals for many symbols in as few requests as possible.
+    /// Symbols missing from the result are fetched one at a time by the caller,
+    /// so providers without a batch endpoint simply return nothing.
+    async fn fetch_quote_batch(&self, _symbols: &[String]) -> Vec<BatchQuote> {
+        Vec::new()
+    }
+
+    /// Sector label in Yahoo Finance vocabulary, for `map_yahoo_sector_to_db`.
+    async fn fetch_sector(&self, symbol: &str) -> Option<String> {
+        self.fetch_fundamentals(symbol).await.sector
+    }
+
+    /// Daily OHLCV bars for `start..=end`, oldest first.
+    async fn fetch_daily_bars(
+        &self,
+        symbol: &str,
+        _start: NaiveDate,
+        _end: NaiveDate,
+    ) -> Result<Vec<DailyBar>, String> {
+        Err(format!("{} does not provide price history ({symbol})", self.name()))
+    }
+}
+
+/// The market data providers we know how to connect to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderKind {
+    Yahoo,
+    AlphaVantage,
+}
+
+impl ProviderKind {
+    const ALL: [ProviderKind; 2] = [ProviderKind::Yahoo, ProviderKind::AlphaVantage];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "yahoo" => Some(ProviderKind::Yahoo),
+            "alpha_vantage" | "alphavantage" => Some(ProviderKind::AlphaVantage),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProviderKind::Yahoo => "yahoo",
+            ProviderKind::AlphaVantage => "alpha_vantage",
+        }
+    }
+}
+
+/// Order in which providers are tried: the preferred one first, then the rest.
+fn fallback_order(preferred: ProviderKind) -> Vec<ProviderKind> {
+    std::iter::once(preferred)
+        .chain(ProviderKind::ALL.into_iter().filter(|k| *k != preferred))
+        .collect()
+}
+
+/// Connect to a single provider, throttled to the configured request rate.
+async fn connect(
+    kind: ProviderKind,
+    alpha_vantage_key: Option<&str>,
+    config: &AppSettings,
+) -> Result<Box<dyn MarketDataProvider>, String> {
+    match kind {
+        ProviderKind::Yahoo => {
+            let session = YahooSession::new(config.yahoo_requests_per_second)
+                .await
+                .map_err(|e| format!("Yahoo Finance auth failed: {e}"))?;
+            Ok(Box::new(session))
+        }
+        ProviderKind::AlphaVantage => {
+            let key = alpha_vantage_key
+                .filter(|k| !k.trim().is_empty())
+                .ok_or_else(|| "Alpha Vantage API key not configured".to_string())?;
+            Ok(Box::new(AlphaVantageProvider::new(
+                key,
+                config.alpha_vantage_requests_per_minute,
+            )?))
+        }
+    }
+}
+
+/// Connect to the provider selected in settings, falling back to the others
+/// if it can't be reached (e.g. Yahoo rejects the crumb handshake).
+pub async fn connect_provider(pool: &SqlitePool) -> Result<Box<dyn MarketDataProvider>, String> {
+    let config = settings::load_app_settings(pool).await?;
+    let preferred =
+        ProviderKind::parse(&config.market_data_provider).unwrap_or(ProviderKind::Yahoo);
+    let alpha_vantage_key = settings::get_setting(pool, ALPHA_VANTAGE_KEY_SETTING).await?;
+
+    let mut errors = Vec::new();
+    for kind in fallback_order(preferred) {
+        match connect(kind, alpha_vantage_key.as_deref(), &config).await {
+            Ok(provider) => {
+                if kind != preferred {
+                    eprintln!(
+                        "Preferred provider '{}' unavailable, falling back to '{}'",
+                        preferred.as_str(),
+                        kind.as_str()
+                    );
+                }
+                return Ok(provider);
+            }
+            Err(e) => errors.push(format!("{}: {e}", kind.as_str())),
+        }
+    }
+
+    Err(format!("No market data provider available ({})", errors.join("; ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_known_providers() {
+        assert_eq!(ProviderKind::parse("yahoo"), Some(ProviderKind::Yahoo));
+        assert_eq!(ProviderKind::parse("alpha_vantage"), Some(ProviderKind::AlphaVantage));
+        assert_eq!(ProviderKind::parse("AlphaVantage"), Some(ProviderKind::AlphaVantage));
+    }
+
+    #[test]
+    fn test_parse_trims_and_ignores_case() {
+        assert_eq!(ProviderKind::parse("  Yahoo "), Some(ProviderKind::Yahoo));
+    }
+
+    #[test]
+    fn test_parse_unknown_provider_returns_none() {
+        assert_eq!(ProviderKind::parse("polygon"), None);
+        assert_eq!(ProviderKind::parse(""), None);
+    }
+
+    #[test]
+    fn test_as_str_round_trips_through_parse() {
+        for kind in ProviderKind::ALL {
+            assert_eq!(ProviderKind::parse(kind.as_str()), Some(kind));
+        }
+    }
+
+    #[test]
+    fn test_fallback_order_starts_with_preferred() {
+        assert_eq!(
+            fallback_order(ProviderKind::AlphaVantage),
+            vec![ProviderKind::AlphaVantage, ProviderKind::Yahoo]
+        );
+        assert_eq!(
+            fallback_order(ProviderKind::Yahoo),
+            vec![ProviderKind::Yahoo, ProviderKind::AlphaVantage]
+        );
+    }
+}
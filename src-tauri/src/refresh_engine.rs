@@ -0,0 +1,291 @@
+use crate::jobs::JobToken;
+use crate::market_data::{self, BatchQuote, StockQuote};
+use crate::price_history::{self, BackfillTarget};
+use crate::provider::MarketDataProvider;
+use crate::types::{BackfillResult, Stock};
+use chrono::NaiveDate;
+use futures::stream::{FuturesUnordered, StreamExt};
+use sqlx::sqlite::SqlitePool;
+use std::collections::HashMap;
+
+/// Default number of stocks fetched concurrently. Request pacing is left to the
+/// provider's token bucket, so this only bounds how many are in flight.
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// Per-run accounting for a quote refresh.
+#[derive(Debug, Default)]
+pub struct RefreshStats {
+    pub succeeded: u32,
+    pub failed: u32,
+    /// Stocks never attempted because the job was cancelled.
+    pub skipped: u32,
+    /// Provider sector labels for refreshed stocks that have no sector yet.
+    pub unclassified_sectors: Vec<(i32, String)>,
+}
+
+/// Quote one stock, preferring its entry from the batch response. Batch quotes
+/// carry no sector, so stocks that still need one get a separate sector lookup.
+/// They carry no beta either, so the last stored one is kept.
+async fn quote_stock(
+    pool: &SqlitePool,
+    provider: &dyn MarketDataProvider,
+    stock: &Stock,
+    batched: Option<&BatchQuote>,
+) -> Result<StockQuote, String> {
+    let batched = match batched {
+        Some(b) => b,
+        None => return market_data::fetch_stock_quote(provider, stock.id, &stock.symbol).await,
+    };
+
+    let mut fundamentals = batched.fundamentals.clone();
+    if fundamentals.beta.is_none() {
+        fundamentals.beta = market_data::stored_beta(pool, stock.id).await?;
+    }
+    if stock.sector_id.is_none() {
+        fundamentals.sector = provider.fetch_sector(&stock.symbol).await;
+    }
+    Ok(market_data::build_stock_quote(stock.id, batched.quote.clone(), fundamentals))
+}
+
+/// How one stock's refresh ended.
+enum Outcome {
+    /// Saved; carries the provider's sector label when one was fetched.
+    Saved(Option<String>),
+    Failed(String),
+    Skipped,
+}
+
+/// Quote and save one stock, unless the job is cancelled before it starts.
+async fn refresh_one<'a>(
+    pool: &SqlitePool,
+    provider: &dyn MarketDataProvider,
+    stock: &'a Stock,
+    batched: Option<&BatchQuote>,
+    job: &JobToken,
+) -> (&'a Stock, Outcome) {
+    if job.checkpoint().await.is_err() {
+        return (stock, Outcome::Skipped);
+    }
+    let outcome = match quote_stock(pool, provider, stock, batched).await {
+        Ok(quote) => match market_data::save_quote(pool, &quote).await {
+            Ok(()) => Outcome::Saved(quote.yahoo_sector),
+            Err(e) => Outcome::Failed(format!("Failed to save {}: {e}", stock.symbol)),
+        },
+        Err(e) => Outcome::Failed(format!("Failed to fetch {}: {e}", stock.symbol)),
+    };
+    (stock, outcome)
+}
+
+/// Fetch and save the latest quote for every stock, at most `concurrency` at a time.
+///
+/// Quotes come from the provider's batch endpoint where possible; only symbols
+/// missing from the batch response fall back to per-symbol requests.
+/// `on_progress(done, total)` fires as each stock finishes, in completion order.
+/// Failures are logged and counted; they never abort the rest of the run.
+/// Each stock waits at a `job` checkpoint first, so pausing holds back new
+/// fetches and cancelling skips everything not yet started.
+pub async fn refresh_quotes(
+    pool: &SqlitePool,
+    provider: &dyn MarketDataProvider,
+    stocks: &[Stock],
+    concurrency: usize,
+    job: &JobToken,
+    on_progress: impl Fn(u32, u32),
+) -> RefreshStats {
+    let total = stocks.len() as u32;
+    let mut stats = RefreshStats::default();
+    let mut done = 0;
+
+    if job.checkpoint().await.is_err() {
+        stats.skipped = total;
+        return stats;
+    }
+
+    let symbols: Vec<String> = stocks.iter().map(|s| s.symbol.clone()).collect();
+    let batched: HashMap<String, BatchQuote> = provider
+        .fetch_quote_batch(&symbols)
+        .await
+        .into_iter()
+        .map(|b| (b.symbol.to_uppercase(), b))
+        .collect();
+    if !batched.is_empty() {
+        println!("Batch quotes: {}/{} symbols, rest fetched individually", batched.len(), total);
+    }
+
+    // Keep at most `concurrency` stocks in flight, topping up as each one finishes
+    let mut pending = stocks.iter();
+    let mut in_flight = FuturesUnordered::new();
+    for stock in pending.by_ref().take(concurrency.max(1)) {
+        let entry = batched.get(&stock.symbol.to_uppercase());
+        in_flight.push(refresh_one(pool, provider, stock, entry, job));
+    }
+
+    while let Some((stock, outcome)) = in_flight.next().await {
+        if let Some(next) = pending.next() {
+            let entry = batched.get(&next.symbol.to_uppercase());
+            in_flight.push(refresh_one(pool, provider, next, entry, job));
+        }
+        done += 1;
+        on_progress(done, total);
+
+        match outcome {
+            Outcome::Saved(sector) => {
+                stats.succeeded += 1;
+                if let (None, Some(sector)) = (stock.sector_id, sector) {
+                    stats.unclassified_sectors.push((stock.id, sector));
+                }
+            }
+            Outcome::Failed(e) => {
+                eprintln!("{e}");
+                stats.failed += 1;
+            }
+            Outcome::Skipped => stats.skipped += 1,
+        }
+    }
+
+    stats
+}
+
+/// Backfill one stock's history, unless the job is cancelled before it starts.
+/// `Ok(None)` means it was skipped.
+async fn backfill_one<'a>(
+    pool: &SqlitePool,
+    provider: &dyn MarketDataProvider,
+    target: &'a BackfillTarget,
+    target_start: NaiveDate,
+    today: NaiveDate,
+    job: &JobToken,
+) -> (&'a BackfillTarget, Result<Option<u32>, String>) {
+    if job.checkpoint().await.is_err() {
+        return (target, Ok(None));
+    }
+    let outcome = price_history::backfill_stock(pool, provider, target, target_start, today).await;
+    (target, outcome.map(Some))
+}
+
+/// Backfill daily bars for every target, at most `concurrency` stocks at a time.
+/// Per-stock failures are collected into the result rather than aborting the run.
+#[allow(clippy::too_many_arguments)]
+pub async fn backfill_history(
+    pool: &SqlitePool,
+    provider: &dyn MarketDataProvider,
+    targets: &[BackfillTarget],
+    target_start: NaiveDate,
+    today: NaiveDate,
+    concurrency: usize,
+    job: &JobToken,
+    on_progress: impl Fn(u32, u32),
+) -> BackfillResult {
+    let total = targets.len() as u32;
+    let mut bars_written = 0;
+    let mut errors = Vec::new();
+    let mut done = 0;
+
+    let mut pending = targets.iter();
+    let mut in_flight = FuturesUnordered::new();
+    for target in pending.by_ref().take(concurrency.max(1)) {
+        in_flight.push(backfill_one(pool, provider, target, target_start, today, job));
+    }
+
+    while let Some((target, outcome)) = in_flight.next().await {
+        if let Some(next) = pending.next() {
+            in_flight.push(backfill_one(pool, provider, next, target_start, today, job));
+        }
+        done += 1;
+        on_progress(done, total);
+
+        match outcome {
+            Ok(Some(n)) => bars_written += n,
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!("History backfill failed for {}: {e}", target.symbol);
+                errors.push(format!("{}: {e}", target.symbol));
+            }
+        }
+    }
+
+    BackfillResult {
+        stocks_processed: total,
+        bars_written,
+        errors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::JobRegistry;
+    use crate::market_data::{Fundamentals, PriceQuote};
+    use async_trait::async_trait;
+
+    /// Batch-only provider quoting every symbol at 100 without a beta.
+    struct BatchOnly;
+
+    #[async_trait]
+    impl MarketDataProvider for BatchOnly {
+        fn name(&self) -> &'static str {
+            "batch-only"
+        }
+
+        async fn fetch_quote(&self, symbol: &str) -> Result<PriceQuote, String> {
+            Err(format!("no single quote for {symbol}"))
+        }
+
+        async fn fetch_fundamentals(&self, _symbol: &str) -> Fundamentals {
+            Fundamentals::default()
+        }
+
+        async fn fetch_quote_batch(&self, symbols: &[String]) -> Vec<BatchQuote> {
+            symbols
+                .iter()
+                .map(|symbol| BatchQuote {
+                    symbol: symbol.clone(),
+                    quote: PriceQuote {
+                        price: 100.0,
+                        prev_close: 99.0,
+                        volume: None,
+                    },
+                    fundamentals: Fundamentals::default(),
+                })
+                .collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batched_refresh_keeps_stored_beta() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::database::run_migrations(&pool).await.unwrap();
+        let stock: Stock =
+            sqlx::query_as("SELECT id, symbol, name, sector_id FROM stocks ORDER BY id LIMIT 1")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        sqlx::query(
+            "INSERT INTO market_data (stock_id, price, price_change, price_change_percent, beta)
+             VALUES (?, 99.0, 0.0, 0.0, 1.3)",
+        )
+        .bind(stock.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let job = JobRegistry::default().start("refresh");
+        let stocks = [stock];
+        let stats = refresh_quotes(&pool, &BatchOnly, &stocks, 1, &job, |_, _| {}).await;
+        assert_eq!(stats.succeeded, 1);
+
+        let (price, beta): (f64, Option<f64>) = sqlx::query_as(
+            "SELECT price, beta FROM market_data WHERE stock_id = ? ORDER BY id DESC LIMIT 1",
+        )
+        .bind(stocks[0].id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(price, 100.0);
+        assert_eq!(beta, Some(1.3));
+    }
+}
@@ -0,0 +1,110 @@
+use crate::market_hours;
+use crate::types::ReturnHorizon;
+use chrono::{Datelike, Duration, Months, NaiveDate, Utc};
+
+/// How far before the base date a stored close may be and still count as the
+/// base price. Covers weekends and holiday runs without reaching into stale history.
+pub const MAX_BASE_STALENESS_DAYS: i64 = 7;
+
+/// The date whose close a return over `horizon` is measured from, or `None`
+/// for the one-day change, which comes straight from the latest quote.
+pub fn base_date(horizon: ReturnHorizon, as_of: NaiveDate) -> Option<NaiveDate> {
+    match horizon {
+        ReturnHorizon::OneDay => None,
+        ReturnHorizon::OneWeek => Some(as_of - Duration::days(7)),
+        ReturnHorizon::OneMonth => as_of.checked_sub_months(Months::new(1)),
+        ReturnHorizon::ThreeMonths => as_of.checked_sub_months(Months::new(3)),
+        // Last close of the previous year
+        ReturnHorizon::YearToDate => NaiveDate::from_ymd_opt(as_of.year() - 1, 12, 31),
+    }
+}
+
+/// Today in New York, which is what the stored bar dates are keyed on.
+pub fn market_today() -> NaiveDate {
+    market_hours::eastern_date(Utc::now())
+}
+
+/// SQL expression for a stock's percent return over `horizon` ending at its
+/// latest quote. Expects `s` (stocks) and `md` (the latest market_data row) in
+/// scope. NULL when there is no stored close near the base date.
+///
+/// The return is chained: the adjusted close from the base date to the latest
+/// stored bar on or before `as_of`, then the quote against that bar's close.
+/// Both closes of the first leg come from one fetch (history is re-fetched
+/// whole when the provider re-adjusts it), so splits and dividends in stored
+/// history don't show up as moves. A split after the latest stored bar does,
+/// until history is next backfilled.
+///
+/// The dates are formatted from `NaiveDate`, so embedding them is safe.
+pub fn return_sql(horizon: ReturnHorizon, as_of: NaiveDate) -> String {
+    match base_date(horizon, as_of) {
+        None => "md.price_change_percent".to_string(),
+        Some(base) => format!(
+            "((
+                SELECT ph.adj_close / ph.close * md.price FROM price_history ph
+                WHERE ph.stock_id = s.id AND ph.date <= '{as_of}'
+                ORDER BY ph.date DESC LIMIT 1
+            ) / (
+                SELECT ph.adj_close FROM price_history ph
+                WHERE ph.stock_id = s.id AND ph.date <= '{base}' AND ph.date >= '{oldest}'
+                ORDER BY ph.date DESC LIMIT 1
+            ) - 1.0) * 100.0",
+            oldest = base - Duration::days(MAX_BASE_STALENESS_DAYS),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(y: i32, m: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, day).unwrap()
+    }
+
+    // ---- base_date ----
+
+    #[test]
+    fn test_one_day_uses_quote() {
+        assert_eq!(base_date(ReturnHorizon::OneDay, d(2025, 6, 10)), None);
+    }
+
+    #[test]
+    fn test_week_and_month_lookbacks() {
+        let as_of = d(2025, 6, 10);
+        assert_eq!(base_date(ReturnHorizon::OneWeek, as_of), Some(d(2025, 6, 3)));
+        assert_eq!(base_date(ReturnHorizon::OneMonth, as_of), Some(d(2025, 5, 10)));
+        assert_eq!(base_date(ReturnHorizon::ThreeMonths, as_of), Some(d(2025, 3, 10)));
+    }
+
+    #[test]
+    fn test_month_lookback_clamps_to_month_end() {
+        assert_eq!(base_date(ReturnHorizon::OneMonth, d(2024, 3, 31)), Some(d(2024, 2, 29)));
+    }
+
+    #[test]
+    fn test_ytd_measures_from_prior_year_end() {
+        assert_eq!(base_date(ReturnHorizon::YearToDate, d(2025, 1, 2)), Some(d(2024, 12, 31)));
+    }
+
+    // ---- return_sql ----
+
+    #[test]
+    fn test_return_sql_one_day_reads_quote_change() {
+        assert_eq!(return_sql(ReturnHorizon::OneDay, d(2025, 6, 10)), "md.price_change_percent");
+    }
+
+    #[test]
+    fn test_return_sql_bounds_base_window() {
+        let sql = return_sql(ReturnHorizon::OneWeek, d(2025, 6, 10));
+        assert!(sql.contains("ph.date <= '2025-06-03'"), "{sql}");
+        assert!(sql.contains("ph.date >= '2025-05-27'"), "{sql}");
+    }
+
+    #[test]
+    fn test_return_sql_rebases_quote_on_latest_bar() {
+        let sql = return_sql(ReturnHorizon::OneMonth, d(2025, 6, 10));
+        assert!(sql.contains("ph.adj_close / ph.close * md.price"), "{sql}");
+        assert!(sql.contains("ph.date <= '2025-06-10'"), "{sql}");
+    }
+}
@@ -0,0 +1,432 @@
+use crate::returns;
+use crate::types::{PeriodReturns, ReturnHorizon, RotationPoint, RotationQuadrant, SectorRotation};
+use chrono::{Duration, NaiveDate};
+use sqlx::sqlite::SqlitePool;
+use std::collections::{BTreeMap, HashMap};
+
+/// Sessions in the moving average that normalizes relative strength into the
+/// RS-Ratio (about a month).
+const RATIO_WINDOW: usize = 20;
+
+/// Sessions the RS-Momentum compares the RS-Ratio against (about two weeks).
+const MOMENTUM_LOOKBACK: usize = 10;
+
+/// Calendar days of history loaded: a full year-to-date plus room for the windows.
+const HISTORY_DAYS: i64 = 400;
+
+/// One session's member returns: across the universe, and by sector.
+type SessionReturns = (Vec<f64>, HashMap<i32, Vec<f64>>);
+
+#[derive(Debug, sqlx::FromRow)]
+struct Bar {
+    stock_id: i32,
+    sector_id: i32,
+    date: String,
+    adj_close: f64,
+}
+
+/// Equal-weight daily returns on a shared calendar: the whole universe, and
+/// each sector. A sector with no members trading on a date gets a flat day.
+#[derive(Debug, Default)]
+struct DailyReturns {
+    dates: Vec<NaiveDate>,
+    universe: Vec<f64>,
+    sectors: HashMap<i32, Vec<f64>>,
+}
+
+/// Rank sectors by strength relative to their universe and place each one in
+/// its rotation quadrant, with the last `trail` points of its path.
+pub async fn sector_rotation(
+    pool: &SqlitePool,
+    universe: &str,
+    rank_by: ReturnHorizon,
+    trail: usize,
+) -> Result<Vec<SectorRotation>, String> {
+    let sectors: Vec<(i32, String, String)> =
+        sqlx::query_as("SELECT id, name, symbol FROM sectors ORDER BY name")
+            .fetch_all(pool)
+            .await
+            .map_err(|e| format!("Failed to fetch sectors: {e}"))?;
+
+    let since = returns::market_today() - Duration::days(HISTORY_DAYS);
+    let bars: Vec<Bar> = sqlx::query_as(
+        "SELECT ph.stock_id, s.sector_id, ph.date, ph.adj_close
+         FROM price_history ph
+         JOIN stocks s ON s.id = ph.stock_id
+         JOIN stock_universe su ON su.stock_id = s.id
+            AND su.universe_type = ?
+            AND su.date_removed IS NULL
+         WHERE s.sector_id IS NOT NULL AND ph.date >= ?
+         ORDER BY ph.stock_id, ph.date",
+    )
+    .bind(universe)
+    .bind(since.to_string())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch price history: {e}"))?;
+
+    let daily = daily_returns(&bars);
+    let universe_index = index_levels(&daily.universe);
+
+    let mut rotations: Vec<SectorRotation> = sectors
+        .into_iter()
+        .map(|(sector_id, name, symbol)| {
+            let (relative_strength, path) = match daily.sectors.get(&sector_id) {
+                Some(returns) => {
+                    let index = index_levels(returns);
+                    let excess = |h| period_excess(&daily.dates, &index, &universe_index, h);
+                    let strength = PeriodReturns {
+                        one_day: excess(ReturnHorizon::OneDay),
+                        one_week: excess(ReturnHorizon::OneWeek),
+                        one_month: excess(ReturnHorizon::OneMonth),
+                        three_months: excess(ReturnHorizon::ThreeMonths),
+                        year_to_date: excess(ReturnHorizon::YearToDate),
+                    };
+                    let rs = relative_strength(&index, &universe_index);
+                    (strength, rotation_path(&daily.dates, &rs))
+                }
+                None => (PeriodReturns::default(), Vec::new()),
+            };
+            let trajectory = path[path.len().saturating_sub(trail)..].to_vec();
+            SectorRotation {
+                sector_id,
+                name,
+                symbol,
+                rank: None,
+                quadrant: trajectory.last().map(|p| p.quadrant),
+                relative_strength,
+                trajectory,
+            }
+        })
+        .collect();
+
+    rank_sectors(&mut rotations, rank_by);
+    Ok(rotations)
+}
+
+/// Average each day's member returns into universe and sector series.
+/// `bars` must be ordered by stock, then date.
+fn daily_returns(bars: &[Bar]) -> DailyReturns {
+    let mut by_date: BTreeMap<NaiveDate, SessionReturns> = BTreeMap::new();
+    for pair in bars.windows(2) {
+        let (prev, bar) = (&pair[0], &pair[1]);
+        if prev.stock_id != bar.stock_id || prev.adj_close <= 0.0 {
+            continue;
+        }
+        let date = match NaiveDate::parse_from_str(&bar.date, "%Y-%m-%d") {
+            Ok(d) => d,
+            Err(_) => continue,
+        };
+        let r = bar.adj_close / prev.adj_close - 1.0;
+        let (all, sectors) = by_date.entry(date).or_default();
+        all.push(r);
+        sectors.entry(bar.sector_id).or_default().push(r);
+    }
+
+    let mut daily = DailyReturns::default();
+    for (i, (date, (all, sectors))) in by_date.into_iter().enumerate() {
+        daily.dates.push(date);
+        daily.universe.push(mean(&all));
+        for (sector_id, returns) in sectors {
+            let series = daily.sectors.entry(sector_id).or_default();
+            series.resize(i, 0.0);
+            series.push(mean(&returns));
+        }
+    }
+    let days = daily.dates.len();
+    for series in daily.sectors.values_mut() {
+        series.resize(days, 0.0);
+    }
+    daily
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+}
+
+/// Compound daily returns into an index that starts at 100 before the first day.
+fn index_levels(returns: &[f64]) -> Vec<f64> {
+    returns
+        .iter()
+        .scan(100.0, |level, r| {
+            *level *= 1.0 + r;
+            Some(*level)
+        })
+        .collect()
+}
+
+/// Sector index over universe index, scaled to 100 when they move together.
+fn relative_strength(sector: &[f64], universe: &[f64]) -> Vec<f64> {
+    sector
+        .iter()
+        .zip(universe)
+        .map(|(s, u)| if *u > 0.0 { s / u * 100.0 } else { 100.0 })
+        .collect()
+}
+
+/// Percentage points the sector index gained over the universe across `horizon`,
+/// measured from the last session on or before the horizon's base date.
+fn period_excess(
+    dates: &[NaiveDate],
+    sector: &[f64],
+    universe: &[f64],
+    horizon: ReturnHorizon,
+) -> Option<f64> {
+    let last = dates.len().checked_sub(1)?;
+    let base = match returns::base_date(horizon, dates[last]) {
+        None => last.checked_sub(1)?,
+        Some(base_date) => {
+            let i = dates.iter().rposition(|d| *d <= base_date)?;
+            if base_date - dates[i] > Duration::days(returns::MAX_BASE_STALENESS_DAYS) {
+                return None;
+            }
+            i
+        }
+    };
+    let gain = |index: &[f64]| (index[last] / index[base] - 1.0) * 100.0;
+    Some(gain(sector) - gain(universe))
+}
+
+/// RRG-style coordinates for each session once enough history has built up.
+///
+/// RS-Ratio is relative strength over its own moving average, so 100 means in
+/// line with its recent trend versus the universe. RS-Momentum is the RS-Ratio
+/// against its value `MOMENTUM_LOOKBACK` sessions earlier: above 100 when the
+/// ratio is rising.
+fn rotation_path(dates: &[NaiveDate], rs: &[f64]) -> Vec<RotationPoint> {
+    if rs.len() < RATIO_WINDOW {
+        return Vec::new();
+    }
+    let ratios: Vec<f64> = rs
+        .windows(RATIO_WINDOW)
+        .map(|w| w[RATIO_WINDOW - 1] / mean(w) * 100.0)
+        .collect();
+
+    ratios
+        .iter()
+        .enumerate()
+        .skip(MOMENTUM_LOOKBACK)
+        .map(|(i, &rs_ratio)| {
+            let rs_momentum = rs_ratio / ratios[i - MOMENTUM_LOOKBACK] * 100.0;
+            RotationPoint {
+                date: dates[i + RATIO_WINDOW - 1].to_string(),
+                rs_ratio,
+                rs_momentum,
+                quadrant: quadrant(rs_ratio, rs_momentum),
+            }
+        })
+        .collect()
+}
+
+/// Which RRG quadrant a point falls in. Exactly 100 counts as the strong side.
+fn quadrant(rs_ratio: f64, rs_momentum: f64) -> RotationQuadrant {
+    match (rs_ratio >= 100.0, rs_momentum >= 100.0) {
+        (true, true) => RotationQuadrant::Leading,
+        (true, false) => RotationQuadrant::Weakening,
+        (false, false) => RotationQuadrant::Lagging,
+        (false, true) => RotationQuadrant::Improving,
+    }
+}
+
+fn horizon_value(returns: &PeriodReturns, horizon: ReturnHorizon) -> Option<f64> {
+    match horizon {
+        ReturnHorizon::OneDay => returns.one_day,
+        ReturnHorizon::OneWeek => returns.one_week,
+        ReturnHorizon::OneMonth => returns.one_month,
+        ReturnHorizon::ThreeMonths => returns.three_months,
+        ReturnHorizon::YearToDate => returns.year_to_date,
+    }
+}
+
+/// Order strongest first by relative strength over `rank_by` and number them
+/// from 1. Sectors without enough history go last, unranked.
+fn rank_sectors(rotations: &mut [SectorRotation], rank_by: ReturnHorizon) {
+    rotations.sort_by(|a, b| {
+        let a = horizon_value(&a.relative_strength, rank_by);
+        let b = horizon_value(&b.relative_strength, rank_by);
+        match (a, b) {
+            (Some(a), Some(b)) => b.total_cmp(&a),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        }
+    });
+    for (i, rotation) in rotations.iter_mut().enumerate() {
+        if horizon_value(&rotation.relative_strength, rank_by).is_some() {
+            rotation.rank = Some(i + 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx_eq(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    fn bar(stock_id: i32, sector_id: i32, date: &str, adj_close: f64) -> Bar {
+        Bar {
+            stock_id,
+            sector_id,
+            date: date.to_string(),
+            adj_close,
+        }
+    }
+
+    fn sessions(n: usize) -> Vec<NaiveDate> {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        (0..n).map(|i| start + Duration::days(i as i64)).collect()
+    }
+
+    fn rotation(symbol: &str, one_month: Option<f64>) -> SectorRotation {
+        SectorRotation {
+            sector_id: 0,
+            name: symbol.to_string(),
+            symbol: symbol.to_string(),
+            rank: None,
+            quadrant: None,
+            relative_strength: PeriodReturns {
+                one_month,
+                ..PeriodReturns::default()
+            },
+            trajectory: Vec::new(),
+        }
+    }
+
+    // ---- daily_returns ----
+
+    #[test]
+    fn test_daily_returns_average_members() {
+        let bars = [
+            bar(1, 1, "2025-06-09", 100.0),
+            bar(1, 1, "2025-06-10", 102.0),
+            bar(2, 2, "2025-06-09", 50.0),
+            bar(2, 2, "2025-06-10", 49.0),
+        ];
+        let daily = daily_returns(&bars);
+        assert_eq!(daily.dates.len(), 1);
+        assert!(approx_eq(daily.universe[0], 0.0));
+        assert!(approx_eq(daily.sectors[&1][0], 0.02));
+        assert!(approx_eq(daily.sectors[&2][0], -0.02));
+    }
+
+    #[test]
+    fn test_daily_returns_pad_missing_sector_days() {
+        let bars = [
+            bar(1, 1, "2025-06-09", 100.0),
+            bar(1, 1, "2025-06-10", 110.0),
+            bar(1, 1, "2025-06-11", 121.0),
+            bar(2, 2, "2025-06-10", 10.0),
+            bar(2, 2, "2025-06-11", 11.0),
+        ];
+        let daily = daily_returns(&bars);
+        assert_eq!(daily.dates.len(), 2);
+        assert_eq!(daily.sectors[&2].len(), 2);
+        assert!(approx_eq(daily.sectors[&2][0], 0.0));
+        assert!(approx_eq(daily.sectors[&2][1], 0.1));
+    }
+
+    // ---- index_levels / relative_strength ----
+
+    #[test]
+    fn test_index_levels_compound() {
+        let index = index_levels(&[0.1, 0.1]);
+        assert!(approx_eq(index[0], 110.0));
+        assert!(approx_eq(index[1], 121.0));
+    }
+
+    #[test]
+    fn test_relative_strength_is_100_in_lockstep() {
+        let rs = relative_strength(&[110.0, 121.0], &[110.0, 121.0]);
+        assert!(rs.iter().all(|v| approx_eq(*v, 100.0)));
+    }
+
+    // ---- period_excess ----
+
+    #[test]
+    fn test_period_excess_one_day() {
+        let dates = sessions(2);
+        let excess = period_excess(&dates, &[100.0, 103.0], &[100.0, 101.0], ReturnHorizon::OneDay);
+        assert!(approx_eq(excess.unwrap(), 2.0));
+    }
+
+    #[test]
+    fn test_period_excess_week_measures_from_base_date() {
+        let dates = sessions(10);
+        let sector: Vec<f64> = (0..10).map(|i| 100.0 + i as f64).collect();
+        let universe = vec![100.0; 10];
+        // Last date Jan 10 → base Jan 3, index 2 → 109 / 102
+        let excess = period_excess(&dates, &sector, &universe, ReturnHorizon::OneWeek).unwrap();
+        assert!(approx_eq(excess, (109.0 / 102.0 - 1.0) * 100.0));
+    }
+
+    #[test]
+    fn test_period_excess_needs_history() {
+        let dates = sessions(10);
+        let flat = vec![100.0; 10];
+        assert_eq!(period_excess(&dates, &flat, &flat, ReturnHorizon::ThreeMonths), None);
+        assert_eq!(period_excess(&[], &[], &[], ReturnHorizon::OneDay), None);
+    }
+
+    // ---- rotation_path / quadrant ----
+
+    #[test]
+    fn test_quadrants() {
+        assert_eq!(quadrant(101.0, 101.0), RotationQuadrant::Leading);
+        assert_eq!(quadrant(101.0, 99.0), RotationQuadrant::Weakening);
+        assert_eq!(quadrant(99.0, 99.0), RotationQuadrant::Lagging);
+        assert_eq!(quadrant(99.0, 101.0), RotationQuadrant::Improving);
+        assert_eq!(quadrant(100.0, 100.0), RotationQuadrant::Leading);
+    }
+
+    #[test]
+    fn test_rotation_path_needs_full_windows() {
+        // The first point needs a full ratio window plus the momentum lookback
+        const N: usize = RATIO_WINDOW + MOMENTUM_LOOKBACK;
+        let dates = sessions(N + 5);
+        assert!(rotation_path(&dates, &[100.0; RATIO_WINDOW - 1]).is_empty());
+        assert!(rotation_path(&dates, &[100.0; N - 1]).is_empty());
+        assert_eq!(rotation_path(&dates, &[100.0; N]).len(), 1);
+        assert_eq!(rotation_path(&dates, &[100.0; N + 5]).len(), 6);
+    }
+
+    #[test]
+    fn test_rotation_path_accelerating_outperformer_leads() {
+        let n = RATIO_WINDOW + MOMENTUM_LOOKBACK + 5;
+        let dates = sessions(n);
+        let rs: Vec<f64> = (0..n).map(|i| 100.0 + (i * i) as f64 * 0.01).collect();
+        let path = rotation_path(&dates, &rs);
+        let last = path.last().unwrap();
+        assert_eq!(last.quadrant, RotationQuadrant::Leading);
+        assert_eq!(last.date, dates[n - 1].to_string());
+    }
+
+    #[test]
+    fn test_rotation_path_fading_underperformer_lags() {
+        let n = RATIO_WINDOW + MOMENTUM_LOOKBACK + 5;
+        let dates = sessions(n);
+        let rs: Vec<f64> = (0..n).map(|i| 100.0 - (i * i) as f64 * 0.01).collect();
+        let last = rotation_path(&dates, &rs).pop().unwrap();
+        assert_eq!(last.quadrant, RotationQuadrant::Lagging);
+    }
+
+    // ---- rank_sectors ----
+
+    #[test]
+    fn test_rank_sectors_strongest_first_unranked_last() {
+        let mut rotations = vec![
+            rotation("XLU", Some(-1.0)),
+            rotation("XLRE", None),
+            rotation("XLK", Some(2.5)),
+        ];
+        rank_sectors(&mut rotations, ReturnHorizon::OneMonth);
+        let order: Vec<_> = rotations.iter().map(|r| (r.symbol.as_str(), r.rank)).collect();
+        assert_eq!(order, vec![("XLK", Some(1)), ("XLU", Some(2)), ("XLRE", None)]);
+    }
+}
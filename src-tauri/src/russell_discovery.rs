@@ -1,4 +1,5 @@
 use crate::types::DiscoveryResult;
+use crate::universes;
 use reqwest::Client;
 use sqlx::sqlite::SqlitePool;
 
@@ -103,8 +104,12 @@ async fn fetch_iwm_csv(client: &Client) -> Result<String, String> {
 /// Discover Russell 2000 stocks from iShares IWM CSV and upsert into the database.
 ///
 /// New stocks are inserted with `sector_id = NULL` (GICS sector is not provided by IWM CSV).
-/// All discovered stocks are tracked in `stock_universe` as `russell2000`.
-pub async fn discover_russell_2000(pool: &SqlitePool, client: &Client) -> Result<DiscoveryResult, String> {
+/// The membership of `universe` is reconciled with the full holdings list.
+pub async fn discover_russell_2000(
+    pool: &SqlitePool,
+    client: &Client,
+    universe: &str,
+) -> Result<DiscoveryResult, String> {
     let csv = fetch_iwm_csv(client).await?;
     let entries = parse_iwm_csv(&csv);
 
@@ -120,13 +125,10 @@ pub async fn discover_russell_2000(pool: &SqlitePool, client: &Client) -> Result
                 .await
                 .map_err(|e| format!("DB error checking {ticker}: {e}"))?;
 
-        let stock_id = match existing {
-            Some((id, _)) => {
-                stocks_unchanged += 1;
-                id
-            }
+        match existing {
+            Some(_) => stocks_unchanged += 1,
             None => {
-                let result = sqlx::query(
+                sqlx::query(
                     "INSERT INTO stocks (symbol, name, sector_id) VALUES (?, ?, NULL)",
                 )
                 .bind(ticker)
@@ -135,23 +137,19 @@ pub async fn discover_russell_2000(pool: &SqlitePool, client: &Client) -> Result
                 .await
                 .map_err(|e| format!("Failed to insert {ticker}: {e}"))?;
                 stocks_discovered += 1;
-                result.last_insert_rowid() as i32
             }
-        };
-
-        sqlx::query(
-            "INSERT OR IGNORE INTO stock_universe (stock_id, universe_type) VALUES (?, 'russell2000')",
-        )
-        .bind(stock_id)
-        .execute(pool)
-        .await
-        .map_err(|e| format!("Failed to upsert universe for {ticker}: {e}"))?;
+        }
     }
 
+    let listed: Vec<String> = entries.iter().map(|(ticker, _)| ticker.clone()).collect();
+    let members = universes::reconcile_members(pool, universe, &listed).await?;
+
     println!(
-        "Russell 2000 discovery: {} new, {} existing, {} errors",
+        "Russell 2000 discovery: {} new, {} existing, {} joined, {} left, {} errors",
         stocks_discovered,
         stocks_unchanged,
+        members.added.len(),
+        members.removed.len(),
         errors.len()
     );
 
@@ -159,6 +157,8 @@ pub async fn discover_russell_2000(pool: &SqlitePool, client: &Client) -> Result
         stocks_discovered,
         stocks_updated: 0,
         stocks_unchanged,
+        members_added: members.added,
+        members_removed: members.removed,
         errors,
     })
 }
@@ -0,0 +1,233 @@
+use crate::benchmarks;
+use crate::cache::SectorCache;
+use crate::jobs::{JobRegistry, JobToken};
+use crate::market_hours;
+use crate::provider;
+use crate::refresh_engine::{self, RefreshStats};
+use crate::settings;
+use crate::types::{SchedulerStatus, Sector, Stock};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::sqlite::SqlitePool;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+
+pub const MIN_INTERVAL_MINUTES: u32 = 5;
+pub const MAX_INTERVAL_MINUTES: u32 = 30;
+pub const DEFAULT_INTERVAL_MINUTES: u32 = 15;
+
+/// Longest single sleep. Keeps the loop honest across system sleep, where a
+/// monotonic timer would otherwise fire late.
+const MAX_SLEEP_SECS: i64 = 5 * 60;
+
+#[derive(Clone, Serialize)]
+struct MarketDataUpdated {
+    refreshed_at: String,
+    succeeded: u32,
+    failed: u32,
+}
+
+/// Shared scheduler state, managed as Tauri state.
+#[derive(Default)]
+pub struct SchedulerState {
+    wake: Notify,
+    status: Mutex<Option<SchedulerStatus>>,
+}
+
+impl SchedulerState {
+    /// Wake the scheduler loop so it re-reads its settings.
+    pub fn reschedule(&self) {
+        self.wake.notify_one();
+    }
+
+    pub fn status(&self) -> Option<SchedulerStatus> {
+        self.status.lock().ok().and_then(|s| s.clone())
+    }
+
+    fn set_status(&self, status: SchedulerStatus) {
+        if let Ok(mut guard) = self.status.lock() {
+            *guard = Some(status);
+        }
+    }
+}
+
+struct SchedulerConfig {
+    enabled: bool,
+    interval_minutes: u32,
+}
+
+async fn load_config(pool: &SqlitePool) -> SchedulerConfig {
+    let config = settings::load_app_settings(pool).await.unwrap_or_default();
+    SchedulerConfig {
+        enabled: config.scheduler_enabled,
+        interval_minutes: config
+            .scheduler_interval_minutes
+            .clamp(MIN_INTERVAL_MINUTES, MAX_INTERVAL_MINUTES),
+    }
+}
+
+fn format_timestamp(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// When the next automatic refresh is due: one interval after `from`,
+/// pushed forward to the next session open if that lands outside market hours.
+fn next_run_at(from: DateTime<Utc>, interval_minutes: u32) -> DateTime<Utc> {
+    market_hours::next_open(from + Duration::minutes(interval_minutes as i64))
+}
+
+/// Quote refresh for every current member of every universe, plus the sector
+/// benchmarks. Discovery is left to the manual refreshes; constituents don't
+/// change intraday.
+async fn refresh_all_universes(pool: SqlitePool, job: JobToken) -> Result<RefreshStats, String> {
+    let config = settings::load_app_settings(&pool).await?;
+    let provider = provider::connect_provider(&pool).await?;
+
+    // Benchmarks first, so each stock quote has an ETF quote from at or before it
+    let sectors: Vec<Sector> = sqlx::query_as("SELECT id, name, symbol FROM sectors")
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("Failed to fetch sectors: {e}"))?;
+    benchmarks::refresh_benchmarks(&pool, provider.as_ref(), &sectors).await;
+
+    let stocks: Vec<Stock> = sqlx::query_as(
+        "SELECT DISTINCT s.id, s.symbol, s.name, s.sector_id
+         FROM stocks s
+         JOIN stock_universe su ON su.stock_id = s.id
+         WHERE su.date_removed IS NULL
+         ORDER BY s.symbol",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to fetch stocks: {e}"))?;
+
+    let stats = refresh_engine::refresh_quotes(
+        &pool,
+        provider.as_ref(),
+        &stocks,
+        config.refresh_concurrency as usize,
+        &job,
+        |current, total| job.report("market-data", current, total),
+    )
+    .await;
+
+    job.checkpoint().await?;
+
+    Ok(stats)
+}
+
+/// Run one scheduled refresh unless a manual one is already in progress.
+async fn run_scheduled_refresh(app: &AppHandle, pool: &SqlitePool) -> Result<(), String> {
+    let jobs = app.state::<JobRegistry>();
+    if jobs.has_active() {
+        println!("Scheduled refresh skipped: another refresh is in progress");
+        return Ok(());
+    }
+
+    let pool = pool.clone();
+    let stats = jobs
+        .run("scheduled", |job| refresh_all_universes(pool, job))
+        .await?;
+
+    println!(
+        "Scheduled refresh: {} succeeded, {} failed",
+        stats.succeeded, stats.failed
+    );
+
+    app.state::<SectorCache>().invalidate();
+    let _ = app.emit("market-data-updated", MarketDataUpdated {
+        refreshed_at: format_timestamp(Utc::now()),
+        succeeded: stats.succeeded,
+        failed: stats.failed,
+    });
+
+    Ok(())
+}
+
+/// Start the background scheduler loop.
+pub fn start(app: AppHandle, pool: SqlitePool) {
+    tauri::async_runtime::spawn(async move { run(app, pool).await });
+}
+
+async fn run(app: AppHandle, pool: SqlitePool) {
+    let state = app.state::<SchedulerState>();
+    let mut last_run: Option<DateTime<Utc>> = None;
+    // Refresh straight away on launch if the market is open
+    let mut next_at = Utc::now();
+
+    loop {
+        let config = load_config(&pool).await;
+        let now = Utc::now();
+
+        if config.enabled && now >= next_at {
+            if market_hours::is_market_open(now) {
+                if let Err(e) = run_scheduled_refresh(&app, &pool).await {
+                    eprintln!("Scheduled refresh failed: {e}");
+                }
+                last_run = Some(Utc::now());
+            }
+            next_at = next_run_at(Utc::now(), config.interval_minutes);
+        }
+
+        state.set_status(SchedulerStatus {
+            enabled: config.enabled,
+            interval_minutes: config.interval_minutes,
+            market_open: market_hours::is_market_open(Utc::now()),
+            last_run_at: last_run.map(format_timestamp),
+            next_run_at: config.enabled.then(|| format_timestamp(next_at)),
+        });
+
+        let sleep_secs = if config.enabled {
+            (next_at - Utc::now()).num_seconds().clamp(0, MAX_SLEEP_SECS)
+        } else {
+            MAX_SLEEP_SECS
+        };
+
+        tokio::select! {
+            _ = tokio::time::sleep(std::time::Duration::from_secs(sleep_secs as u64)) => {}
+            _ = state.wake.notified() => {
+                // Settings changed: re-plan from the last run with the new interval
+                let config = load_config(&pool).await;
+                let from = last_run.unwrap_or_else(Utc::now);
+                next_at = next_run_at(from, config.interval_minutes);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn test_next_run_during_session_is_one_interval_later() {
+        // 11:00 EDT on a regular Tuesday
+        let from = utc(2025, 6, 10, 15, 0);
+        assert_eq!(next_run_at(from, 15), utc(2025, 6, 10, 15, 15));
+    }
+
+    #[test]
+    fn test_next_run_after_close_waits_for_next_open() {
+        // 15:50 EDT + 15 minutes is after the bell → Wednesday 9:30 EDT
+        let from = utc(2025, 6, 10, 19, 50);
+        assert_eq!(next_run_at(from, 15), utc(2025, 6, 11, 13, 30));
+    }
+
+    #[test]
+    fn test_next_run_respects_half_day_close() {
+        // Christmas Eve 2025 closes at 13:00 EST (18:00 UTC); next open is Dec 26
+        let from = utc(2025, 12, 24, 17, 50);
+        assert_eq!(next_run_at(from, 15), utc(2025, 12, 26, 14, 30));
+    }
+
+    #[test]
+    fn test_format_timestamp_matches_sqlite_style() {
+        assert_eq!(format_timestamp(utc(2025, 6, 10, 15, 0)), "2025-06-10 15:00:00");
+    }
+}
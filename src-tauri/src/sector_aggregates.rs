@@ -0,0 +1,201 @@
+use crate::stats::{percentile, sorted};
+use crate::types::{GicsLevel, Quartiles};
+use sqlx::sqlite::SqlitePool;
+use std::collections::HashMap;
+
+/// Latest quote fields for one universe member, as used by the sector roll-ups.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct MemberQuote {
+    /// The member's group at the level the quotes were fetched for.
+    pub group_id: i32,
+    pub price_change_percent: Option<f64>,
+    pub market_cap: Option<i64>,
+    pub pe_ratio: Option<f64>,
+}
+
+/// Cap-weighted and distribution statistics for one sector.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SectorAggregates {
+    pub cap_weighted_change_percent: Option<f64>,
+    pub change_quartiles: Option<Quartiles>,
+    pub cap_weighted_pe_ratio: Option<f64>,
+    pub pe_quartiles: Option<Quartiles>,
+}
+
+/// Latest quote for every current member of `universe`, grouped by the
+/// members' GICS group at `level`.
+pub async fn fetch_member_quotes(
+    pool: &SqlitePool,
+    universe: &str,
+    level: GicsLevel,
+) -> Result<HashMap<i32, Vec<MemberQuote>>, String> {
+    let sql = format!(
+        "SELECT sg.{column} as group_id, md.price_change_percent, md.market_cap, md.pe_ratio
+         FROM stock_gics sg
+         JOIN stock_universe su ON su.stock_id = sg.stock_id
+            AND su.universe_type = ?
+            AND su.date_removed IS NULL
+         JOIN market_data md ON md.stock_id = sg.stock_id
+            AND md.id = (
+                SELECT md2.id FROM market_data md2
+                WHERE md2.stock_id = sg.stock_id
+                ORDER BY md2.timestamp DESC LIMIT 1
+            )
+         WHERE sg.{column} IS NOT NULL",
+        column = level.member_column(),
+    );
+    let quotes: Vec<MemberQuote> = sqlx::query_as(&sql)
+        .bind(universe)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to fetch {} member quotes: {e}", level.as_str()))?;
+
+    let mut by_group: HashMap<i32, Vec<MemberQuote>> = HashMap::new();
+    for quote in quotes {
+        by_group.entry(quote.group_id).or_default().push(quote);
+    }
+    Ok(by_group)
+}
+
+/// Roll one sector's member quotes up into its aggregates.
+pub fn aggregate(quotes: &[MemberQuote]) -> SectorAggregates {
+    let changes: Vec<f64> = quotes.iter().filter_map(|q| q.price_change_percent).collect();
+    let pes: Vec<f64> = quotes.iter().filter_map(|q| q.pe_ratio).collect();
+
+    SectorAggregates {
+        cap_weighted_change_percent: cap_weighted_mean(
+            quotes.iter().filter_map(|q| Some((q.price_change_percent?, q.market_cap?))),
+        ),
+        change_quartiles: quartiles(&changes),
+        cap_weighted_pe_ratio: cap_weighted_pe(
+            quotes.iter().filter_map(|q| Some((q.pe_ratio?, q.market_cap?))),
+        ),
+        pe_quartiles: quartiles(&pes),
+    }
+}
+
+/// Mean of `(value, market_cap)` pairs weighted by cap. Members without a
+/// positive cap carry no weight.
+fn cap_weighted_mean(pairs: impl Iterator<Item = (f64, i64)>) -> Option<f64> {
+    let (weighted, total_cap) = pairs
+        .filter(|&(_, cap)| cap > 0)
+        .fold((0.0, 0.0), |(sum, caps), (value, cap)| {
+            (sum + value * cap as f64, caps + cap as f64)
+        });
+    (total_cap > 0.0).then(|| weighted / total_cap)
+}
+
+/// Sector P/E as the inverse of its aggregate earnings yield: total market cap
+/// over total earnings. Summing earnings rather than averaging ratios keeps one
+/// near-zero earner from blowing up the figure.
+///
+/// Only members with a positive P/E count, since the quote carries no P/E
+/// (and so no earnings figure) for loss-makers.
+fn cap_weighted_pe(pairs: impl Iterator<Item = (f64, i64)>) -> Option<f64> {
+    let (earnings, total_cap) = pairs
+        .filter(|&(pe, cap)| pe > 0.0 && cap > 0)
+        .fold((0.0, 0.0), |(earnings, caps), (pe, cap)| {
+            (earnings + cap as f64 / pe, caps + cap as f64)
+        });
+    (earnings > 0.0).then(|| total_cap / earnings)
+}
+
+/// First quartile, median and third quartile, or `None` for an empty sample.
+fn quartiles(values: &[f64]) -> Option<Quartiles> {
+    if values.is_empty() {
+        return None;
+    }
+    let sorted = sorted(values);
+    Some(Quartiles {
+        q1: percentile(&sorted, 25.0),
+        median: percentile(&sorted, 50.0),
+        q3: percentile(&sorted, 75.0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx_eq(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    fn quote(change: Option<f64>, cap: Option<i64>, pe: Option<f64>) -> MemberQuote {
+        MemberQuote {
+            group_id: 1,
+            price_change_percent: change,
+            market_cap: cap,
+            pe_ratio: pe,
+        }
+    }
+
+    // ---- cap_weighted_mean ----
+
+    #[test]
+    fn test_cap_weighted_mean_follows_the_giant() {
+        let pairs = [(1.0, 3_000_000_000_000), (-5.0, 2_000_000_000)];
+        let mean = cap_weighted_mean(pairs.into_iter()).unwrap();
+        assert!(mean > 0.99 && mean < 1.0, "{mean}");
+    }
+
+    #[test]
+    fn test_cap_weighted_mean_ignores_missing_caps() {
+        assert_eq!(cap_weighted_mean([(2.0, 0)].into_iter()), None);
+        assert_eq!(cap_weighted_mean(std::iter::empty()), None);
+    }
+
+    // ---- cap_weighted_pe ----
+
+    #[test]
+    fn test_cap_weighted_pe_inverts_aggregate_earnings_yield() {
+        // Earnings: 100/10 + 100/40 = 12.5 on 200 of cap → P/E 16
+        let pe = cap_weighted_pe([(10.0, 100), (40.0, 100)].into_iter()).unwrap();
+        assert!(approx_eq(pe, 16.0));
+    }
+
+    #[test]
+    fn test_cap_weighted_pe_skips_non_positive_ratios() {
+        let pe = cap_weighted_pe([(20.0, 100), (-5.0, 100), (0.0, 100)].into_iter()).unwrap();
+        assert!(approx_eq(pe, 20.0));
+        assert_eq!(cap_weighted_pe([(-5.0, 100)].into_iter()), None);
+    }
+
+    // ---- quartiles ----
+
+    #[test]
+    fn test_quartiles_interpolate() {
+        let q = quartiles(&[4.0, 1.0, 3.0, 2.0, 5.0]).unwrap();
+        assert!(approx_eq(q.q1, 2.0));
+        assert!(approx_eq(q.median, 3.0));
+        assert!(approx_eq(q.q3, 4.0));
+    }
+
+    #[test]
+    fn test_quartiles_empty() {
+        assert_eq!(quartiles(&[]), None);
+    }
+
+    // ---- aggregate ----
+
+    #[test]
+    fn test_aggregate_uses_each_members_available_fields() {
+        let quotes = [
+            quote(Some(2.0), Some(300), Some(30.0)),
+            quote(Some(-1.0), Some(100), None),
+            quote(None, None, Some(10.0)),
+        ];
+        let agg = aggregate(&quotes);
+        // (2*300 - 1*100) / 400
+        assert!(approx_eq(agg.cap_weighted_change_percent.unwrap(), 1.25));
+        assert!(approx_eq(agg.change_quartiles.unwrap().median, 0.5));
+        // Only the first member has both P/E and cap
+        assert!(approx_eq(agg.cap_weighted_pe_ratio.unwrap(), 30.0));
+        assert!(approx_eq(agg.pe_quartiles.unwrap().median, 20.0));
+    }
+
+    #[test]
+    fn test_aggregate_empty_sector() {
+        assert_eq!(aggregate(&[]), SectorAggregates::default());
+    }
+}
@@ -0,0 +1,335 @@
+use crate::alpha_vantage;
+use crate::market_data;
+use crate::outlier_detection;
+use crate::provider::{self, ProviderKind};
+use crate::refresh_engine;
+use crate::scheduler;
+use crate::types::{AppSettings, CompositeWeights};
+use sqlx::sqlite::SqlitePool;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const WEIGHT_PRICE: &str = "weight_price";
+const WEIGHT_PE: &str = "weight_pe";
+const WEIGHT_PB: &str = "weight_pb";
+const WEIGHT_VOLUME: &str = "weight_volume";
+const WEIGHT_DIVIDEND_YIELD: &str = "weight_dividend_yield";
+const WEIGHT_BETA: &str = "weight_beta";
+const WEIGHT_EPS: &str = "weight_eps";
+const WEIGHT_RANGE_POSITION: &str = "weight_range_position";
+const CACHE_TTL_MINUTES: &str = "cache_ttl_minutes";
+const SCHEDULER_ENABLED: &str = "scheduler_enabled";
+const SCHEDULER_INTERVAL_MINUTES: &str = "scheduler_interval_minutes";
+const YAHOO_REQUESTS_PER_SECOND: &str = "yahoo_requests_per_second";
+const ALPHA_VANTAGE_REQUESTS_PER_MINUTE: &str = "alpha_vantage_requests_per_minute";
+const REFRESH_CONCURRENCY: &str = "refresh_concurrency";
+
+const MAX_CACHE_TTL_MINUTES: u32 = 24 * 60;
+const MAX_YAHOO_REQUESTS_PER_SECOND: f64 = 50.0;
+const MAX_REFRESH_CONCURRENCY: u32 = 32;
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            composite_weights: outlier_detection::DEFAULT_WEIGHTS,
+            cache_ttl_minutes: 15,
+            scheduler_enabled: false,
+            scheduler_interval_minutes: scheduler::DEFAULT_INTERVAL_MINUTES,
+            market_data_provider: ProviderKind::Yahoo.as_str().to_string(),
+            yahoo_requests_per_second: market_data::YAHOO_REQUESTS_PER_SEC,
+            alpha_vantage_requests_per_minute: alpha_vantage::AV_REQUESTS_PER_MIN,
+            refresh_concurrency: refresh_engine::DEFAULT_CONCURRENCY as u32,
+        }
+    }
+}
+
+/// Reject settings that would break detection or hammer a provider.
+fn validate(config: &AppSettings) -> Result<(), String> {
+    outlier_detection::validate_weights(&config.composite_weights)?;
+
+    if !(1..=MAX_CACHE_TTL_MINUTES).contains(&config.cache_ttl_minutes) {
+        return Err(format!(
+            "Cache lifetime must be between 1 and {MAX_CACHE_TTL_MINUTES} minutes"
+        ));
+    }
+    if !(scheduler::MIN_INTERVAL_MINUTES..=scheduler::MAX_INTERVAL_MINUTES)
+        .contains(&config.scheduler_interval_minutes)
+    {
+        return Err(format!(
+            "Refresh interval must be between {} and {} minutes",
+            scheduler::MIN_INTERVAL_MINUTES,
+            scheduler::MAX_INTERVAL_MINUTES
+        ));
+    }
+    if ProviderKind::parse(&config.market_data_provider).is_none() {
+        return Err(format!(
+            "Unknown market data provider '{}'",
+            config.market_data_provider
+        ));
+    }
+    if !(config.yahoo_requests_per_second > 0.0
+        && config.yahoo_requests_per_second <= MAX_YAHOO_REQUESTS_PER_SECOND)
+    {
+        return Err(format!(
+            "Yahoo request rate must be above 0 and at most {MAX_YAHOO_REQUESTS_PER_SECOND}/s"
+        ));
+    }
+    if !(config.alpha_vantage_requests_per_minute > 0.0
+        && config.alpha_vantage_requests_per_minute.is_finite())
+    {
+        return Err("Alpha Vantage request rate must be above 0".to_string());
+    }
+    if !(1..=MAX_REFRESH_CONCURRENCY).contains(&config.refresh_concurrency) {
+        return Err(format!(
+            "Refresh concurrency must be between 1 and {MAX_REFRESH_CONCURRENCY}"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Parse a stored value, falling back to the default when it's missing or malformed.
+fn parse_or<T: FromStr>(values: &HashMap<String, String>, key: &str, default: T) -> T {
+    values
+        .get(key)
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+/// Build settings from raw `app_settings` rows. Unset keys keep their defaults.
+fn from_values(values: &HashMap<String, String>) -> AppSettings {
+    let d = AppSettings::default();
+    let w = d.composite_weights;
+    AppSettings {
+        composite_weights: CompositeWeights {
+            price: parse_or(values, WEIGHT_PRICE, w.price),
+            pe: parse_or(values, WEIGHT_PE, w.pe),
+            pb: parse_or(values, WEIGHT_PB, w.pb),
+            volume: parse_or(values, WEIGHT_VOLUME, w.volume),
+            dividend_yield: parse_or(values, WEIGHT_DIVIDEND_YIELD, w.dividend_yield),
+            beta: parse_or(values, WEIGHT_BETA, w.beta),
+            eps: parse_or(values, WEIGHT_EPS, w.eps),
+            range_position: parse_or(values, WEIGHT_RANGE_POSITION, w.range_position),
+        },
+        cache_ttl_minutes: parse_or(values, CACHE_TTL_MINUTES, d.cache_ttl_minutes),
+        scheduler_enabled: parse_or(values, SCHEDULER_ENABLED, d.scheduler_enabled),
+        scheduler_interval_minutes: parse_or(
+            values,
+            SCHEDULER_INTERVAL_MINUTES,
+            d.scheduler_interval_minutes,
+        ),
+        market_data_provider: values
+            .get(provider::PROVIDER_SETTING)
+            .and_then(|name| ProviderKind::parse(name))
+            .map_or(d.market_data_provider, |kind| kind.as_str().to_string()),
+        yahoo_requests_per_second: parse_or(
+            values,
+            YAHOO_REQUESTS_PER_SECOND,
+            d.yahoo_requests_per_second,
+        ),
+        alpha_vantage_requests_per_minute: parse_or(
+            values,
+            ALPHA_VANTAGE_REQUESTS_PER_MINUTE,
+            d.alpha_vantage_requests_per_minute,
+        ),
+        refresh_concurrency: parse_or(values, REFRESH_CONCURRENCY, d.refresh_concurrency),
+    }
+}
+
+/// Flatten settings into `app_settings` rows.
+fn to_values(config: &AppSettings) -> Vec<(&'static str, String)> {
+    let w = &config.composite_weights;
+    vec![
+        (WEIGHT_PRICE, w.price.to_string()),
+        (WEIGHT_PE, w.pe.to_string()),
+        (WEIGHT_PB, w.pb.to_string()),
+        (WEIGHT_VOLUME, w.volume.to_string()),
+        (WEIGHT_DIVIDEND_YIELD, w.dividend_yield.to_string()),
+        (WEIGHT_BETA, w.beta.to_string()),
+        (WEIGHT_EPS, w.eps.to_string()),
+        (WEIGHT_RANGE_POSITION, w.range_position.to_string()),
+        (CACHE_TTL_MINUTES, config.cache_ttl_minutes.to_string()),
+        (SCHEDULER_ENABLED, config.scheduler_enabled.to_string()),
+        (SCHEDULER_INTERVAL_MINUTES, config.scheduler_interval_minutes.to_string()),
+        (provider::PROVIDER_SETTING, config.market_data_provider.clone()),
+        (YAHOO_REQUESTS_PER_SECOND, config.yahoo_requests_per_second.to_string()),
+        (
+            ALPHA_VANTAGE_REQUESTS_PER_MINUTE,
+            config.alpha_vantage_requests_per_minute.to_string(),
+        ),
+        (REFRESH_CONCURRENCY, config.refresh_concurrency.to_string()),
+    ]
+}
+
+/// Read a single value from the `app_settings` table.
+pub async fn get_setting(pool: &SqlitePool, key: &str) -> Result<Option<String>, String> {
+    sqlx::query_scalar("SELECT value FROM app_settings WHERE key = ?")
+        .bind(key)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to read setting {key}: {e}"))
+}
+
+/// Insert or overwrite a single value in the `app_settings` table.
+pub async fn set_setting(pool: &SqlitePool, key: &str, value: &str) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO app_settings (key, value) VALUES (?, ?)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = CURRENT_TIMESTAMP",
+    )
+    .bind(key)
+    .bind(value)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save setting {key}: {e}"))?;
+
+    Ok(())
+}
+
+/// Load the typed settings, with defaults for anything never saved.
+pub async fn load_app_settings(pool: &SqlitePool) -> Result<AppSettings, String> {
+    let rows: Vec<(String, String)> = sqlx::query_as("SELECT key, value FROM app_settings")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to read settings: {e}"))?;
+
+    Ok(from_values(&rows.into_iter().collect()))
+}
+
+/// Validate and persist the typed settings in one transaction.
+pub async fn save_app_settings(pool: &SqlitePool, config: &AppSettings) -> Result<(), String> {
+    validate(config)?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {e}"))?;
+
+    for (key, value) in to_values(config) {
+        sqlx::query(
+            "INSERT INTO app_settings (key, value) VALUES (?, ?)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = CURRENT_TIMESTAMP",
+        )
+        .bind(key)
+        .bind(&value)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save setting {key}: {e}"))?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit settings: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(config: &AppSettings) -> HashMap<String, String> {
+        to_values(config)
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect()
+    }
+
+    // ---- defaults ----
+
+    #[test]
+    fn test_defaults_are_valid() {
+        assert!(validate(&AppSettings::default()).is_ok());
+    }
+
+    #[test]
+    fn test_empty_table_gives_defaults() {
+        assert_eq!(from_values(&HashMap::new()), AppSettings::default());
+    }
+
+    #[test]
+    fn test_scheduler_off_until_enabled() {
+        assert!(!from_values(&HashMap::new()).scheduler_enabled);
+    }
+
+    // ---- round trip ----
+
+    #[test]
+    fn test_round_trip_preserves_every_field() {
+        let config = AppSettings {
+            composite_weights: CompositeWeights {
+                volume: 0.0,
+                beta: 0.25,
+                ..outlier_detection::DEFAULT_WEIGHTS
+            },
+            cache_ttl_minutes: 5,
+            scheduler_enabled: true,
+            market_data_provider: "alpha_vantage".to_string(),
+            alpha_vantage_requests_per_minute: 75.0,
+            refresh_concurrency: 4,
+            ..AppSettings::default()
+        };
+        assert_eq!(from_values(&stored(&config)), config);
+    }
+
+    #[test]
+    fn test_malformed_value_falls_back_to_default() {
+        let mut values = stored(&AppSettings::default());
+        values.insert(REFRESH_CONCURRENCY.to_string(), "lots".to_string());
+        values.insert(provider::PROVIDER_SETTING.to_string(), "bloomberg".to_string());
+        assert_eq!(from_values(&values), AppSettings::default());
+    }
+
+    // ---- validation ----
+
+    #[test]
+    fn test_all_zero_weights_rejected() {
+        let config = AppSettings {
+            composite_weights: CompositeWeights {
+                price: 0.0,
+                pe: 0.0,
+                pb: 0.0,
+                volume: 0.0,
+                dividend_yield: 0.0,
+                beta: 0.0,
+                eps: 0.0,
+                range_position: 0.0,
+            },
+            ..AppSettings::default()
+        };
+        assert!(validate(&config).is_err());
+    }
+
+    #[test]
+    fn test_negative_weight_rejected() {
+        let config = AppSettings {
+            composite_weights: CompositeWeights { pe: -0.1, ..outlier_detection::DEFAULT_WEIGHTS },
+            ..AppSettings::default()
+        };
+        assert!(validate(&config).is_err());
+    }
+
+    #[test]
+    fn test_unknown_provider_rejected() {
+        let config = AppSettings {
+            market_data_provider: "bloomberg".to_string(),
+            ..AppSettings::default()
+        };
+        assert!(validate(&config).is_err());
+    }
+
+    #[test]
+    fn test_zero_concurrency_rejected() {
+        let config = AppSettings {
+            refresh_concurrency: 0,
+            ..AppSettings::default()
+        };
+        assert!(validate(&config).is_err());
+    }
+
+    #[test]
+    fn test_interval_outside_scheduler_bounds_rejected() {
+        let config = AppSettings {
+            scheduler_interval_minutes: 60,
+            ..AppSettings::default()
+        };
+        assert!(validate(&config).is_err());
+    }
+}
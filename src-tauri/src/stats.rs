@@ -0,0 +1,97 @@
+/// Calculate mean and standard deviation of a slice.
+pub fn mean_std(values: &[f64]) -> (f64, f64) {
+    let n = values.len() as f64;
+    if n < 1.0 {
+        return (0.0, 0.0);
+    }
+    let mean = values.iter().sum::<f64>() / n;
+    if n < 2.0 {
+        return (mean, 0.0);
+    }
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (mean, variance.sqrt())
+}
+
+/// Percentile (0-100) of an ascending slice, interpolating between ranks; 0 when empty.
+pub fn percentile(sorted: &[f64], pct: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (pct / 100.0).clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let lo = rank.floor() as usize;
+    let hi = rank.ceil() as usize;
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (rank - lo as f64)
+}
+
+/// Copy of `values` in ascending order, for `percentile`.
+pub fn sorted(values: &[f64]) -> Vec<f64> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    sorted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx_eq(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    // ---- mean_std ----
+
+    #[test]
+    fn test_mean_std_empty() {
+        let (mean, std) = mean_std(&[]);
+        assert_eq!(mean, 0.0);
+        assert_eq!(std, 0.0);
+    }
+
+    #[test]
+    fn test_mean_std_single_value() {
+        let (mean, std) = mean_std(&[5.0]);
+        assert!(approx_eq(mean, 5.0));
+        assert_eq!(std, 0.0);
+    }
+
+    #[test]
+    fn test_mean_std_two_values() {
+        // mean = (1+3)/2 = 2.0
+        // variance = ((1-2)² + (3-2)²) / (2-1) = 2.0, std = sqrt(2)
+        let (mean, std) = mean_std(&[1.0, 3.0]);
+        assert!(approx_eq(mean, 2.0));
+        assert!(approx_eq(std, 2.0_f64.sqrt()));
+    }
+
+    #[test]
+    fn test_mean_std_known_dataset() {
+        // [1, 2, 3]: mean=2, variance=((1-2)²+(2-2)²+(3-2)²)/2=1.0, std=1.0
+        let (mean, std) = mean_std(&[1.0, 2.0, 3.0]);
+        assert!(approx_eq(mean, 2.0));
+        assert!(approx_eq(std, 1.0));
+    }
+
+    #[test]
+    fn test_mean_std_all_same_values() {
+        // Zero std dev when all values are equal
+        let (mean, std) = mean_std(&[5.0, 5.0, 5.0, 5.0]);
+        assert!(approx_eq(mean, 5.0));
+        assert!(approx_eq(std, 0.0));
+    }
+
+    #[test]
+    fn test_mean_std_negative_values() {
+        // [-1, 0, 1]: mean=0.0, variance=(1+0+1)/2=1.0, std=1.0
+        let (mean, std) = mean_std(&[-1.0, 0.0, 1.0]);
+        assert!(approx_eq(mean, 0.0));
+        assert!(approx_eq(std, 1.0));
+    }
+
+    #[test]
+    fn test_percentile_interpolates() {
+        let sorted = [1.0, 2.0, 3.0, 4.0];
+        assert!(approx_eq(percentile(&sorted, 0.0), 1.0));
+        assert!(approx_eq(percentile(&sorted, 50.0), 2.5));
+        assert!(approx_eq(percentile(&sorted, 100.0), 4.0));
+    }
+}
@@ -0,0 +1,204 @@
+use crate::types::{DetectionRecord, PricePoint, PriceSource, UniverseMembership};
+use sqlx::sqlite::SqlitePool;
+
+#[derive(Debug, sqlx::FromRow)]
+struct DailyRow {
+    date: String,
+    open: Option<f64>,
+    high: Option<f64>,
+    low: Option<f64>,
+    close: f64,
+    adj_close: f64,
+    volume: Option<i64>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SnapshotRow {
+    timestamp: String,
+    price: f64,
+    volume: Option<i64>,
+}
+
+/// Resolve a ticker to its stock id.
+pub async fn find_stock_id(pool: &SqlitePool, symbol: &str) -> Result<i32, String> {
+    sqlx::query_scalar("SELECT id FROM stocks WHERE symbol = ?")
+        .bind(symbol.trim().to_uppercase())
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to look up {symbol}: {e}"))?
+        .ok_or_else(|| format!("Unknown symbol '{symbol}'"))
+}
+
+/// Merge daily bars with quote snapshots into one ascending series.
+///
+/// Daily bars are authoritative for the days they cover; snapshots only fill in
+/// days after the last stored bar (typically today's intraday refreshes).
+fn merge_history(daily: Vec<DailyRow>, snapshots: Vec<SnapshotRow>) -> Vec<PricePoint> {
+    let last_daily = daily.last().map(|d| d.date.clone());
+
+    let mut points: Vec<PricePoint> = daily
+        .into_iter()
+        .map(|d| PricePoint {
+            timestamp: d.date,
+            open: d.open,
+            high: d.high,
+            low: d.low,
+            close: d.close,
+            adj_close: Some(d.adj_close),
+            volume: d.volume,
+            source: PriceSource::Daily,
+        })
+        .collect();
+
+    points.extend(
+        snapshots
+            .into_iter()
+            // market_data timestamps are "YYYY-MM-DD HH:MM:SS", so the first 10 chars are the date
+            .filter(|s| last_daily.as_deref().is_none_or(|d| s.timestamp.get(..10).unwrap_or("") > d))
+            .map(|s| PricePoint {
+                timestamp: s.timestamp,
+                open: None,
+                high: None,
+                low: None,
+                close: s.price,
+                adj_close: None,
+                volume: s.volume,
+                source: PriceSource::Snapshot,
+            }),
+    );
+
+    points
+}
+
+/// Price series for a stock over the last `days` calendar days.
+pub async fn stock_history(
+    pool: &SqlitePool,
+    stock_id: i32,
+    days: u32,
+) -> Result<Vec<PricePoint>, String> {
+    let since = (chrono::Utc::now() - chrono::Duration::days(days as i64))
+        .format("%Y-%m-%d")
+        .to_string();
+
+    let daily: Vec<DailyRow> = sqlx::query_as(
+        "SELECT date, open, high, low, close, adj_close, volume
+         FROM price_history
+         WHERE stock_id = ? AND date >= ?
+         ORDER BY date",
+    )
+    .bind(stock_id)
+    .bind(&since)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch price history: {e}"))?;
+
+    let snapshots: Vec<SnapshotRow> = sqlx::query_as(
+        "SELECT timestamp, price, volume
+         FROM market_data
+         WHERE stock_id = ? AND timestamp >= ?
+         ORDER BY timestamp",
+    )
+    .bind(stock_id)
+    .bind(&since)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch market data history: {e}"))?;
+
+    Ok(merge_history(daily, snapshots))
+}
+
+/// Past outlier detections for a stock, newest first.
+pub async fn stock_detections(
+    pool: &SqlitePool,
+    stock_id: i32,
+    limit: u32,
+) -> Result<Vec<DetectionRecord>, String> {
+    sqlx::query_as(
+        "SELECT run_id, detection_date, detection_timestamp, sector_id, gics_level,
+                group_id, universe_type,
+                pe_z_score, pb_z_score, price_z_score, volume_z_score,
+                dividend_yield_z_score, beta_z_score, eps_z_score, range_position_z_score,
+                week52_position, composite_score, outlier_type, significance_level, threshold_used
+         FROM outlier_detections
+         WHERE stock_id = ?
+         ORDER BY detection_timestamp DESC
+         LIMIT ?",
+    )
+    .bind(stock_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch detections: {e}"))
+}
+
+/// Universe membership spans for a stock, oldest first.
+pub async fn stock_memberships(
+    pool: &SqlitePool,
+    stock_id: i32,
+) -> Result<Vec<UniverseMembership>, String> {
+    sqlx::query_as(
+        "SELECT universe_type, date_added, date_removed
+         FROM stock_universe
+         WHERE stock_id = ?
+         ORDER BY date_added, universe_type",
+    )
+    .bind(stock_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch universe membership: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn daily(date: &str, close: f64) -> DailyRow {
+        DailyRow {
+            date: date.to_string(),
+            open: Some(close),
+            high: Some(close),
+            low: Some(close),
+            close,
+            adj_close: close,
+            volume: Some(1_000),
+        }
+    }
+
+    fn snapshot(timestamp: &str, price: f64) -> SnapshotRow {
+        SnapshotRow {
+            timestamp: timestamp.to_string(),
+            price,
+            volume: None,
+        }
+    }
+
+    #[test]
+    fn test_merge_daily_only() {
+        let points = merge_history(vec![daily("2024-01-02", 10.0), daily("2024-01-03", 11.0)], vec![]);
+        assert_eq!(points.len(), 2);
+        assert!(points.iter().all(|p| p.source == PriceSource::Daily));
+    }
+
+    #[test]
+    fn test_merge_snapshots_only_when_no_daily_bars() {
+        let points = merge_history(vec![], vec![snapshot("2024-01-02 15:00:00", 10.0)]);
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].source, PriceSource::Snapshot);
+        assert!(points[0].adj_close.is_none());
+    }
+
+    #[test]
+    fn test_merge_drops_snapshots_covered_by_daily_bars() {
+        let points = merge_history(
+            vec![daily("2024-01-02", 10.0), daily("2024-01-03", 11.0)],
+            vec![
+                snapshot("2024-01-02 15:00:00", 10.1),
+                snapshot("2024-01-03 15:00:00", 11.1),
+                snapshot("2024-01-04 15:00:00", 12.0),
+            ],
+        );
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[2].timestamp, "2024-01-04 15:00:00");
+        assert_eq!(points[2].source, PriceSource::Snapshot);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::types::DiscoveryResult;
+use crate::universes;
 use reqwest::Client;
-use scraper::{Html, Selector};
+use scraper::{ElementRef, Html, Selector};
 use sqlx::sqlite::SqlitePool;
 use std::collections::HashMap;
 
@@ -9,64 +10,152 @@ struct WikiStock {
     symbol: String,
     name: String,
     gics_sector: String,
+    /// `false` when the list classifies by another scheme (ICB) and
+    /// `gics_sector` is only its closest GICS equivalent.
+    gics: bool,
+    /// GICS sub-industry, on lists that give one.
+    sub_industry: Option<String>,
 }
 
-/// Map known Wikipedia GICS sector name variants to internal DB sector names.
+/// Indexes whose constituents we read from a Wikipedia list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WikiIndex {
+    Sp500,
+    Sp400,
+    Sp600,
+    Nasdaq100,
+}
+
+impl WikiIndex {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WikiIndex::Sp500 => "S&P 500",
+            WikiIndex::Sp400 => "S&P MidCap 400",
+            WikiIndex::Sp600 => "S&P SmallCap 600",
+            WikiIndex::Nasdaq100 => "Nasdaq-100",
+        }
+    }
+
+    fn url(&self) -> &'static str {
+        match self {
+            WikiIndex::Sp500 => "https://en.wikipedia.org/wiki/List_of_S%26P_500_companies",
+            WikiIndex::Sp400 => "https://en.wikipedia.org/wiki/List_of_S%26P_400_companies",
+            WikiIndex::Sp600 => "https://en.wikipedia.org/wiki/List_of_S%26P_600_companies",
+            WikiIndex::Nasdaq100 => "https://en.wikipedia.org/wiki/Nasdaq-100",
+        }
+    }
+}
+
+/// Map known Wikipedia sector name variants to internal DB sector names.
 /// Returns the unchanged name if no alias is defined.
 fn apply_wikipedia_name_alias(name: &str) -> &str {
     match name {
         "Information Technology" => "Technology",
+        // ICB industry names, used by some of the lists
+        "Basic Materials" => "Materials",
+        "Telecommunications" => "Communication Services",
         _ => name,
     }
 }
 
-/// Parse an S&P 500 Wikipedia HTML page into a list of WikiStock entries.
-fn parse_sp500_html(html: &str) -> Result<Vec<WikiStock>, String> {
+/// Where a constituents table keeps each field.
+#[derive(Debug, PartialEq)]
+struct ConstituentColumns {
+    symbol: usize,
+    name: usize,
+    sector: usize,
+    gics: bool,
+    sub_industry: Option<usize>,
+}
+
+/// Find the symbol, name and sector columns from the header cells. The lists
+/// don't agree on column order or labels (e.g. "Symbol" and "Security" on the
+/// S&P 500, "Ticker" and "Company" on the Nasdaq-100). The GICS sub-industry
+/// column is optional.
+fn constituent_columns(headers: &[String]) -> Option<ConstituentColumns> {
+    let find = |labels: &[&str]| {
+        headers
+            .iter()
+            .position(|h| labels.contains(&h.trim().to_lowercase().as_str()))
+    };
+    let symbol = find(&["symbol", "ticker", "ticker symbol"])?;
+    let name = find(&["security", "company", "company name"])?;
+    let (sector, gics) = match find(&["gics sector", "sector"]) {
+        Some(col) => (col, true),
+        None => (find(&["icb industry", "industry"])?, false),
+    };
+    let sub_industry = if gics {
+        find(&["gics sub-industry", "sub-industry"])
+    } else {
+        None
+    };
+    Some(ConstituentColumns {
+        symbol,
+        name,
+        sector,
+        gics,
+        sub_industry,
+    })
+}
+
+/// Text of a cell, preferring the link text since Wikipedia wraps tickers
+/// and company names in `<a>` tags.
+fn cell_text(cell: &ElementRef, a_sel: &Selector) -> String {
+    cell.select(a_sel)
+        .next()
+        .map(|a| a.text().collect::<String>())
+        .unwrap_or_else(|| cell.text().collect::<String>())
+        .trim()
+        .to_string()
+}
+
+/// Parse a Wikipedia constituents list into WikiStock entries. The first
+/// `wikitable` with symbol, name and sector columns is taken as the list.
+fn parse_constituents_html(html: &str, index: WikiIndex) -> Result<Vec<WikiStock>, String> {
     let document = Html::parse_document(html);
-    let table_sel = Selector::parse("table.wikitable.sortable").unwrap();
+    let table_sel = Selector::parse("table.wikitable").unwrap();
     let tr_sel = Selector::parse("tr").unwrap();
+    let th_sel = Selector::parse("th").unwrap();
     let td_sel = Selector::parse("td").unwrap();
     let a_sel = Selector::parse("a").unwrap();
 
-    let table = document
+    let (table, cols) = document
         .select(&table_sel)
-        .next()
-        .ok_or_else(|| "Could not find S&P 500 table on Wikipedia".to_string())?;
+        .find_map(|table| {
+            let header = table.select(&tr_sel).next()?;
+            let headers: Vec<String> = header
+                .select(&th_sel)
+                .map(|th| th.text().collect::<String>())
+                .collect();
+            constituent_columns(&headers).map(|cols| (table, cols))
+        })
+        .ok_or_else(|| format!("Could not find {} table on Wikipedia", index.name()))?;
+    let last_col = cols.symbol.max(cols.name).max(cols.sector);
 
     let mut stocks = Vec::new();
 
     for row in table.select(&tr_sel).skip(1) {
         let cells: Vec<_> = row.select(&td_sel).collect();
-        if cells.len() < 4 {
+        if cells.len() <= last_col {
             continue;
         }
 
-        // Column 0: Symbol (inside <a> tag on Wikipedia)
-        let symbol = cells[0]
-            .select(&a_sel)
-            .next()
-            .map(|a| a.text().collect::<String>())
-            .unwrap_or_else(|| cells[0].text().collect::<String>())
-            .trim()
-            .to_string();
-
-        // Column 1: Security name (inside <a> tag on Wikipedia)
-        let name = cells[1]
-            .select(&a_sel)
-            .next()
-            .map(|a| a.text().collect::<String>())
-            .unwrap_or_else(|| cells[1].text().collect::<String>())
-            .trim()
-            .to_string();
-
-        // Column 2: GICS Sector
-        let gics_sector = cells[2].text().collect::<String>().trim().to_string();
+        let symbol = cell_text(&cells[cols.symbol], &a_sel);
+        let name = cell_text(&cells[cols.name], &a_sel);
+        let gics_sector = cells[cols.sector].text().collect::<String>().trim().to_string();
+        let sub_industry = cols
+            .sub_industry
+            .and_then(|col| cells.get(col))
+            .map(|cell| cell.text().collect::<String>().trim().to_string())
+            .filter(|text| !text.is_empty());
 
         if !symbol.is_empty() && !gics_sector.is_empty() {
             stocks.push(WikiStock {
                 symbol,
                 name,
                 gics_sector,
+                gics: cols.gics,
+                sub_industry,
             });
         }
     }
@@ -74,11 +163,10 @@ fn parse_sp500_html(html: &str) -> Result<Vec<WikiStock>, String> {
     Ok(stocks)
 }
 
-/// Fetch S&P 500 stock list from Wikipedia and parse HTML table.
-async fn fetch_sp500_from_wikipedia(client: &Client) -> Result<Vec<WikiStock>, String> {
-    let url = "https://en.wikipedia.org/wiki/List_of_S%26P_500_companies";
+/// Fetch an index's constituents list from Wikipedia and parse the HTML table.
+async fn fetch_from_wikipedia(client: &Client, index: WikiIndex) -> Result<Vec<WikiStock>, String> {
     let html = client
-        .get(url)
+        .get(index.url())
         .header("User-Agent", "SectorView/1.0")
         .send()
         .await
@@ -87,7 +175,7 @@ async fn fetch_sp500_from_wikipedia(client: &Client) -> Result<Vec<WikiStock>, S
         .await
         .map_err(|e| format!("Failed to read Wikipedia response: {e}"))?;
 
-    parse_sp500_html(&html)
+    parse_constituents_html(&html, index)
 }
 
 /// Build a mapping from DB sector names to sector IDs.
@@ -106,10 +194,27 @@ async fn build_sector_map(pool: &SqlitePool) -> Result<HashMap<String, i32>, Str
     Ok(map)
 }
 
-/// Discover S&P 500 stocks from Wikipedia and upsert into the database.
-pub async fn discover_stocks(pool: &SqlitePool, client: &Client) -> Result<DiscoveryResult, String> {
-    let wiki_stocks = fetch_sp500_from_wikipedia(client).await?;
+/// Build a mapping from GICS sub-industry names to their IDs.
+async fn build_sub_industry_map(pool: &SqlitePool) -> Result<HashMap<String, i32>, String> {
+    let rows: Vec<(i32, String)> = sqlx::query_as("SELECT id, name FROM gics_sub_industries")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to query sub-industries: {e}"))?;
+
+    Ok(rows.into_iter().map(|(id, name)| (name, id)).collect())
+}
+
+/// Discover an index's stocks from its Wikipedia list, upsert them, and
+/// reconcile the membership of `universe` with the list.
+pub async fn discover_stocks(
+    pool: &SqlitePool,
+    client: &Client,
+    index: WikiIndex,
+    universe: &str,
+) -> Result<DiscoveryResult, String> {
+    let wiki_stocks = fetch_from_wikipedia(client, index).await?;
     let sector_map = build_sector_map(pool).await?;
+    let sub_industry_map = build_sub_industry_map(pool).await?;
 
     let mut stocks_discovered: u32 = 0;
     let mut stocks_updated: u32 = 0;
@@ -127,66 +232,88 @@ pub async fn discover_stocks(pool: &SqlitePool, client: &Client) -> Result<Disco
             }
         };
 
+        // An unrecognised sub-industry leaves the stock classified down to its sector only
+        let sub_industry_id = match ws.sub_industry.as_deref() {
+            Some(name) => {
+                let id = sub_industry_map.get(name).copied();
+                if id.is_none() {
+                    errors.push(format!("Unknown sub-industry '{name}' for {}", ws.symbol));
+                }
+                id
+            }
+            None => None,
+        };
+
         // Check if stock already exists
-        let existing: Option<(i32, Option<i32>)> = sqlx::query_as(
-            "SELECT id, sector_id FROM stocks WHERE symbol = ?",
+        let existing: Option<(i32, Option<i32>, Option<i32>)> = sqlx::query_as(
+            "SELECT id, sector_id, sub_industry_id FROM stocks WHERE symbol = ?",
         )
         .bind(&ws.symbol)
         .fetch_optional(pool)
         .await
         .map_err(|e| format!("DB error checking {}: {e}", ws.symbol))?;
 
-        let stock_id = match existing {
-            Some((id, current_sector_id)) => {
-                if current_sector_id != Some(sector_id) {
-                    // Sector changed — update
-                    sqlx::query("UPDATE stocks SET sector_id = ?, name = ? WHERE symbol = ?")
-                        .bind(sector_id)
-                        .bind(&ws.name)
-                        .bind(&ws.symbol)
-                        .execute(pool)
-                        .await
-                        .map_err(|e| format!("Failed to update {}: {e}", ws.symbol))?;
+        match existing {
+            Some((_, current_sector_id, current_sub_industry_id)) => {
+                // A mapped ICB sector only fills in a missing one, so lists with
+                // different schemes don't keep reclassifying shared stocks
+                let reclassify = ws.gics || current_sector_id.is_none();
+                let changed = current_sector_id != Some(sector_id)
+                    || (ws.gics && current_sub_industry_id != sub_industry_id);
+                if changed && reclassify {
+                    // Classification changed — update
+                    sqlx::query(
+                        "UPDATE stocks SET sector_id = ?, sub_industry_id = ?, name = ? WHERE symbol = ?",
+                    )
+                    .bind(sector_id)
+                    .bind(sub_industry_id)
+                    .bind(&ws.name)
+                    .bind(&ws.symbol)
+                    .execute(pool)
+                    .await
+                    .map_err(|e| format!("Failed to update {}: {e}", ws.symbol))?;
                     stocks_updated += 1;
                 } else {
                     stocks_unchanged += 1;
                 }
-                id
             }
             None => {
                 // New stock — insert
-                let result =
-                    sqlx::query("INSERT INTO stocks (symbol, name, sector_id) VALUES (?, ?, ?)")
-                        .bind(&ws.symbol)
-                        .bind(&ws.name)
-                        .bind(sector_id)
-                        .execute(pool)
-                        .await
-                        .map_err(|e| format!("Failed to insert {}: {e}", ws.symbol))?;
+                sqlx::query(
+                    "INSERT INTO stocks (symbol, name, sector_id, sub_industry_id) VALUES (?, ?, ?, ?)",
+                )
+                .bind(&ws.symbol)
+                .bind(&ws.name)
+                .bind(sector_id)
+                .bind(sub_industry_id)
+                .execute(pool)
+                .await
+                .map_err(|e| format!("Failed to insert {}: {e}", ws.symbol))?;
                 stocks_discovered += 1;
-                result.last_insert_rowid() as i32
             }
-        };
-
-        // Track stock as S&P 500 member
-        sqlx::query(
-            "INSERT OR IGNORE INTO stock_universe (stock_id, universe_type) VALUES (?, 'sp500')",
-        )
-        .bind(stock_id)
-        .execute(pool)
-        .await
-        .map_err(|e| format!("Failed to upsert universe for {}: {e}", ws.symbol))?;
+        }
     }
 
+    let listed: Vec<String> = wiki_stocks.iter().map(|ws| ws.symbol.clone()).collect();
+    let members = universes::reconcile_members(pool, universe, &listed).await?;
+
     println!(
-        "Discovery complete: {} new, {} updated, {} unchanged, {} errors",
-        stocks_discovered, stocks_updated, stocks_unchanged, errors.len()
+        "{} discovery: {} new, {} updated, {} unchanged, {} joined, {} left, {} errors",
+        index.name(),
+        stocks_discovered,
+        stocks_updated,
+        stocks_unchanged,
+        members.added.len(),
+        members.removed.len(),
+        errors.len()
     );
 
     Ok(DiscoveryResult {
         stocks_discovered,
         stocks_updated,
         stocks_unchanged,
+        members_added: members.added,
+        members_removed: members.removed,
         errors,
     })
 }
@@ -259,12 +386,12 @@ mod tests {
         assert_eq!(apply_wikipedia_name_alias(""), "");
     }
 
-    // ---- parse_sp500_html ----
+    // ---- parse_constituents_html ----
 
     #[test]
     fn test_parse_single_stock() {
         let html = make_wiki_html(&[("AAPL", "Apple Inc.", "Information Technology")]);
-        let stocks = parse_sp500_html(&html).unwrap();
+        let stocks = parse_constituents_html(&html, WikiIndex::Sp500).unwrap();
         assert_eq!(stocks.len(), 1);
         assert_eq!(stocks[0].symbol, "AAPL");
         assert_eq!(stocks[0].name, "Apple Inc.");
@@ -278,7 +405,7 @@ mod tests {
             ("JPM", "JPMorgan Chase", "Financials"),
             ("XOM", "Exxon Mobil", "Energy"),
         ]);
-        let stocks = parse_sp500_html(&html).unwrap();
+        let stocks = parse_constituents_html(&html, WikiIndex::Sp500).unwrap();
         assert_eq!(stocks.len(), 3);
         assert_eq!(stocks[0].symbol, "AAPL");
         assert_eq!(stocks[1].symbol, "JPM");
@@ -289,7 +416,7 @@ mod tests {
     fn test_parse_symbol_in_anchor_tag() {
         // Wikipedia wraps the ticker in an <a> tag — verify it's extracted from the link text
         let html = make_wiki_html(&[("MSFT", "Microsoft Corporation", "Information Technology")]);
-        let stocks = parse_sp500_html(&html).unwrap();
+        let stocks = parse_constituents_html(&html, WikiIndex::Sp500).unwrap();
         assert_eq!(stocks[0].symbol, "MSFT");
     }
 
@@ -301,7 +428,7 @@ mod tests {
         html.push_str(&make_plain_row("GOOG", "Alphabet Inc.", "Communication Services"));
         html.push_str("</tbody></table>");
 
-        let stocks = parse_sp500_html(&html).unwrap();
+        let stocks = parse_constituents_html(&html, WikiIndex::Sp500).unwrap();
         assert_eq!(stocks.len(), 1);
         assert_eq!(stocks[0].symbol, "GOOG");
         assert_eq!(stocks[0].name, "Alphabet Inc.");
@@ -317,7 +444,7 @@ mod tests {
         );
         html.push_str("</tbody></table>");
 
-        let stocks = parse_sp500_html(&html).unwrap();
+        let stocks = parse_constituents_html(&html, WikiIndex::Sp500).unwrap();
         assert_eq!(stocks[0].symbol, "AMZN");
         assert_eq!(stocks[0].name, "Amazon.com Inc.");
         assert_eq!(stocks[0].gics_sector, "Consumer Discretionary");
@@ -327,7 +454,7 @@ mod tests {
     fn test_parse_skips_header_row() {
         // The header <tr> contains <th> not <td>, so the td selector yields 0 cells → skipped
         let html = make_wiki_html(&[("WMT", "Walmart Inc.", "Consumer Staples")]);
-        let stocks = parse_sp500_html(&html).unwrap();
+        let stocks = parse_constituents_html(&html, WikiIndex::Sp500).unwrap();
         // Only 1 stock row, not 2 (header not included)
         assert_eq!(stocks.len(), 1);
     }
@@ -342,7 +469,7 @@ mod tests {
         html.push_str(&make_plain_row("JPM", "JPMorgan Chase", "Financials"));
         html.push_str("</tbody></table>");
 
-        let stocks = parse_sp500_html(&html).unwrap();
+        let stocks = parse_constituents_html(&html, WikiIndex::Sp500).unwrap();
         assert_eq!(stocks.len(), 1);
         assert_eq!(stocks[0].symbol, "JPM");
     }
@@ -356,7 +483,7 @@ mod tests {
         html.push_str(&make_plain_row("JPM", "JPMorgan Chase", "Financials"));
         html.push_str("</tbody></table>");
 
-        let stocks = parse_sp500_html(&html).unwrap();
+        let stocks = parse_constituents_html(&html, WikiIndex::Sp500).unwrap();
         assert_eq!(stocks.len(), 1);
         assert_eq!(stocks[0].symbol, "JPM");
     }
@@ -370,7 +497,7 @@ mod tests {
         html.push_str(&make_plain_row("JPM", "JPMorgan Chase", "Financials"));
         html.push_str("</tbody></table>");
 
-        let stocks = parse_sp500_html(&html).unwrap();
+        let stocks = parse_constituents_html(&html, WikiIndex::Sp500).unwrap();
         assert_eq!(stocks.len(), 1);
         assert_eq!(stocks[0].symbol, "JPM");
     }
@@ -379,7 +506,7 @@ mod tests {
     fn test_parse_empty_table_returns_no_stocks() {
         // Table with only a header row and no data rows
         let html = make_wiki_html(&[]);
-        let stocks = parse_sp500_html(&html).unwrap();
+        let stocks = parse_constituents_html(&html, WikiIndex::Sp500).unwrap();
         assert!(stocks.is_empty());
     }
 
@@ -387,7 +514,7 @@ mod tests {
     fn test_parse_no_wikitable_returns_error() {
         // HTML with no table.wikitable.sortable
         let html = "<html><body><table><tr><td>No class</td></tr></table></body></html>";
-        let result = parse_sp500_html(html);
+        let result = parse_constituents_html(html, WikiIndex::Sp500);
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("Could not find S&P 500 table"));
     }
@@ -395,7 +522,7 @@ mod tests {
     #[test]
     fn test_parse_malformed_html_returns_error() {
         // Completely empty / non-HTML string
-        let result = parse_sp500_html("not html at all");
+        let result = parse_constituents_html("not html at all", WikiIndex::Sp500);
         assert!(result.is_err());
     }
 
@@ -416,7 +543,7 @@ mod tests {
             ("LIN", "Linde plc", "Materials"),
         ];
         let html = make_wiki_html(&rows);
-        let stocks = parse_sp500_html(&html).unwrap();
+        let stocks = parse_constituents_html(&html, WikiIndex::Sp500).unwrap();
         assert_eq!(stocks.len(), 11);
 
         let sectors: Vec<&str> = stocks.iter().map(|s| s.gics_sector.as_str()).collect();
@@ -425,13 +552,92 @@ mod tests {
         assert!(sectors.contains(&"Financials"));
     }
 
+    #[test]
+    fn test_parse_ticker_first_layout() {
+        // Nasdaq-100 style: ticker before company, ICB classification
+        let mut html = String::from(r#"<table class="wikitable sortable" id="constituents"><tbody>"#);
+        html.push_str("<tr><th>Ticker</th><th>Company</th><th>ICB Industry</th><th>ICB Subsector</th></tr>");
+        html.push_str(r#"<tr><td>ADBE</td><td><a href="/wiki/Adobe">Adobe Inc.</a></td><td>Technology</td><td>Software</td></tr>"#);
+        html.push_str("</tbody></table>");
+
+        let stocks = parse_constituents_html(&html, WikiIndex::Nasdaq100).unwrap();
+        assert_eq!(stocks.len(), 1);
+        assert_eq!(stocks[0].symbol, "ADBE");
+        assert_eq!(stocks[0].name, "Adobe Inc.");
+        assert_eq!(stocks[0].gics_sector, "Technology");
+        assert!(!stocks[0].gics);
+    }
+
+    #[test]
+    fn test_parse_skips_tables_without_constituent_columns() {
+        let mut html = String::from(r#"<table class="wikitable"><tbody>"#);
+        html.push_str("<tr><th>Year</th><th>Return</th></tr><tr><td>2023</td><td>53.8%</td></tr>");
+        html.push_str("</tbody></table>");
+        html.push_str(&make_wiki_html(&[("DECK", "Deckers Brands", "Consumer Discretionary")]));
+
+        let stocks = parse_constituents_html(&html, WikiIndex::Sp400).unwrap();
+        assert_eq!(stocks.len(), 1);
+        assert_eq!(stocks[0].symbol, "DECK");
+        assert!(stocks[0].gics);
+    }
+
+    #[test]
+    fn test_parse_missing_table_names_the_index() {
+        let err = parse_constituents_html("<table class=\"wikitable\"></table>", WikiIndex::Sp600)
+            .unwrap_err();
+        assert!(err.contains("S&P SmallCap 600"));
+    }
+
+    #[test]
+    fn test_constituent_columns_prefers_gics() {
+        let headers: Vec<String> = ["Company", "Symbol", "Industry", "GICS Sector"]
+            .iter()
+            .map(|h| h.to_string())
+            .collect();
+        assert_eq!(
+            constituent_columns(&headers),
+            Some(ConstituentColumns {
+                symbol: 1,
+                name: 0,
+                sector: 3,
+                gics: true,
+                sub_industry: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_gics_sub_industry() {
+        let html = make_wiki_html(&[("NVDA", "Nvidia", "Information Technology")])
+            .replace("<td>Sub</td>", "<td> Semiconductors </td>");
+        let stocks = parse_constituents_html(&html, WikiIndex::Sp500).unwrap();
+        assert_eq!(stocks[0].sub_industry.as_deref(), Some("Semiconductors"));
+    }
+
+    #[test]
+    fn test_parse_ignores_icb_subsector() {
+        let mut html = String::from(r#"<table class="wikitable"><tbody>"#);
+        html.push_str("<tr><th>Ticker</th><th>Company</th><th>ICB Industry</th><th>Sub-Industry</th></tr>");
+        html.push_str("<tr><td>ADBE</td><td>Adobe Inc.</td><td>Technology</td><td>Software</td></tr>");
+        html.push_str("</tbody></table>");
+
+        let stocks = parse_constituents_html(&html, WikiIndex::Nasdaq100).unwrap();
+        assert_eq!(stocks[0].sub_industry, None);
+    }
+
+    #[test]
+    fn test_alias_icb_industries() {
+        assert_eq!(apply_wikipedia_name_alias("Basic Materials"), "Materials");
+        assert_eq!(apply_wikipedia_name_alias("Telecommunications"), "Communication Services");
+    }
+
     // ---- alias + parse integration ----
 
     #[test]
     fn test_alias_applied_after_parsing_maps_it_to_technology() {
         // Simulate what discover_stocks does: parse HTML then apply alias during lookup
         let html = make_wiki_html(&[("AAPL", "Apple Inc.", "Information Technology")]);
-        let stocks = parse_sp500_html(&html).unwrap();
+        let stocks = parse_constituents_html(&html, WikiIndex::Sp500).unwrap();
 
         let canonical = apply_wikipedia_name_alias(&stocks[0].gics_sector);
         assert_eq!(canonical, "Technology");
@@ -440,7 +646,7 @@ mod tests {
     #[test]
     fn test_alias_applied_after_parsing_leaves_other_sectors_unchanged() {
         let html = make_wiki_html(&[("JPM", "JPMorgan Chase", "Financials")]);
-        let stocks = parse_sp500_html(&html).unwrap();
+        let stocks = parse_constituents_html(&html, WikiIndex::Sp500).unwrap();
 
         let canonical = apply_wikipedia_name_alias(&stocks[0].gics_sector);
         assert_eq!(canonical, "Financials");
@@ -458,7 +664,7 @@ mod tests {
         let html = make_wiki_html(&rows);
 
         let start = Instant::now();
-        let stocks = parse_sp500_html(&html).unwrap();
+        let stocks = parse_constituents_html(&html, WikiIndex::Sp500).unwrap();
         let elapsed = start.elapsed();
 
         assert_eq!(stocks.len(), 500);
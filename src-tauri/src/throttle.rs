@@ -0,0 +1,231 @@
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Token bucket: holds up to `capacity` tokens, refilled continuously at `refill_per_sec`.
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(refill_per_sec: f64, burst: u32, now: Instant) -> Self {
+        let capacity = burst.max(1) as f64;
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_per_sec: refill_per_sec.max(f64::EPSILON),
+            last_refill: now,
+        }
+    }
+
+    /// Take a token if one is available, otherwise return how long until one will be.
+    fn try_acquire(&mut self, now: Instant) -> Option<Duration> {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - self.tokens) / self.refill_per_sec))
+        }
+    }
+}
+
+/// Exponential backoff for retryable HTTP failures.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `attempt` (0-based): base × 2^attempt, capped at `max_delay`.
+    fn delay_for(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt);
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+/// Rate-limited, retrying request sender shared by every request a provider makes.
+pub struct Throttle {
+    bucket: Mutex<TokenBucket>,
+    retry: RetryPolicy,
+}
+
+/// 429 and 5xx are worth retrying; anything else is the caller's problem.
+fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Parse a `Retry-After` header given in seconds (HTTP-date form is ignored).
+fn retry_after(resp: &Response) -> Option<Duration> {
+    resp.headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+impl Throttle {
+    pub fn new(requests_per_sec: f64, burst: u32, retry: RetryPolicy) -> Self {
+        Self {
+            bucket: Mutex::new(TokenBucket::new(requests_per_sec, burst, Instant::now())),
+            retry,
+        }
+    }
+
+    /// Wait until the token bucket allows another request.
+    async fn acquire(&self) {
+        loop {
+            let wait = match self.bucket.lock() {
+                Ok(mut bucket) => bucket.try_acquire(Instant::now()),
+                Err(_) => None,
+            };
+            match wait {
+                None => return,
+                Some(d) => tokio::time::sleep(d).await,
+            }
+        }
+    }
+
+    /// Send a request, retrying 429/5xx responses and network errors with backoff.
+    /// The final response is returned as-is, so callers still check its status.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response, String> {
+        let mut attempt = 0;
+        loop {
+            // Bodies we send are always clonable GETs; if not, fall back to a single try
+            let current = match request.try_clone() {
+                Some(r) => r,
+                None => {
+                    self.acquire().await;
+                    return request.send().await.map_err(|e| e.to_string());
+                }
+            };
+
+            self.acquire().await;
+            let can_retry = attempt < self.retry.max_retries;
+
+            match current.send().await {
+                Ok(resp) if can_retry && is_retryable_status(resp.status()) => {
+                    let delay = retry_after(&resp)
+                        .map(|d| d.min(self.retry.max_delay))
+                        .unwrap_or_else(|| self.retry.delay_for(attempt));
+                    tokio::time::sleep(delay).await;
+                }
+                Ok(resp) => return Ok(resp),
+                Err(e) if can_retry && (e.is_timeout() || e.is_connect()) => {
+                    tokio::time::sleep(self.retry.delay_for(attempt)).await;
+                }
+                Err(e) => return Err(e.to_string()),
+            }
+
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ---- TokenBucket ----
+
+    #[test]
+    fn test_bucket_allows_burst_then_waits() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(1.0, 3, start);
+        assert!(bucket.try_acquire(start).is_none());
+        assert!(bucket.try_acquire(start).is_none());
+        assert!(bucket.try_acquire(start).is_none());
+        let wait = bucket.try_acquire(start).expect("bucket should be empty");
+        assert!(wait > Duration::from_millis(900) && wait <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_bucket_refills_over_time() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(10.0, 1, start);
+        assert!(bucket.try_acquire(start).is_none());
+        assert!(bucket.try_acquire(start).is_some());
+        // 10/s → one token every 100ms
+        assert!(bucket.try_acquire(start + Duration::from_millis(100)).is_none());
+    }
+
+    #[test]
+    fn test_bucket_never_exceeds_capacity() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(100.0, 2, start);
+        let later = start + Duration::from_secs(60);
+        assert!(bucket.try_acquire(later).is_none());
+        assert!(bucket.try_acquire(later).is_none());
+        assert!(bucket.try_acquire(later).is_some());
+    }
+
+    #[test]
+    fn test_bucket_zero_burst_still_allows_one() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(1.0, 0, start);
+        assert!(bucket.try_acquire(start).is_none());
+    }
+
+    // ---- RetryPolicy ----
+
+    #[test]
+    fn test_retry_delay_doubles() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+        };
+        assert_eq!(policy.delay_for(0), Duration::from_millis(100));
+        assert_eq!(policy.delay_for(1), Duration::from_millis(200));
+        assert_eq!(policy.delay_for(3), Duration::from_millis(800));
+    }
+
+    #[test]
+    fn test_retry_delay_is_capped() {
+        let policy = RetryPolicy {
+            max_retries: 50,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(5),
+        };
+        assert_eq!(policy.delay_for(10), Duration::from_secs(5));
+        assert_eq!(policy.delay_for(40), Duration::from_secs(5));
+    }
+
+    // ---- is_retryable_status ----
+
+    #[test]
+    fn test_retryable_statuses() {
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(is_retryable_status(StatusCode::BAD_GATEWAY));
+        assert!(is_retryable_status(StatusCode::SERVICE_UNAVAILABLE));
+    }
+
+    #[test]
+    fn test_non_retryable_statuses() {
+        assert!(!is_retryable_status(StatusCode::OK));
+        assert!(!is_retryable_status(StatusCode::NOT_FOUND));
+        assert!(!is_retryable_status(StatusCode::UNAUTHORIZED));
+    }
+}
@@ -6,6 +6,10 @@ pub struct DiscoveryResult {
     pub stocks_discovered: u32,
     pub stocks_updated: u32,
     pub stocks_unchanged: u32,
+    /// Symbols that joined the universe, including ones rejoining after a removal.
+    pub members_added: Vec<String>,
+    /// Symbols that are no longer on the source's list.
+    pub members_removed: Vec<String>,
     pub errors: Vec<String>,
 }
 
@@ -15,6 +19,13 @@ pub struct RefreshResult {
     pub discovery: Option<DiscoveryResult>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackfillResult {
+    pub stocks_processed: u32,
+    pub bars_written: u32,
+    pub errors: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Sector {
     pub id: i32,
@@ -40,16 +51,210 @@ pub struct SectorSummary {
     pub total_market_cap: Option<i64>,
     pub stock_count: i32,
     pub avg_beta: Option<f64>,
+    /// Average member return over each horizon.
+    pub returns: PeriodReturns,
+    /// Change weighted by market cap, comparable to the sector ETF's move.
+    pub cap_weighted_change_percent: Option<f64>,
+    pub change_quartiles: Option<Quartiles>,
+    /// Total market cap over total earnings of members with a positive P/E.
+    pub cap_weighted_pe_ratio: Option<f64>,
+    pub pe_quartiles: Option<Quartiles>,
+    /// Latest quote for the sector's ETF, once one has been fetched.
+    pub benchmark: Option<SectorBenchmark>,
+    /// Cap-weighted constituent change minus the ETF's change, in percentage points.
+    pub benchmark_divergence: Option<f64>,
 }
 
-// -- Outlier Detection Types --
+/// Level of the GICS hierarchy that stocks are grouped and compared at.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GicsLevel {
+    #[default]
+    Sector,
+    IndustryGroup,
+    Industry,
+    SubIndustry,
+}
+
+/// Roll-up of a universe's members in one GICS group at any level.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupSummary {
+    pub level: GicsLevel,
+    pub group_id: i32,
+    pub name: String,
+    /// Sector the group belongs to (the group itself at the sector level).
+    pub sector_id: i32,
+    pub avg_change_percent: f64,
+    pub avg_pe_ratio: Option<f64>,
+    pub total_market_cap: Option<i64>,
+    pub stock_count: i32,
+    pub avg_beta: Option<f64>,
+    pub returns: PeriodReturns,
+    pub cap_weighted_change_percent: Option<f64>,
+    pub change_quartiles: Option<Quartiles>,
+    pub cap_weighted_pe_ratio: Option<f64>,
+    pub pe_quartiles: Option<Quartiles>,
+}
+
+/// A sector ETF quote.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectorBenchmark {
+    pub symbol: String,
+    pub price: f64,
+    pub change_percent: f64,
+    pub as_of: String,
+}
+
+/// Spread of a metric across a sector's members.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Quartiles {
+    pub q1: f64,
+    pub median: f64,
+    pub q3: f64,
+}
+
+/// Lookback window for price returns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReturnHorizon {
+    #[default]
+    OneDay,
+    OneWeek,
+    OneMonth,
+    ThreeMonths,
+    YearToDate,
+}
+
+/// Percent returns by horizon. `None` where there isn't enough stored history.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PeriodReturns {
+    pub one_day: Option<f64>,
+    pub one_week: Option<f64>,
+    pub one_month: Option<f64>,
+    pub three_months: Option<f64>,
+    pub year_to_date: Option<f64>,
+}
+
+/// RRG-style quadrant: where a sector sits on relative strength (RS-Ratio)
+/// and the direction it's heading (RS-Momentum).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RotationQuadrant {
+    Leading,
+    Weakening,
+    Lagging,
+    Improving,
+}
+
+/// One session on a sector's rotation path. Both axes are centred on 100.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RotationPoint {
+    pub date: String,
+    pub rs_ratio: f64,
+    pub rs_momentum: f64,
+    pub quadrant: RotationQuadrant,
+}
+
+/// A sector's strength against its universe and its recent rotation path.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectorRotation {
+    pub sector_id: i32,
+    pub name: String,
+    pub symbol: String,
+    /// 1 = strongest over the ranking horizon; `None` without enough history.
+    pub rank: Option<usize>,
+    pub quadrant: Option<RotationQuadrant>,
+    /// Sector return minus universe return by horizon, in percentage points.
+    pub relative_strength: PeriodReturns,
+    /// Oldest first, ending at the latest session.
+    pub trajectory: Vec<RotationPoint>,
+}
+
+/// Participation within a group of stocks: how many are moving, and which way.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BreadthMetrics {
+    pub stock_count: u32,
+    pub advancers: u32,
+    pub decliners: u32,
+    pub unchanged: u32,
+    /// Share of members trading above their 50-day average, among those with
+    /// 50 sessions of stored history.
+    pub percent_above_50d: Option<f64>,
+    pub percent_above_200d: Option<f64>,
+    /// Members at (or within half a percent of) their 52-week high or low.
+    pub new_highs: u32,
+    pub new_lows: u32,
+    /// 10-session EMA of the daily advance ratio, 0 to 1.
+    pub thrust_ema: Option<f64>,
+    /// The EMA went from below 0.40 to above 0.615 within the last 10 sessions.
+    pub breadth_thrust: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectorBreadth {
+    pub sector_id: i32,
+    pub name: String,
+    pub symbol: String,
+    pub breadth: BreadthMetrics,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketBreadth {
+    pub universe: BreadthMetrics,
+    pub sectors: Vec<SectorBreadth>,
+}
+
+// -- Outlier Detection Types --
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ZScores {
     pub pe_z: Option<f64>,
     pub pb_z: Option<f64>,
     pub price_z: f64,
     pub volume_z: Option<f64>,
+    pub dividend_yield_z: Option<f64>,
+    pub beta_z: Option<f64>,
+    pub eps_z: Option<f64>,
+    /// Z-score of the percent-of-52-week-range position.
+    pub range_position_z: Option<f64>,
+    /// How each of the scores above was estimated.
+    pub methods: ZScoreMethods,
+}
+
+/// Estimator behind a z-score.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ZScoreMethod {
+    /// (x - mean) / sample standard deviation.
+    #[default]
+    MeanStd,
+    /// Modified z-score: 0.6745 * (x - median) / MAD.
+    MedianMad,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ZScoreMethods {
+    pub price: ZScoreMethod,
+    pub pe: ZScoreMethod,
+    pub pb: ZScoreMethod,
+    pub volume: ZScoreMethod,
+    pub dividend_yield: ZScoreMethod,
+    pub beta: ZScoreMethod,
+    pub eps: ZScoreMethod,
+    pub range_position: ZScoreMethod,
+    /// Sector center and spread were estimated on a winsorized sample.
+    pub winsorized: bool,
+}
+
+/// Percentile bounds (0-100) at which sector samples are clipped.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WinsorLimits {
+    pub lower_percentile: f64,
+    pub upper_percentile: f64,
+}
+
+/// How sector statistics are estimated for one detection run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct StatsOptions {
+    pub method: ZScoreMethod,
+    pub winsorize: Option<WinsorLimits>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -59,6 +264,10 @@ pub enum OutlierType {
     Momentum,
     ValueTrap,
     GrowthPremium,
+    /// High yield propped up by a falling price or weak earnings.
+    YieldTrap,
+    NearYearLow,
+    NearYearHigh,
     Mixed,
 }
 
@@ -70,6 +279,9 @@ impl std::fmt::Display for OutlierType {
             OutlierType::Momentum => write!(f, "Momentum"),
             OutlierType::ValueTrap => write!(f, "ValueTrap"),
             OutlierType::GrowthPremium => write!(f, "GrowthPremium"),
+            OutlierType::YieldTrap => write!(f, "YieldTrap"),
+            OutlierType::NearYearLow => write!(f, "NearYearLow"),
+            OutlierType::NearYearHigh => write!(f, "NearYearHigh"),
             OutlierType::Mixed => write!(f, "Mixed"),
         }
     }
@@ -98,9 +310,20 @@ pub struct OutlierStock {
     pub symbol: String,
     pub name: String,
     pub z_scores: ZScores,
+    /// Percent of the 52-week range (0 = at the low, 100 = at the high).
+    pub week52_position: Option<f64>,
+    /// Today's change minus the sector ETF's, in percentage points. Always
+    /// one-day, whatever horizon the z-scores were computed over.
+    pub excess_return_1d_percent: Option<f64>,
     pub composite_score: f64,
     pub outlier_type: OutlierType,
     pub significance_level: SignificanceLevel,
+    /// Where this detection sits in the stock's run of consecutive flags.
+    pub status: EpisodeStatus,
+    /// Days flagged in a row, counting today.
+    pub streak_days: u32,
+    /// Date the current run of flags began.
+    pub first_seen: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -108,6 +331,282 @@ pub struct SectorOutliers {
     pub sector_id: i32,
     pub sector_name: String,
     pub sector_symbol: String,
+    /// Peer group the outliers were scored against, the sector itself at
+    /// the sector level.
+    pub level: GicsLevel,
+    pub group_id: i32,
+    pub group_name: String,
     pub outlier_count: usize,
     pub outliers: Vec<OutlierStock>,
+    /// Stocks flagged at the previous detection session that no longer are,
+    /// scored as they stand now.
+    pub resolved: Vec<OutlierStock>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EpisodeStatus {
+    /// First day of a run.
+    #[default]
+    New,
+    Continuing,
+    /// Flagged for at least a week of sessions in a row.
+    Persistent,
+    /// Was flagged at the previous session, no longer is.
+    Resolved,
+}
+
+/// A run of detections of one stock on consecutive trading days.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutlierEpisode {
+    pub stock_id: i32,
+    pub symbol: String,
+    pub sector_id: i32,
+    pub first_seen: String,
+    pub last_seen: String,
+    /// Distinct days the stock was flagged during the run.
+    pub consecutive_days: u32,
+    pub peak_composite_score: f64,
+    /// Classification at the latest detection in the run.
+    pub outlier_type: String,
+    /// How many times the classification changed between detections.
+    pub type_changes: u32,
+}
+
+/// Outcome of past signals at one forward horizon. Returns are in percent;
+/// excess is over the equal-weight basket of the stock's sector peers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForwardStats {
+    /// Trading sessions after the signal.
+    pub sessions: u32,
+    /// Signals with enough history to measure.
+    pub count: usize,
+    pub mean_return: Option<f64>,
+    pub mean_excess_return: Option<f64>,
+    /// Share of signals that beat their sector, 0 to 1.
+    pub hit_rate: Option<f64>,
+    /// Mean excess return over its standard error; `None` below two signals.
+    pub t_stat: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestGroup {
+    pub outlier_type: String,
+    /// `None` for the row covering every significance level.
+    pub significance_level: Option<String>,
+    pub horizons: Vec<ForwardStats>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestReport {
+    pub universe: String,
+    pub start_date: String,
+    pub end_date: String,
+    /// Episodes that started in the window, counted on their first day.
+    pub signal_count: usize,
+    pub groups: Vec<BacktestGroup>,
+}
+
+// -- Stock Drill-in Types --
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PriceSource {
+    /// Daily bar from `price_history`.
+    Daily,
+    /// Quote snapshot from `market_data`.
+    Snapshot,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PricePoint {
+    pub timestamp: String,
+    pub open: Option<f64>,
+    pub high: Option<f64>,
+    pub low: Option<f64>,
+    pub close: f64,
+    pub adj_close: Option<f64>,
+    pub volume: Option<i64>,
+    pub source: PriceSource,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StockZScores {
+    pub stock_id: i32,
+    pub sector_id: i32,
+    /// Level of the peer group and its id at that level.
+    pub level: GicsLevel,
+    pub group_id: i32,
+    pub universe: String,
+    pub peer_count: usize,
+    pub z_scores: ZScores,
+    pub week52_position: Option<f64>,
+    /// One-day change minus the sector ETF's, as on `OutlierStock`.
+    pub excess_return_1d_percent: Option<f64>,
+    pub composite_score: f64,
+    pub outlier_type: OutlierType,
+    pub significance_level: SignificanceLevel,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DetectionRecord {
+    /// Detection run the record was written by; `None` for records that predate runs.
+    pub run_id: Option<i64>,
+    pub detection_date: String,
+    pub detection_timestamp: String,
+    pub sector_id: i32,
+    /// GICS level the stock was scored at, and its group at that level.
+    pub gics_level: String,
+    pub group_id: i32,
+    pub universe_type: String,
+    pub pe_z_score: Option<f64>,
+    pub pb_z_score: Option<f64>,
+    pub price_z_score: f64,
+    pub volume_z_score: Option<f64>,
+    pub dividend_yield_z_score: Option<f64>,
+    pub beta_z_score: Option<f64>,
+    pub eps_z_score: Option<f64>,
+    pub range_position_z_score: Option<f64>,
+    pub week52_position: Option<f64>,
+    pub composite_score: f64,
+    pub outlier_type: String,
+    pub significance_level: String,
+    pub threshold_used: f64,
+}
+
+/// Whether a universe's sector summaries are served from the in-memory cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CachePolicy {
+    /// Kept for `AppSettings::cache_ttl_minutes`.
+    Ttl,
+    /// Always read from the database.
+    Never,
+}
+
+/// A set of stocks that can be summarized, scored and refreshed on its own.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Universe {
+    pub id: String,
+    pub display_name: String,
+    /// Where membership comes from, see `DiscoverySource`. `None` for lists
+    /// maintained by hand.
+    pub discovery_source: Option<String>,
+    /// Composite score threshold used when a request doesn't give one.
+    pub default_threshold: f64,
+    pub cache_policy: CachePolicy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct UniverseMembership {
+    pub universe_type: String,
+    pub date_added: String,
+    pub date_removed: Option<String>,
+}
+
+// -- Background Job Types --
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JobStatus {
+    Running,
+    Paused,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobInfo {
+    pub id: u64,
+    /// What the job does: a universe id for its refresh, or e.g. "sector", "history".
+    pub kind: String,
+    pub status: JobStatus,
+    pub phase: String,
+    pub current: u32,
+    pub total: u32,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub error: Option<String>,
+}
+
+// -- Scheduler Types --
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchedulerStatus {
+    pub enabled: bool,
+    pub interval_minutes: u32,
+    pub market_open: bool,
+    pub last_run_at: Option<String>,
+    pub next_run_at: Option<String>,
+}
+
+// -- Settings Types --
+
+/// Relative weight of each metric in the composite outlier score.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CompositeWeights {
+    pub price: f64,
+    pub pe: f64,
+    pub pb: f64,
+    pub volume: f64,
+    #[serde(default)]
+    pub dividend_yield: f64,
+    #[serde(default)]
+    pub beta: f64,
+    #[serde(default)]
+    pub eps: f64,
+    #[serde(default)]
+    pub range_position: f64,
+}
+
+/// Named weight blends for the composite score.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScoringPreset {
+    /// Price, P/E, P/B and volume together (the default weights).
+    Balanced,
+    /// P/E, P/B and dividend yield.
+    ValuationOnly,
+    /// Price change, volume and 52-week position.
+    MomentumOnly,
+}
+
+/// A metric that can be included in or left out of the composite score.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScoreMetric {
+    Price,
+    PeRatio,
+    PbRatio,
+    Volume,
+    DividendYield,
+    Beta,
+    Eps,
+    RangePosition,
+}
+
+/// Scoring choices for a detection request; anything left out takes the
+/// universe's default threshold, the saved weights and plain statistics.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScoringArgs {
+    pub threshold: Option<f64>,
+    pub preset: Option<ScoringPreset>,
+    pub weights: Option<CompositeWeights>,
+    pub metrics: Option<Vec<ScoreMetric>>,
+    pub stats: Option<StatsOptions>,
+    pub horizon: Option<ReturnHorizon>,
+}
+
+/// User preferences, persisted in `app_settings` and applied on the next use.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppSettings {
+    pub composite_weights: CompositeWeights,
+    /// How long sector summaries are served from memory.
+    pub cache_ttl_minutes: u32,
+    /// Off by default: each scheduled refresh quotes every open member of
+    /// every universe.
+    pub scheduler_enabled: bool,
+    pub scheduler_interval_minutes: u32,
+    /// Preferred provider, see `ProviderKind::as_str`.
+    pub market_data_provider: String,
+    pub yahoo_requests_per_second: f64,
+    pub alpha_vantage_requests_per_minute: f64,
+    /// Stocks fetched in parallel during a refresh.
+    pub refresh_concurrency: u32,
 }
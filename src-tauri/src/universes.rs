@@ -0,0 +1,559 @@
+use crate::russell_discovery;
+use crate::stock_discovery::{self, WikiIndex};
+use crate::types::{CachePolicy, DiscoveryResult, Stock, Universe};
+use reqwest::Client;
+use sqlx::sqlite::SqlitePool;
+use std::collections::HashSet;
+
+/// Universe used when a command isn't given one.
+pub const DEFAULT_UNIVERSE: &str = "sp500";
+
+const MIN_THRESHOLD: f64 = 1.0;
+const MAX_THRESHOLD: f64 = 3.0;
+
+/// A source list shorter than this share of the current membership is taken
+/// to be truncated rather than a reconstitution, and nothing is removed.
+const MIN_LISTED_SHARE: f64 = 0.5;
+
+/// Default threshold for new watchlists, the same as the S&P 500's.
+const WATCHLIST_THRESHOLD: f64 = 1.5;
+
+/// The membership sources we know how to read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscoverySource {
+    /// Constituents table of Wikipedia's "List of S&P 500 companies".
+    Sp500Wikipedia,
+    /// Wikipedia's "List of S&P 400 companies".
+    Sp400Wikipedia,
+    /// Wikipedia's "List of S&P 600 companies".
+    Sp600Wikipedia,
+    /// Components table of Wikipedia's "Nasdaq-100" article.
+    Nasdaq100Wikipedia,
+    /// iShares Russell 2000 ETF (IWM) holdings CSV.
+    IsharesIwm,
+}
+
+impl DiscoverySource {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "sp500_wikipedia" => Some(DiscoverySource::Sp500Wikipedia),
+            "sp400_wikipedia" => Some(DiscoverySource::Sp400Wikipedia),
+            "sp600_wikipedia" => Some(DiscoverySource::Sp600Wikipedia),
+            "nasdaq100_wikipedia" => Some(DiscoverySource::Nasdaq100Wikipedia),
+            "ishares_iwm" => Some(DiscoverySource::IsharesIwm),
+            _ => None,
+        }
+    }
+
+    /// The Wikipedia list read by this source, `None` for holdings files.
+    fn wiki_index(&self) -> Option<WikiIndex> {
+        match self {
+            DiscoverySource::Sp500Wikipedia => Some(WikiIndex::Sp500),
+            DiscoverySource::Sp400Wikipedia => Some(WikiIndex::Sp400),
+            DiscoverySource::Sp600Wikipedia => Some(WikiIndex::Sp600),
+            DiscoverySource::Nasdaq100Wikipedia => Some(WikiIndex::Nasdaq100),
+            DiscoverySource::IsharesIwm => None,
+        }
+    }
+}
+
+impl CachePolicy {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "ttl" => Some(CachePolicy::Ttl),
+            "never" => Some(CachePolicy::Never),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            CachePolicy::Ttl => "ttl",
+            CachePolicy::Never => "never",
+        }
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct UniverseRow {
+    id: String,
+    display_name: String,
+    discovery_source: Option<String>,
+    default_threshold: f64,
+    cache_policy: String,
+}
+
+impl From<UniverseRow> for Universe {
+    fn from(row: UniverseRow) -> Self {
+        Universe {
+            id: row.id,
+            display_name: row.display_name,
+            discovery_source: row.discovery_source,
+            default_threshold: row.default_threshold,
+            // The column is CHECK-constrained; anything else is treated as uncached
+            cache_policy: CachePolicy::parse(&row.cache_policy).unwrap_or(CachePolicy::Never),
+        }
+    }
+}
+
+/// Every registered universe, in display order.
+pub async fn list_universes(pool: &SqlitePool) -> Result<Vec<Universe>, String> {
+    let rows: Vec<UniverseRow> = sqlx::query_as(
+        "SELECT id, display_name, discovery_source, default_threshold, cache_policy
+         FROM universes
+         ORDER BY sort_order, display_name",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch universes: {e}"))?;
+
+    Ok(rows.into_iter().map(Universe::from).collect())
+}
+
+/// Look up a universe by id, or the default universe when none is given.
+/// Unknown ids are an error rather than an empty result.
+pub async fn resolve(pool: &SqlitePool, id: Option<&str>) -> Result<Universe, String> {
+    let id = id.unwrap_or(DEFAULT_UNIVERSE);
+    let row: Option<UniverseRow> = sqlx::query_as(
+        "SELECT id, display_name, discovery_source, default_threshold, cache_policy
+         FROM universes WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch universe {id}: {e}"))?;
+
+    row.map(Universe::from)
+        .ok_or_else(|| format!("Unknown universe '{id}'"))
+}
+
+/// Thresholds outside this range either flag most of a sector or nothing at all.
+fn validate_threshold(threshold: f64) -> Result<(), String> {
+    if !(MIN_THRESHOLD..=MAX_THRESHOLD).contains(&threshold) {
+        return Err(format!(
+            "Threshold must be between {MIN_THRESHOLD} and {MAX_THRESHOLD}"
+        ));
+    }
+    Ok(())
+}
+
+/// Change a universe's display name, default threshold or cache policy.
+/// Fields left as `None` keep their current value.
+pub async fn update_universe(
+    pool: &SqlitePool,
+    id: &str,
+    display_name: Option<String>,
+    default_threshold: Option<f64>,
+    cache_policy: Option<CachePolicy>,
+) -> Result<Universe, String> {
+    let current = resolve(pool, Some(id)).await?;
+
+    let display_name = display_name.map_or(current.display_name, |n| n.trim().to_string());
+    if display_name.is_empty() {
+        return Err("Universe name cannot be empty".to_string());
+    }
+    let default_threshold = default_threshold.unwrap_or(current.default_threshold);
+    validate_threshold(default_threshold)?;
+    let cache_policy = cache_policy.unwrap_or(current.cache_policy);
+
+    sqlx::query(
+        "UPDATE universes SET display_name = ?, default_threshold = ?, cache_policy = ?
+         WHERE id = ?",
+    )
+    .bind(&display_name)
+    .bind(default_threshold)
+    .bind(cache_policy.as_str())
+    .bind(id)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to update universe {id}: {e}"))?;
+
+    resolve(pool, Some(id)).await
+}
+
+/// Run the universe's discovery source and reconcile membership with it.
+/// `None` for universes maintained by hand.
+pub async fn discover(
+    pool: &SqlitePool,
+    client: &Client,
+    universe: &Universe,
+) -> Result<Option<DiscoveryResult>, String> {
+    let Some(name) = universe.discovery_source.as_deref() else {
+        return Ok(None);
+    };
+    let source = DiscoverySource::parse(name).ok_or_else(|| {
+        format!("Unknown discovery source '{name}' for universe {}", universe.id)
+    })?;
+
+    let result = match source.wiki_index() {
+        Some(index) => stock_discovery::discover_stocks(pool, client, index, &universe.id).await?,
+        None => russell_discovery::discover_russell_2000(pool, client, &universe.id).await?,
+    };
+    Ok(Some(result))
+}
+
+/// Watchlists are the universes without a discovery source. The indexes keep
+/// their membership in sync with the source and can't be edited by hand.
+pub fn require_watchlist(universe: &Universe) -> Result<(), String> {
+    if universe.discovery_source.is_some() {
+        return Err(format!(
+            "{} is maintained by its index source and is not a watchlist",
+            universe.display_name
+        ));
+    }
+    Ok(())
+}
+
+/// Id for a new watchlist: its name in lowercase with runs of anything other
+/// than letters and digits turned into `_`, suffixed when already taken.
+fn watchlist_id(display_name: &str, taken: &[String]) -> String {
+    let mut base = String::new();
+    for c in display_name.to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            base.push(c);
+        } else if !base.is_empty() && !base.ends_with('_') {
+            base.push('_');
+        }
+    }
+    let base = match base.trim_end_matches('_') {
+        "" => "watchlist".to_string(),
+        trimmed => trimmed.to_string(),
+    };
+
+    let mut id = base.clone();
+    let mut n = 2;
+    while taken.contains(&id) {
+        id = format!("{base}_{n}");
+        n += 1;
+    }
+    id
+}
+
+/// Create an empty watchlist listed after the existing universes.
+pub async fn create_watchlist(
+    pool: &SqlitePool,
+    display_name: &str,
+    default_threshold: Option<f64>,
+) -> Result<Universe, String> {
+    let display_name = display_name.trim();
+    if display_name.is_empty() {
+        return Err("Universe name cannot be empty".to_string());
+    }
+    let default_threshold = default_threshold.unwrap_or(WATCHLIST_THRESHOLD);
+    validate_threshold(default_threshold)?;
+
+    let taken: Vec<String> = sqlx::query_scalar("SELECT id FROM universes")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to fetch universes: {e}"))?;
+    let id = watchlist_id(display_name, &taken);
+
+    sqlx::query(
+        "INSERT INTO universes (id, display_name, discovery_source, default_threshold, cache_policy, sort_order)
+         SELECT ?, ?, NULL, ?, 'never', COALESCE(MAX(sort_order), -1) + 1 FROM universes",
+    )
+    .bind(&id)
+    .bind(display_name)
+    .bind(default_threshold)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to create watchlist {display_name}: {e}"))?;
+
+    resolve(pool, Some(&id)).await
+}
+
+/// Delete a watchlist with its memberships and detection history. Runs are
+/// keyed by universe id, so they have to go too or a later watchlist given
+/// the same id would inherit them.
+pub async fn delete_watchlist(pool: &SqlitePool, id: &str) -> Result<(), String> {
+    let universe = resolve(pool, Some(id)).await?;
+    require_watchlist(&universe)?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start deleting {id}: {e}"))?;
+    for sql in [
+        "DELETE FROM outlier_detections WHERE universe_type = ?",
+        "DELETE FROM detection_runs WHERE universe_type = ?",
+        "DELETE FROM stock_universe WHERE universe_type = ?",
+        "DELETE FROM universes WHERE id = ?",
+    ] {
+        sqlx::query(sql)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to delete watchlist {id}: {e}"))?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to delete watchlist {id}: {e}"))
+}
+
+/// Trimmed, upper-cased ticker. Only characters that appear in exchange
+/// symbols are accepted (e.g. `BRK.B`, `BF-B`).
+pub fn normalize_symbol(symbol: &str) -> Result<String, String> {
+    let symbol = symbol.trim().to_uppercase();
+    let valid = !symbol.is_empty()
+        && symbol.len() <= 12
+        && symbol
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '^' | '='));
+    if !valid {
+        return Err(format!("'{symbol}' is not a valid ticker symbol"));
+    }
+    Ok(symbol)
+}
+
+/// Open a membership span for the stock. Adding a current member is an error.
+pub async fn add_member(
+    pool: &SqlitePool,
+    universe: &Universe,
+    stock: &Stock,
+) -> Result<(), String> {
+    let open: Option<i64> = sqlx::query_scalar(
+        "SELECT id FROM stock_universe
+         WHERE stock_id = ? AND universe_type = ? AND date_removed IS NULL",
+    )
+    .bind(stock.id)
+    .bind(&universe.id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to check {} membership: {e}", stock.symbol))?;
+    if open.is_some() {
+        return Err(format!("{} is already in {}", stock.symbol, universe.display_name));
+    }
+
+    sqlx::query("INSERT INTO stock_universe (stock_id, universe_type) VALUES (?, ?)")
+        .bind(stock.id)
+        .bind(&universe.id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to add {} to {}: {e}", stock.symbol, universe.display_name))?;
+    Ok(())
+}
+
+/// Close the stock's open membership span, keeping it for as-of queries.
+pub async fn remove_member(
+    pool: &SqlitePool,
+    universe: &Universe,
+    symbol: &str,
+) -> Result<(), String> {
+    let closed = sqlx::query(
+        "UPDATE stock_universe SET date_removed = date('now')
+         WHERE universe_type = ? AND date_removed IS NULL
+           AND stock_id = (SELECT id FROM stocks WHERE symbol = ?)",
+    )
+    .bind(&universe.id)
+    .bind(symbol)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to remove {symbol} from {}: {e}", universe.display_name))?
+    .rows_affected();
+
+    if closed == 0 {
+        return Err(format!("{symbol} is not in {}", universe.display_name));
+    }
+    Ok(())
+}
+
+/// Symbols joining and leaving a universe.
+#[derive(Debug, Default, PartialEq)]
+pub struct MembershipChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// Compare the current members with a source's full list. Both sides come
+/// back sorted.
+fn membership_diff(
+    universe: &str,
+    current: &[String],
+    listed: &[String],
+) -> Result<MembershipChanges, String> {
+    let listed: HashSet<&str> = listed.iter().map(String::as_str).collect();
+    if !current.is_empty() && (listed.len() as f64) < current.len() as f64 * MIN_LISTED_SHARE {
+        return Err(format!(
+            "{universe} source listed {} stocks against {} current members; \
+             not reconciling a list that looks truncated",
+            listed.len(),
+            current.len()
+        ));
+    }
+    let members: HashSet<&str> = current.iter().map(String::as_str).collect();
+
+    let mut added: Vec<String> = listed.difference(&members).map(|s| s.to_string()).collect();
+    let mut removed: Vec<String> = members.difference(&listed).map(|s| s.to_string()).collect();
+    added.sort();
+    removed.sort();
+    Ok(MembershipChanges { added, removed })
+}
+
+/// Make the universe's open memberships match `listed`, the source's complete
+/// list of symbols. Members missing from it get today as `date_removed`; listed
+/// stocks without an open membership start a new span, so a stock that left
+/// and came back keeps both. Listed symbols not in `stocks` are skipped.
+pub async fn reconcile_members(
+    pool: &SqlitePool,
+    universe: &str,
+    listed: &[String],
+) -> Result<MembershipChanges, String> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start membership update: {e}"))?;
+
+    let current: Vec<String> = sqlx::query_scalar(
+        "SELECT s.symbol FROM stock_universe su
+         JOIN stocks s ON s.id = su.stock_id
+         WHERE su.universe_type = ? AND su.date_removed IS NULL",
+    )
+    .bind(universe)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to fetch {universe} members: {e}"))?;
+
+    let planned = membership_diff(universe, &current, listed)?;
+    let mut changes = MembershipChanges {
+        added: Vec::new(),
+        removed: planned.removed,
+    };
+
+    for symbol in &changes.removed {
+        sqlx::query(
+            "UPDATE stock_universe SET date_removed = date('now')
+             WHERE universe_type = ? AND date_removed IS NULL
+               AND stock_id = (SELECT id FROM stocks WHERE symbol = ?)",
+        )
+        .bind(universe)
+        .bind(symbol)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to remove {symbol} from {universe}: {e}"))?;
+    }
+
+    for symbol in planned.added {
+        let inserted = sqlx::query(
+            "INSERT INTO stock_universe (stock_id, universe_type)
+             SELECT id, ? FROM stocks WHERE symbol = ?",
+        )
+        .bind(universe)
+        .bind(&symbol)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to add {symbol} to {universe}: {e}"))?
+        .rows_affected();
+        if inserted > 0 {
+            changes.added.push(symbol);
+        }
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save {universe} membership: {e}"))?;
+
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discovery_source_names() {
+        assert_eq!(DiscoverySource::parse("sp500_wikipedia"), Some(DiscoverySource::Sp500Wikipedia));
+        assert_eq!(DiscoverySource::parse(" iShares_IWM "), Some(DiscoverySource::IsharesIwm));
+        assert_eq!(DiscoverySource::parse("sp600_wikipedia"), Some(DiscoverySource::Sp600Wikipedia));
+        assert_eq!(
+            DiscoverySource::parse("nasdaq100_wikipedia"),
+            Some(DiscoverySource::Nasdaq100Wikipedia)
+        );
+        assert_eq!(DiscoverySource::parse("bloomberg"), None);
+    }
+
+    #[test]
+    fn test_unknown_cache_policy_is_uncached() {
+        let universe = Universe::from(UniverseRow {
+            id: "mine".to_string(),
+            display_name: "Mine".to_string(),
+            discovery_source: None,
+            default_threshold: 1.5,
+            cache_policy: "forever".to_string(),
+        });
+        assert_eq!(universe.cache_policy, CachePolicy::Never);
+    }
+
+    fn symbols(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_membership_diff_adds_and_removes() {
+        let current = symbols(&["AAA", "BBB", "CCC"]);
+        let listed = symbols(&["DDD", "BBB", "AAA", "BBB"]);
+        let changes = membership_diff("sp500", &current, &listed).unwrap();
+        assert_eq!(changes.added, ["DDD"]);
+        assert_eq!(changes.removed, ["CCC"]);
+    }
+
+    #[test]
+    fn test_membership_diff_from_empty_universe() {
+        let changes = membership_diff("mine", &[], &symbols(&["BBB", "AAA"])).unwrap();
+        assert_eq!(changes.added, ["AAA", "BBB"]);
+        assert!(changes.removed.is_empty());
+    }
+
+    #[test]
+    fn test_membership_diff_refuses_truncated_list() {
+        let current = symbols(&["AAA", "BBB", "CCC", "DDD", "EEE"]);
+        let err = membership_diff("sp500", &current, &symbols(&["AAA", "BBB"])).unwrap_err();
+        assert!(err.contains("truncated"));
+        assert!(membership_diff("sp500", &current, &[]).is_err());
+    }
+
+    #[test]
+    fn test_watchlist_id_from_name() {
+        assert_eq!(watchlist_id("My Coverage", &[]), "my_coverage");
+        assert_eq!(watchlist_id("  Semis & AI!! ", &[]), "semis_ai");
+        assert_eq!(watchlist_id("★", &[]), "watchlist");
+    }
+
+    #[test]
+    fn test_watchlist_id_avoids_taken_ids() {
+        let taken = symbols(&["sp500", "semis", "semis_2"]);
+        assert_eq!(watchlist_id("Semis", &taken), "semis_3");
+        assert_eq!(watchlist_id("S&P 500", &taken), "s_p_500");
+        assert_eq!(watchlist_id("sp500", &taken), "sp500_2");
+    }
+
+    #[test]
+    fn test_indexes_are_not_watchlists() {
+        let mut universe = Universe::from(UniverseRow {
+            id: "sp500".to_string(),
+            display_name: "S&P 500".to_string(),
+            discovery_source: Some("sp500_wikipedia".to_string()),
+            default_threshold: 1.5,
+            cache_policy: "ttl".to_string(),
+        });
+        assert!(require_watchlist(&universe).unwrap_err().contains("not a watchlist"));
+        universe.discovery_source = None;
+        assert!(require_watchlist(&universe).is_ok());
+    }
+
+    #[test]
+    fn test_normalize_symbol() {
+        assert_eq!(normalize_symbol(" brk.b ").unwrap(), "BRK.B");
+        assert_eq!(normalize_symbol("bf-b").unwrap(), "BF-B");
+        assert!(normalize_symbol("").is_err());
+        assert!(normalize_symbol("AA PL").is_err());
+        assert!(normalize_symbol("DROP;").is_err());
+    }
+
+    #[test]
+    fn test_threshold_out_of_range_rejected() {
+        assert!(validate_threshold(1.5).is_ok());
+        assert!(validate_threshold(MAX_THRESHOLD).is_ok());
+        assert!(validate_threshold(3.5).unwrap_err().contains("between"));
+        assert!(validate_threshold(0.5).is_err());
+    }
+}
@@ -4,22 +4,37 @@ import SectorGrid from "./components/SectorGrid";
 import OutlierDashboard from "./components/OutlierDashboard";
 import Toast, { type ToastMessage } from "./components/Toast";
 import { useDatabase } from "./hooks/useDatabase";
-import type { SectorSummary, SectorOutliers, UniverseType } from "./types/database";
+import type { SectorSummary, SectorOutliers, Universe, UniverseType } from "./types/database";
 import "./App.css";
 
+const NO_DATA: Omit<SectorSummary, "sector_id" | "name" | "symbol"> = {
+  avg_change_percent: 0,
+  avg_pe_ratio: null,
+  total_market_cap: null,
+  stock_count: 0,
+  avg_beta: null,
+  returns: { one_day: null, one_week: null, one_month: null, three_months: null, year_to_date: null },
+  cap_weighted_change_percent: null,
+  change_quartiles: null,
+  cap_weighted_pe_ratio: null,
+  pe_quartiles: null,
+  benchmark: null,
+  benchmark_divergence: null,
+};
+
 // Default sector data shown before any data is loaded
 const DEFAULT_SECTORS: SectorSummary[] = [
-  { sector_id: 0, name: "Technology", symbol: "XLK", avg_change_percent: 0, avg_pe_ratio: null, total_market_cap: null, stock_count: 0, avg_beta: null },
-  { sector_id: 0, name: "Health Care", symbol: "XLV", avg_change_percent: 0, avg_pe_ratio: null, total_market_cap: null, stock_count: 0, avg_beta: null },
-  { sector_id: 0, name: "Financials", symbol: "XLF", avg_change_percent: 0, avg_pe_ratio: null, total_market_cap: null, stock_count: 0, avg_beta: null },
-  { sector_id: 0, name: "Consumer Discretionary", symbol: "XLY", avg_change_percent: 0, avg_pe_ratio: null, total_market_cap: null, stock_count: 0, avg_beta: null },
-  { sector_id: 0, name: "Communication Services", symbol: "XLC", avg_change_percent: 0, avg_pe_ratio: null, total_market_cap: null, stock_count: 0, avg_beta: null },
-  { sector_id: 0, name: "Industrials", symbol: "XLI", avg_change_percent: 0, avg_pe_ratio: null, total_market_cap: null, stock_count: 0, avg_beta: null },
-  { sector_id: 0, name: "Consumer Staples", symbol: "XLP", avg_change_percent: 0, avg_pe_ratio: null, total_market_cap: null, stock_count: 0, avg_beta: null },
-  { sector_id: 0, name: "Energy", symbol: "XLE", avg_change_percent: 0, avg_pe_ratio: null, total_market_cap: null, stock_count: 0, avg_beta: null },
-  { sector_id: 0, name: "Utilities", symbol: "XLU", avg_change_percent: 0, avg_pe_ratio: null, total_market_cap: null, stock_count: 0, avg_beta: null },
-  { sector_id: 0, name: "Real Estate", symbol: "XLRE", avg_change_percent: 0, avg_pe_ratio: null, total_market_cap: null, stock_count: 0, avg_beta: null },
-  { sector_id: 0, name: "Materials", symbol: "XLB", avg_change_percent: 0, avg_pe_ratio: null, total_market_cap: null, stock_count: 0, avg_beta: null },
+  { sector_id: 0, name: "Technology", symbol: "XLK", ...NO_DATA },
+  { sector_id: 0, name: "Health Care", symbol: "XLV", ...NO_DATA },
+  { sector_id: 0, name: "Financials", symbol: "XLF", ...NO_DATA },
+  { sector_id: 0, name: "Consumer Discretionary", symbol: "XLY", ...NO_DATA },
+  { sector_id: 0, name: "Communication Services", symbol: "XLC", ...NO_DATA },
+  { sector_id: 0, name: "Industrials", symbol: "XLI", ...NO_DATA },
+  { sector_id: 0, name: "Consumer Staples", symbol: "XLP", ...NO_DATA },
+  { sector_id: 0, name: "Energy", symbol: "XLE", ...NO_DATA },
+  { sector_id: 0, name: "Utilities", symbol: "XLU", ...NO_DATA },
+  { sector_id: 0, name: "Real Estate", symbol: "XLRE", ...NO_DATA },
+  { sector_id: 0, name: "Materials", symbol: "XLB", ...NO_DATA },
 ];
 
 let toastId = 0;
@@ -36,6 +51,7 @@ function App() {
   const [sectors, setSectors] = useState<SectorSummary[]>(DEFAULT_SECTORS);
   const [outliersBySector, setOutliersBySector] = useState<Map<string, SectorOutliers>>(new Map());
   const [universe, setUniverse] = useState<UniverseType>("sp500");
+  const [universes, setUniverses] = useState<Universe[]>([]);
   const [globalRefreshing, setGlobalRefreshing] = useState(false);
   const [refreshingSectors, setRefreshingSectors] = useState<Set<string>>(new Set());
   const [lastRefresh, setLastRefresh] = useState<Date | null>(null);
@@ -43,7 +59,7 @@ function App() {
   const [progress, setProgress] = useState<{ current: number; total: number; phase: string } | null>(null);
   const unlistenRef = useRef<UnlistenFn | null>(null);
 
-  const { getSectorPerformance, refreshMarketData, refreshSectorData, detectOutliers, refreshRussell2000Data } = useDatabase();
+  const { getUniverses, getSectorPerformance, refreshMarketData, refreshSectorData, detectOutliers } = useDatabase();
 
   const anyRefreshing = globalRefreshing || refreshingSectors.size > 0;
 
@@ -67,6 +83,11 @@ function App() {
 
   // Load cached/existing sector performance and outliers on mount
   useEffect(() => {
+    getUniverses()
+      .then(setUniverses)
+      .catch(() => {
+        // Without the registry there is nothing to switch to
+      });
     getSectorPerformance("sp500")
       .then((data) => {
         if (data.length > 0) {
@@ -79,6 +100,26 @@ function App() {
       });
   }, []); // eslint-disable-line react-hooks/exhaustive-deps
 
+  // Pick up fresh data landed by the background scheduler
+  useEffect(() => {
+    const unlistenPromise = listen("market-data-updated", () => {
+      getSectorPerformance(universe)
+        .then((data) => {
+          if (data.length > 0) {
+            setSectors(data);
+            setLastRefresh(new Date());
+            loadOutliers(universe);
+          }
+        })
+        .catch(() => {
+          // Keep current data on error
+        });
+    });
+    return () => {
+      unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, [universe]); // eslint-disable-line react-hooks/exhaustive-deps
+
   const handleUniverseChange = useCallback(async (newUniverse: UniverseType) => {
     if (anyRefreshing || newUniverse === universe) return;
     setUniverse(newUniverse);
@@ -101,14 +142,12 @@ function App() {
     setGlobalRefreshing(true);
     setProgress(null);
     try {
-      unlistenRef.current = await listen<{ current: number; total: number; phase: string }>(
+      unlistenRef.current = await listen<{ job_id: number; current: number; total: number; phase: string }>(
         "refresh-progress",
         (event) => setProgress(event.payload),
       );
 
-      const result = universe === "russell2000"
-        ? await refreshRussell2000Data()
-        : await refreshMarketData();
+      const result = await refreshMarketData(universe);
 
       setSectors(result.sectors);
       setLastRefresh(new Date());
@@ -119,6 +158,9 @@ function App() {
         if (d.stocks_discovered > 0 || d.stocks_updated > 0) {
           message += ` | Found ${d.stocks_discovered} new, ${d.stocks_updated} sector changes`;
         }
+        if (d.members_added.length > 0 || d.members_removed.length > 0) {
+          message += ` | ${d.members_added.length} joined, ${d.members_removed.length} left`;
+        }
       }
       showToast(message, "success");
       await loadOutliers(universe);
@@ -130,18 +172,18 @@ function App() {
       setProgress(null);
       setGlobalRefreshing(false);
     }
-  }, [anyRefreshing, universe, refreshMarketData, refreshRussell2000Data, showToast, loadOutliers]);
+  }, [anyRefreshing, universe, refreshMarketData, showToast, loadOutliers]);
 
   const handleSectorRefresh = useCallback(async (symbol: string) => {
     if (anyRefreshing) return;
     setRefreshingSectors(new Set([symbol]));
     setProgress(null);
     try {
-      unlistenRef.current = await listen<{ current: number; total: number; phase: string }>(
+      unlistenRef.current = await listen<{ job_id: number; current: number; total: number; phase: string }>(
         "refresh-progress",
         (event) => setProgress(event.payload),
       );
-      const data = await refreshSectorData(symbol);
+      const data = await refreshSectorData(symbol, universe);
       setSectors(data);
       const sector = data.find((s) => s.symbol === symbol);
       showToast(`Updated ${sector?.stock_count ?? 0} ${sector?.name ?? symbol} stocks`, "success");
@@ -171,6 +213,7 @@ function App() {
           onRefresh={handleGlobalRefresh}
           progress={progress}
           universe={universe}
+          universes={universes}
           onUniverseChange={handleUniverseChange}
         />
         <OutlierDashboard outliersBySector={outliersBySector} />
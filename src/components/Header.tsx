@@ -1,4 +1,4 @@
-import type { UniverseType } from "../types/database";
+import type { Universe, UniverseType } from "../types/database";
 import "./Header.css";
 
 interface RefreshProgress {
@@ -13,6 +13,7 @@ interface HeaderProps {
   onRefresh: () => void;
   progress?: RefreshProgress | null;
   universe: UniverseType;
+  universes: Universe[];
   onUniverseChange: (universe: UniverseType) => void;
 }
 
@@ -20,7 +21,7 @@ function formatTime(date: Date): string {
   return date.toLocaleTimeString([], { hour: "numeric", minute: "2-digit" });
 }
 
-function Header({ refreshing, lastRefresh, onRefresh, progress, universe, onUniverseChange }: HeaderProps) {
+function Header({ refreshing, lastRefresh, onRefresh, progress, universe, universes, onUniverseChange }: HeaderProps) {
   const progressLabel = refreshing && progress
     ? progress.phase === "discovery"
       ? "Discovering stocks..."
@@ -40,22 +41,17 @@ function Header({ refreshing, lastRefresh, onRefresh, progress, universe, onUniv
         </span>
       )}
       <div className="universe-toggle" role="group" aria-label="Select universe">
-        <button
-          className={`toggle-btn ${universe === "sp500" ? "active" : ""}`}
-          onClick={() => onUniverseChange("sp500")}
-          aria-pressed={universe === "sp500"}
-          disabled={refreshing}
-        >
-          S&amp;P 500
-        </button>
-        <button
-          className={`toggle-btn ${universe === "russell2000" ? "active" : ""}`}
-          onClick={() => onUniverseChange("russell2000")}
-          aria-pressed={universe === "russell2000"}
-          disabled={refreshing}
-        >
-          Russell 2000
-        </button>
+        {universes.map((u) => (
+          <button
+            key={u.id}
+            className={`toggle-btn ${universe === u.id ? "active" : ""}`}
+            onClick={() => onUniverseChange(u.id)}
+            aria-pressed={universe === u.id}
+            disabled={refreshing}
+          >
+            {u.display_name}
+          </button>
+        ))}
       </div>
       <button
         className={`refresh-btn ${refreshing ? "loading" : ""}`}
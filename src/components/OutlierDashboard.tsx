@@ -51,6 +51,9 @@ function typeClass(type: OutlierType): string {
     case "Momentum": return "type-momentum";
     case "ValueTrap": return "type-valuetrap";
     case "GrowthPremium": return "type-growth";
+    case "YieldTrap": return "type-yieldtrap";
+    case "NearYearLow": return "type-yearlow";
+    case "NearYearHigh": return "type-yearhigh";
     default: return "type-mixed";
   }
 }
@@ -140,6 +143,9 @@ function OutlierDashboard({ outliersBySector }: OutlierDashboardProps) {
             <option value="Momentum">Momentum</option>
             <option value="ValueTrap">Value Trap</option>
             <option value="GrowthPremium">Growth Premium</option>
+            <option value="YieldTrap">Yield Trap</option>
+            <option value="NearYearLow">Near 52-Week Low</option>
+            <option value="NearYearHigh">Near 52-Week High</option>
             <option value="Mixed">Mixed</option>
           </select>
 
@@ -213,6 +219,10 @@ function OutlierDashboard({ outliersBySector }: OutlierDashboardProps) {
                     <DetailRow label="P/B Z-Score" value={outlier.z_scores.pb_z} description={pbDescription(outlier.z_scores.pb_z)} />
                     <DetailRow label="Price Z-Score" value={outlier.z_scores.price_z} description={priceDescription(outlier.z_scores.price_z)} />
                     <DetailRow label="Volume Z-Score" value={outlier.z_scores.volume_z} description={volumeDescription(outlier.z_scores.volume_z)} />
+                    <DetailRow label="Yield Z-Score" value={outlier.z_scores.dividend_yield_z} description={relativeDescription(outlier.z_scores.dividend_yield_z, "dividend yield")} />
+                    <DetailRow label="Beta Z-Score" value={outlier.z_scores.beta_z} description={relativeDescription(outlier.z_scores.beta_z, "beta")} />
+                    <DetailRow label="EPS Z-Score" value={outlier.z_scores.eps_z} description={relativeDescription(outlier.z_scores.eps_z, "EPS")} />
+                    <DetailRow label="52W Range Z-Score" value={outlier.z_scores.range_position_z} description={rangeDescription(outlier.week52_position)} />
                   </div>
                 </div>
                 <div className="detail-section">
@@ -223,6 +233,16 @@ function OutlierDashboard({ outliersBySector }: OutlierDashboardProps) {
                   <p className="detail-text">
                     Composite score of <strong>{outlier.composite_score.toFixed(2)}&sigma;</strong> indicates a <strong>{outlier.significance_level.toLowerCase()}</strong> outlier.
                   </p>
+                  {outlier.excess_return_1d_percent != null && (
+                    <p className="detail-text">
+                      1-day excess return: <strong>{formatExcess(outlier.excess_return_1d_percent)}</strong> against the sector ETF, whatever the scoring horizon.
+                    </p>
+                  )}
+                  {outlier.first_seen != null && outlier.streak_days > 1 && (
+                    <p className="detail-text">
+                      Flagged <strong>{outlier.streak_days} days</strong> in a row, since {outlier.first_seen}.
+                    </p>
+                  )}
                 </div>
               </div>
             )}
@@ -287,6 +307,25 @@ function volumeDescription(z: number | null): string {
   return "Normal trading volume";
 }
 
+function relativeDescription(z: number | null, metric: string): string {
+  if (z == null) return `No ${metric} data available`;
+  if (z <= -2) return `Far below sector ${metric}`;
+  if (z <= -1) return `Below sector average ${metric}`;
+  if (z >= 2) return `Far above sector ${metric}`;
+  if (z >= 1) return `Above sector average ${metric}`;
+  return `In line with sector ${metric}`;
+}
+
+function formatExcess(points: number): string {
+  const sign = points >= 0 ? "+" : "";
+  return `${sign}${points.toFixed(2)} pts`;
+}
+
+function rangeDescription(position: number | null): string {
+  if (position == null) return "No 52-week range available";
+  return `${position.toFixed(0)}% of the way from 52-week low to high`;
+}
+
 function classificationDescription(type: OutlierType): string {
   switch (type) {
     case "Undervalued": return "Low valuation multiples suggest stock may be underpriced relative to sector peers.";
@@ -294,6 +333,9 @@ function classificationDescription(type: OutlierType): string {
     case "Momentum": return "Strong price performance with high volume indicates momentum-driven movement.";
     case "ValueTrap": return "Low valuation with poor price performance may indicate fundamental challenges.";
     case "GrowthPremium": return "High valuation with strong price performance suggests market pricing in growth.";
+    case "YieldTrap": return "Unusually high dividend yield driven by a falling price or weak earnings may not be sustainable.";
+    case "NearYearLow": return "Trading near the bottom of its 52-week range.";
+    case "NearYearHigh": return "Trading near the top of its 52-week range.";
     case "Mixed": return "Multiple factors contributing to outlier status without a dominant pattern.";
     default: return "";
   }
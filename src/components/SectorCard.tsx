@@ -30,7 +30,8 @@ function outlierTypeColor(type: string): string {
     case "Undervalued": return "outlier-green";
     case "Overvalued": return "outlier-red";
     case "Momentum": return "outlier-blue";
-    case "ValueTrap": return "outlier-orange";
+    case "ValueTrap":
+    case "YieldTrap": return "outlier-orange";
     case "GrowthPremium": return "outlier-purple";
     default: return "outlier-gray";
   }
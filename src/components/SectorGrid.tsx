@@ -1,5 +1,5 @@
 import { useEffect, useRef, useState } from "react";
-import type { SectorSummary, SectorOutliers, UniverseType } from "../types/database";
+import type { SectorSummary, SectorOutliers, Universe, UniverseType } from "../types/database";
 import Header from "./Header";
 import SectorCard from "./SectorCard";
 import "./SectorGrid.css";
@@ -21,13 +21,14 @@ interface SectorGridProps {
   onRefresh: () => void;
   progress: RefreshProgress | null;
   universe: UniverseType;
+  universes: Universe[];
   onUniverseChange: (universe: UniverseType) => void;
 }
 
 const FLIP_DURATION_MS = 500;
 const FLIP_STAGGER_MS = 50;
 
-function SectorGrid({ sectors, outliersBySector, refreshingSectors, anyRefreshing, onSectorRefresh, refreshing, lastRefresh, onRefresh, progress, universe, onUniverseChange }: SectorGridProps) {
+function SectorGrid({ sectors, outliersBySector, refreshingSectors, anyRefreshing, onSectorRefresh, refreshing, lastRefresh, onRefresh, progress, universe, universes, onUniverseChange }: SectorGridProps) {
   const [isFlipping, setIsFlipping] = useState(false);
   const isFirstRender = useRef(true);
   const flipTimeoutRef = useRef<ReturnType<typeof setTimeout> | null>(null);
@@ -59,6 +60,7 @@ function SectorGrid({ sectors, outliersBySector, refreshingSectors, anyRefreshin
         onRefresh={onRefresh}
         progress={progress}
         universe={universe}
+        universes={universes}
         onUniverseChange={onUniverseChange}
       />
       {sectors.map((sector, index) => (
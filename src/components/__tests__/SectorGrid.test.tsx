@@ -6,6 +6,7 @@ import {
   mockSector,
   mockOutliers,
   mockOutliersSingle,
+  mockUniverses,
 } from "../../test/fixtures/sectors";
 
 const defaultProps = {
@@ -18,7 +19,8 @@ const defaultProps = {
   lastRefresh: null,
   onRefresh: vi.fn(),
   progress: null,
-  universe: "sp500" as const,
+  universe: "sp500",
+  universes: mockUniverses,
   onUniverseChange: vi.fn(),
 };
 
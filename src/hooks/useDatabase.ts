@@ -1,5 +1,46 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { Sector, Stock, SectorSummary, RefreshResult, SectorOutliers, OutlierStock, UniverseType } from "../types/database";
+import type {
+  Sector,
+  Stock,
+  SectorSummary,
+  RefreshResult,
+  SectorOutliers,
+  OutlierStock,
+  UniverseType,
+  Universe,
+  CachePolicy,
+  MarketDataProvider,
+  BackfillResult,
+  PricePoint,
+  DetectionRecord,
+  StockZScores,
+  UniverseMembership,
+  JobInfo,
+  JobStatus,
+  SchedulerStatus,
+  AppSettings,
+  ScoringOptions,
+  ScoringArgs,
+  StatsOptions,
+  ReturnHorizon,
+  SectorRotation,
+  MarketBreadth,
+  OutlierEpisode,
+  BacktestReport,
+  GicsLevel,
+  GroupSummary,
+} from "../types/database";
+
+function scoringArgs(threshold?: number, scoring?: ScoringOptions): ScoringArgs {
+  return {
+    threshold: threshold ?? null,
+    preset: scoring?.preset ?? null,
+    weights: scoring?.weights ?? null,
+    metrics: scoring?.metrics ?? null,
+    stats: scoring?.stats ?? null,
+    horizon: scoring?.horizon ?? null,
+  };
+}
 
 export function useDatabase() {
   const getSectors = async (): Promise<Sector[]> => {
@@ -31,29 +72,135 @@ export function useDatabase() {
     }
   };
 
-  const refreshMarketData = async (): Promise<RefreshResult> => {
+  const getGroupPerformance = async (level: GicsLevel, universe?: UniverseType): Promise<GroupSummary[]> => {
+    try {
+      return await invoke<GroupSummary[]>("get_group_performance", { universe: universe ?? null, level });
+    } catch (error) {
+      console.error("Failed to get group performance:", error);
+      throw error;
+    }
+  };
+
+  const getMarketBreadth = async (universe?: UniverseType): Promise<MarketBreadth> => {
+    try {
+      return await invoke<MarketBreadth>("get_market_breadth", { universe: universe ?? null });
+    } catch (error) {
+      console.error("Failed to get market breadth:", error);
+      throw error;
+    }
+  };
+
+  const getSectorRotation = async (universe?: UniverseType, rankBy?: ReturnHorizon, trail?: number): Promise<SectorRotation[]> => {
+    try {
+      return await invoke<SectorRotation[]>("get_sector_rotation", {
+        universe: universe ?? null,
+        rankBy: rankBy ?? null,
+        trail: trail ?? null,
+      });
+    } catch (error) {
+      console.error("Failed to get sector rotation:", error);
+      throw error;
+    }
+  };
+
+  const getUniverses = async (): Promise<Universe[]> => {
+    try {
+      return await invoke<Universe[]>("get_universes");
+    } catch (error) {
+      console.error("Failed to get universes:", error);
+      throw error;
+    }
+  };
+
+  const updateUniverse = async (
+    universe: UniverseType,
+    changes: { displayName?: string; defaultThreshold?: number; cachePolicy?: CachePolicy },
+  ): Promise<Universe> => {
+    try {
+      return await invoke<Universe>("update_universe", {
+        universe,
+        displayName: changes.displayName ?? null,
+        defaultThreshold: changes.defaultThreshold ?? null,
+        cachePolicy: changes.cachePolicy ?? null,
+      });
+    } catch (error) {
+      console.error("Failed to update universe:", error);
+      throw error;
+    }
+  };
+
+  const createWatchlist = async (displayName: string, defaultThreshold?: number): Promise<Universe> => {
+    try {
+      return await invoke<Universe>("create_watchlist", {
+        displayName,
+        defaultThreshold: defaultThreshold ?? null,
+      });
+    } catch (error) {
+      console.error("Failed to create watchlist:", error);
+      throw error;
+    }
+  };
+
+  const renameWatchlist = async (universe: UniverseType, displayName: string): Promise<Universe> => {
     try {
-      return await invoke<RefreshResult>("refresh_market_data");
+      return await invoke<Universe>("rename_watchlist", { universe, displayName });
+    } catch (error) {
+      console.error("Failed to rename watchlist:", error);
+      throw error;
+    }
+  };
+
+  const deleteWatchlist = async (universe: UniverseType): Promise<void> => {
+    try {
+      await invoke("delete_watchlist", { universe });
+    } catch (error) {
+      console.error("Failed to delete watchlist:", error);
+      throw error;
+    }
+  };
+
+  const addWatchlistSymbol = async (universe: UniverseType, symbol: string): Promise<Stock> => {
+    try {
+      return await invoke<Stock>("add_watchlist_symbol", { universe, symbol });
+    } catch (error) {
+      console.error("Failed to add watchlist symbol:", error);
+      throw error;
+    }
+  };
+
+  const removeWatchlistSymbol = async (universe: UniverseType, symbol: string): Promise<void> => {
+    try {
+      await invoke("remove_watchlist_symbol", { universe, symbol });
+    } catch (error) {
+      console.error("Failed to remove watchlist symbol:", error);
+      throw error;
+    }
+  };
+
+  const refreshMarketData = async (universe?: UniverseType): Promise<RefreshResult> => {
+    try {
+      return await invoke<RefreshResult>("refresh_market_data", { universe: universe ?? null });
     } catch (error) {
       console.error("Failed to refresh market data:", error);
       throw error;
     }
   };
 
-  const refreshSectorData = async (sectorSymbol: string): Promise<SectorSummary[]> => {
+  const refreshSectorData = async (sectorSymbol: string, universe?: UniverseType): Promise<SectorSummary[]> => {
     try {
-      return await invoke<SectorSummary[]>("refresh_sector_data", { sectorSymbol });
+      return await invoke<SectorSummary[]>("refresh_sector_data", { sectorSymbol, universe: universe ?? null });
     } catch (error) {
       console.error("Failed to refresh sector data:", error);
       throw error;
     }
   };
 
-  const detectOutliers = async (threshold?: number, universe?: UniverseType): Promise<SectorOutliers[]> => {
+  const detectOutliers = async (threshold?: number, universe?: UniverseType, scoring?: ScoringOptions): Promise<SectorOutliers[]> => {
     try {
       return await invoke<SectorOutliers[]>("detect_outliers", {
-        threshold: threshold ?? null,
         universe: universe ?? null,
+        scoring: scoringArgs(threshold, scoring),
+        level: scoring?.level ?? null,
       });
     } catch (error) {
       console.error("Failed to detect outliers:", error);
@@ -61,12 +208,26 @@ export function useDatabase() {
     }
   };
 
-  const getSectorOutliers = async (sectorId: number, threshold?: number, universe?: UniverseType): Promise<OutlierStock[]> => {
+  const replayOutliers = async (asOf: string, threshold?: number, universe?: UniverseType, scoring?: ScoringOptions): Promise<SectorOutliers[]> => {
+    try {
+      return await invoke<SectorOutliers[]>("replay_outliers", {
+        asOf,
+        universe: universe ?? null,
+        scoring: scoringArgs(threshold, scoring),
+        level: scoring?.level ?? null,
+      });
+    } catch (error) {
+      console.error("Failed to replay outlier detection:", error);
+      throw error;
+    }
+  };
+
+  const getSectorOutliers = async (sectorId: number, threshold?: number, universe?: UniverseType, scoring?: ScoringOptions): Promise<OutlierStock[]> => {
     try {
       return await invoke<OutlierStock[]>("get_sector_outliers", {
         sectorId,
-        threshold: threshold ?? null,
         universe: universe ?? null,
+        scoring: scoringArgs(threshold, scoring),
       });
     } catch (error) {
       console.error("Failed to get sector outliers:", error);
@@ -74,23 +235,205 @@ export function useDatabase() {
     }
   };
 
-  const refreshRussell2000Data = async (): Promise<RefreshResult> => {
+  const getOutlierEpisodes = async (universe?: UniverseType, sectorId?: number, days?: number, threshold?: number, scoring?: ScoringOptions, groupId?: number): Promise<OutlierEpisode[]> => {
+    try {
+      return await invoke<OutlierEpisode[]>("get_outlier_episodes", {
+        universe: universe ?? null,
+        sectorId: sectorId ?? null,
+        level: scoring?.level ?? null,
+        groupId: groupId ?? null,
+        days: days ?? null,
+        scoring: scoringArgs(threshold, scoring),
+      });
+    } catch (error) {
+      console.error("Failed to get outlier episodes:", error);
+      throw error;
+    }
+  };
+
+  const getOutlierBacktest = async (universe?: UniverseType, startDate?: string, endDate?: string, threshold?: number, scoring?: ScoringOptions): Promise<BacktestReport> => {
+    try {
+      return await invoke<BacktestReport>("get_outlier_backtest", {
+        universe: universe ?? null,
+        startDate: startDate ?? null,
+        endDate: endDate ?? null,
+        scoring: scoringArgs(threshold, scoring),
+      });
+    } catch (error) {
+      console.error("Failed to run outlier backtest:", error);
+      throw error;
+    }
+  };
+
+  const setMarketDataProvider = async (provider: MarketDataProvider, apiKey?: string): Promise<void> => {
+    try {
+      await invoke("set_market_data_provider", { provider, apiKey: apiKey ?? null });
+    } catch (error) {
+      console.error("Failed to set market data provider:", error);
+      throw error;
+    }
+  };
+
+  const backfillPriceHistory = async (years?: number, universe?: UniverseType): Promise<BackfillResult> => {
+    try {
+      return await invoke<BackfillResult>("backfill_price_history", {
+        years: years ?? null,
+        universe: universe ?? null,
+      });
+    } catch (error) {
+      console.error("Failed to backfill price history:", error);
+      throw error;
+    }
+  };
+
+  const getStockHistory = async (symbol: string, days: number): Promise<PricePoint[]> => {
+    try {
+      return await invoke<PricePoint[]>("get_stock_history", { symbol, days });
+    } catch (error) {
+      console.error("Failed to get stock history:", error);
+      throw error;
+    }
+  };
+
+  const getStockDetections = async (symbol: string, limit?: number): Promise<DetectionRecord[]> => {
+    try {
+      return await invoke<DetectionRecord[]>("get_stock_detections", { symbol, limit: limit ?? null });
+    } catch (error) {
+      console.error("Failed to get stock detections:", error);
+      throw error;
+    }
+  };
+
+  const getStockZScores = async (symbol: string, universe?: UniverseType, stats?: StatsOptions, horizon?: ReturnHorizon, level?: GicsLevel): Promise<StockZScores | null> => {
+    try {
+      return await invoke<StockZScores | null>("get_stock_z_scores", {
+        symbol,
+        universe: universe ?? null,
+        stats: stats ?? null,
+        horizon: horizon ?? null,
+        level: level ?? null,
+      });
+    } catch (error) {
+      console.error("Failed to get stock z-scores:", error);
+      throw error;
+    }
+  };
+
+  const getStockMemberships = async (symbol: string): Promise<UniverseMembership[]> => {
+    try {
+      return await invoke<UniverseMembership[]>("get_stock_memberships", { symbol });
+    } catch (error) {
+      console.error("Failed to get stock memberships:", error);
+      throw error;
+    }
+  };
+
+  const cancelRefresh = async (jobId: number): Promise<JobStatus> => {
+    try {
+      return await invoke<JobStatus>("cancel_refresh", { jobId });
+    } catch (error) {
+      console.error("Failed to cancel refresh:", error);
+      throw error;
+    }
+  };
+
+  const pauseRefresh = async (jobId: number): Promise<JobStatus> => {
+    try {
+      return await invoke<JobStatus>("pause_refresh", { jobId });
+    } catch (error) {
+      console.error("Failed to pause refresh:", error);
+      throw error;
+    }
+  };
+
+  const resumeRefresh = async (jobId: number): Promise<JobStatus> => {
+    try {
+      return await invoke<JobStatus>("resume_refresh", { jobId });
+    } catch (error) {
+      console.error("Failed to resume refresh:", error);
+      throw error;
+    }
+  };
+
+  const listJobs = async (): Promise<JobInfo[]> => {
+    try {
+      return await invoke<JobInfo[]>("list_jobs");
+    } catch (error) {
+      console.error("Failed to list jobs:", error);
+      throw error;
+    }
+  };
+
+  const getSchedulerStatus = async (): Promise<SchedulerStatus> => {
+    try {
+      return await invoke<SchedulerStatus>("get_scheduler_status");
+    } catch (error) {
+      console.error("Failed to get scheduler status:", error);
+      throw error;
+    }
+  };
+
+  const setScheduler = async (enabled: boolean, intervalMinutes?: number): Promise<void> => {
+    try {
+      await invoke("set_scheduler", { enabled, intervalMinutes: intervalMinutes ?? null });
+    } catch (error) {
+      console.error("Failed to update scheduler:", error);
+      throw error;
+    }
+  };
+
+  const getSettings = async (): Promise<AppSettings> => {
+    try {
+      return await invoke<AppSettings>("get_settings");
+    } catch (error) {
+      console.error("Failed to fetch settings:", error);
+      throw error;
+    }
+  };
+
+  const updateSettings = async (config: AppSettings): Promise<void> => {
     try {
-      return await invoke<RefreshResult>("refresh_russell_2000_data");
+      await invoke("update_settings", { config });
     } catch (error) {
-      console.error("Failed to refresh Russell 2000 data:", error);
+      console.error("Failed to update settings:", error);
       throw error;
     }
   };
 
   return {
     getSectors,
+    getUniverses,
+    updateUniverse,
+    createWatchlist,
+    renameWatchlist,
+    deleteWatchlist,
+    addWatchlistSymbol,
+    removeWatchlistSymbol,
     getStocksBySector,
     getSectorPerformance,
+    getGroupPerformance,
+    getMarketBreadth,
+    getSectorRotation,
     refreshMarketData,
     refreshSectorData,
     detectOutliers,
+    replayOutliers,
     getSectorOutliers,
-    refreshRussell2000Data,
+    getOutlierEpisodes,
+    getOutlierBacktest,
+    setMarketDataProvider,
+    backfillPriceHistory,
+    getStockHistory,
+    getStockDetections,
+    getStockZScores,
+    getStockMemberships,
+    cancelRefresh,
+    pauseRefresh,
+    resumeRefresh,
+    listJobs,
+    getSchedulerStatus,
+    setScheduler,
+    getSettings,
+    updateSettings,
   };
 }
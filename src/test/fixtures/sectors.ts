@@ -1,4 +1,40 @@
-import type { SectorSummary, SectorOutliers } from "../../types/database";
+import type { PeriodReturns, SectorSummary, SectorOutliers, Universe, ZScores } from "../../types/database";
+
+// Scores the fixtures don't exercise, estimated the default way
+const otherZ: Omit<ZScores, "pe_z" | "pb_z" | "price_z" | "volume_z"> = {
+  dividend_yield_z: null,
+  beta_z: null,
+  eps_z: null,
+  range_position_z: null,
+  methods: {
+    price: "MeanStd",
+    pe: "MeanStd",
+    pb: "MeanStd",
+    volume: "MeanStd",
+    dividend_yield: "MeanStd",
+    beta: "MeanStd",
+    eps: "MeanStd",
+    range_position: "MeanStd",
+    winsorized: false,
+  },
+};
+
+const noReturns: PeriodReturns = {
+  one_day: null,
+  one_week: null,
+  one_month: null,
+  three_months: null,
+  year_to_date: null,
+};
+
+const noAggregates = {
+  cap_weighted_change_percent: null,
+  change_quartiles: null,
+  cap_weighted_pe_ratio: null,
+  pe_quartiles: null,
+  benchmark: null,
+  benchmark_divergence: null,
+};
 
 export const mockSector: SectorSummary = {
   sector_id: 1,
@@ -9,6 +45,19 @@ export const mockSector: SectorSummary = {
   total_market_cap: 12_500_000_000_000, // $12.5T
   stock_count: 65,
   avg_beta: 1.2,
+  returns: {
+    one_day: 2.1,
+    one_week: 3.4,
+    one_month: -1.2,
+    three_months: 8.7,
+    year_to_date: 14.3,
+  },
+  cap_weighted_change_percent: 2.6,
+  change_quartiles: { q1: 0.9, median: 1.8, q3: 3.0 },
+  cap_weighted_pe_ratio: 31.2,
+  pe_quartiles: { q1: 21.0, median: 27.4, q3: 38.5 },
+  benchmark: { symbol: "XLK", price: 231.4, change_percent: 2.4, as_of: "2025-06-10 15:00:00" },
+  benchmark_divergence: 0.2,
 };
 
 export const mockSectorNoData: SectorSummary = {
@@ -20,6 +69,8 @@ export const mockSectorNoData: SectorSummary = {
   total_market_cap: null,
   stock_count: 0,
   avg_beta: null,
+  returns: noReturns,
+  ...noAggregates,
 };
 
 export const mockSectorNegativeChange: SectorSummary = {
@@ -31,6 +82,8 @@ export const mockSectorNegativeChange: SectorSummary = {
   total_market_cap: 500_000_000_000, // $500.0B
   stock_count: 28,
   avg_beta: 0.6,
+  returns: noReturns,
+  ...noAggregates,
 };
 
 export const mockSectorMillions: SectorSummary = {
@@ -42,59 +95,88 @@ export const mockSectorMillions: SectorSummary = {
   total_market_cap: 750_000_000, // $750M
   stock_count: 30,
   avg_beta: 0.9,
+  returns: noReturns,
+  ...noAggregates,
 };
 
 export const mockOutliers: SectorOutliers = {
   sector_id: 1,
   sector_name: "Information Technology",
   sector_symbol: "XLK",
+  level: "sector",
+  group_id: 1,
+  group_name: "Information Technology",
   outlier_count: 2,
   outliers: [
     {
       stock_id: 1,
       symbol: "AAPL",
       name: "Apple Inc.",
-      z_scores: { pe_z: 2.5, pb_z: 1.8, price_z: 2.1, volume_z: 1.2 },
+      z_scores: { pe_z: 2.5, pb_z: 1.8, price_z: 2.1, volume_z: 1.2, ...otherZ },
+      week52_position: null,
+      excess_return_1d_percent: null,
       composite_score: 2.1,
       outlier_type: "GrowthPremium",
       significance_level: "Strong",
+      status: "Persistent",
+      streak_days: 6,
+      first_seen: "2025-06-03",
     },
     {
       stock_id: 2,
       symbol: "MSFT",
       name: "Microsoft Corporation",
-      z_scores: { pe_z: -2.1, pb_z: -1.8, price_z: -1.5, volume_z: null },
+      z_scores: { pe_z: -2.1, pb_z: -1.8, price_z: -1.5, volume_z: null, ...otherZ },
+      week52_position: null,
+      excess_return_1d_percent: null,
       composite_score: 1.8,
       outlier_type: "Undervalued",
       significance_level: "Moderate",
+      status: "New",
+      streak_days: 1,
+      first_seen: "2025-06-10",
     },
   ],
+  resolved: [],
 };
 
 export const mockOutliersSingle: SectorOutliers = {
   sector_id: 3,
   sector_name: "Utilities",
   sector_symbol: "XLU",
+  level: "sector",
+  group_id: 3,
+  group_name: "Utilities",
   outlier_count: 1,
   outliers: [
     {
       stock_id: 10,
       symbol: "NEE",
       name: "NextEra Energy",
-      z_scores: { pe_z: 3.1, pb_z: 2.4, price_z: 1.8, volume_z: null },
+      z_scores: { pe_z: 3.1, pb_z: 2.4, price_z: 1.8, volume_z: null, ...otherZ },
+      week52_position: null,
+      excess_return_1d_percent: null,
       composite_score: 2.5,
       outlier_type: "Overvalued",
       significance_level: "Strong",
+      status: "Continuing",
+      streak_days: 2,
+      first_seen: "2025-06-09",
     },
   ],
+  resolved: [],
 };
 
 export const mockOutliersEmpty: SectorOutliers = {
   sector_id: 2,
   sector_name: "Energy",
   sector_symbol: "XLE",
+  level: "sector",
+  group_id: 2,
+  group_name: "Energy",
   outlier_count: 0,
   outliers: [],
+  resolved: [],
 };
 
 export const mockSectors: SectorSummary[] = [
@@ -110,5 +192,12 @@ export const mockSectors: SectorSummary[] = [
     total_market_cap: 8_000_000_000_000,
     stock_count: 72,
     avg_beta: 1.1,
+    returns: noReturns,
+    ...noAggregates,
   },
 ];
+
+export const mockUniverses: Universe[] = [
+  { id: "sp500", display_name: "S&P 500", discovery_source: "sp500_wikipedia", default_threshold: 1.5, cache_policy: "ttl" },
+  { id: "russell2000", display_name: "Russell 2000", discovery_source: "ishares_iwm", default_threshold: 2.0, cache_policy: "never" },
+];
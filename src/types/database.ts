@@ -1,4 +1,17 @@
-export type UniverseType = "sp500" | "russell2000";
+/** Id of a registered universe, e.g. "sp500" or "russell2000". */
+export type UniverseType = string;
+
+export type CachePolicy = "ttl" | "never";
+
+export interface Universe {
+  id: UniverseType;
+  display_name: string;
+  discovery_source: string | null;
+  default_threshold: number;
+  cache_policy: CachePolicy;
+}
+
+export type MarketDataProvider = "yahoo" | "alpha_vantage";
 
 export interface Sector {
   id: number;
@@ -22,6 +35,99 @@ export interface SectorSummary {
   total_market_cap: number | null;
   stock_count: number;
   avg_beta: number | null;
+  returns: PeriodReturns;
+  cap_weighted_change_percent: number | null;
+  change_quartiles: Quartiles | null;
+  cap_weighted_pe_ratio: number | null;
+  pe_quartiles: Quartiles | null;
+  benchmark: SectorBenchmark | null;
+  benchmark_divergence: number | null;
+}
+
+export type GicsLevel = "sector" | "industry_group" | "industry" | "sub_industry";
+
+export interface GroupSummary {
+  level: GicsLevel;
+  group_id: number;
+  name: string;
+  sector_id: number;
+  avg_change_percent: number;
+  avg_pe_ratio: number | null;
+  total_market_cap: number | null;
+  stock_count: number;
+  avg_beta: number | null;
+  returns: PeriodReturns;
+  cap_weighted_change_percent: number | null;
+  change_quartiles: Quartiles | null;
+  cap_weighted_pe_ratio: number | null;
+  pe_quartiles: Quartiles | null;
+}
+
+export interface SectorBenchmark {
+  symbol: string;
+  price: number;
+  change_percent: number;
+  as_of: string;
+}
+
+export interface Quartiles {
+  q1: number;
+  median: number;
+  q3: number;
+}
+
+export type ReturnHorizon = "OneDay" | "OneWeek" | "OneMonth" | "ThreeMonths" | "YearToDate";
+
+export interface PeriodReturns {
+  one_day: number | null;
+  one_week: number | null;
+  one_month: number | null;
+  three_months: number | null;
+  year_to_date: number | null;
+}
+
+export interface BreadthMetrics {
+  stock_count: number;
+  advancers: number;
+  decliners: number;
+  unchanged: number;
+  percent_above_50d: number | null;
+  percent_above_200d: number | null;
+  new_highs: number;
+  new_lows: number;
+  thrust_ema: number | null;
+  breadth_thrust: boolean;
+}
+
+export interface SectorBreadth {
+  sector_id: number;
+  name: string;
+  symbol: string;
+  breadth: BreadthMetrics;
+}
+
+export interface MarketBreadth {
+  universe: BreadthMetrics;
+  sectors: SectorBreadth[];
+}
+
+export type RotationQuadrant = "Leading" | "Weakening" | "Lagging" | "Improving";
+
+export interface RotationPoint {
+  date: string;
+  rs_ratio: number;
+  rs_momentum: number;
+  quadrant: RotationQuadrant;
+}
+
+export interface SectorRotation {
+  sector_id: number;
+  name: string;
+  symbol: string;
+  rank: number | null;
+  quadrant: RotationQuadrant | null;
+  relative_strength: PeriodReturns;
+  trajectory: RotationPoint[];
 }
 
 export interface ZScores {
@@ -29,6 +135,35 @@ export interface ZScores {
   pb_z: number | null;
   price_z: number;
   volume_z: number | null;
+  dividend_yield_z: number | null;
+  beta_z: number | null;
+  eps_z: number | null;
+  range_position_z: number | null;
+  methods: ZScoreMethods;
+}
+
+export type ZScoreMethod = "MeanStd" | "MedianMad";
+
+export interface ZScoreMethods {
+  price: ZScoreMethod;
+  pe: ZScoreMethod;
+  pb: ZScoreMethod;
+  volume: ZScoreMethod;
+  dividend_yield: ZScoreMethod;
+  beta: ZScoreMethod;
+  eps: ZScoreMethod;
+  range_position: ZScoreMethod;
+  winsorized: boolean;
+}
+
+export interface WinsorLimits {
+  lower_percentile: number;
+  upper_percentile: number;
+}
+
+export interface StatsOptions {
+  method: ZScoreMethod;
+  winsorize: WinsorLimits | null;
 }
 
 export type OutlierType =
@@ -37,6 +172,9 @@ export type OutlierType =
   | "Momentum"
   | "ValueTrap"
   | "GrowthPremium"
+  | "YieldTrap"
+  | "NearYearLow"
+  | "NearYearHigh"
   | "Mixed";
 
 export type SignificanceLevel = "Moderate" | "Strong" | "Extreme";
@@ -46,15 +184,60 @@ export interface OutlierStock {
   symbol: string;
   name: string;
   z_scores: ZScores;
+  week52_position: number | null;
+  excess_return_1d_percent: number | null;
   composite_score: number;
   outlier_type: OutlierType;
   significance_level: SignificanceLevel;
+  status: EpisodeStatus;
+  streak_days: number;
+  first_seen: string | null;
+}
+
+export type EpisodeStatus = "New" | "Continuing" | "Persistent" | "Resolved";
+
+export interface OutlierEpisode {
+  stock_id: number;
+  symbol: string;
+  sector_id: number;
+  first_seen: string;
+  last_seen: string;
+  consecutive_days: number;
+  peak_composite_score: number;
+  outlier_type: OutlierType;
+  type_changes: number;
+}
+
+export interface ForwardStats {
+  sessions: number;
+  count: number;
+  mean_return: number | null;
+  mean_excess_return: number | null;
+  hit_rate: number | null;
+  t_stat: number | null;
+}
+
+export interface BacktestGroup {
+  outlier_type: OutlierType;
+  /** null for the row covering every significance level. */
+  significance_level: SignificanceLevel | null;
+  horizons: ForwardStats[];
+}
+
+export interface BacktestReport {
+  universe: UniverseType;
+  start_date: string;
+  end_date: string;
+  signal_count: number;
+  groups: BacktestGroup[];
 }
 
 export interface DiscoveryResult {
   stocks_discovered: number;
   stocks_updated: number;
   stocks_unchanged: number;
+  members_added: string[];
+  members_removed: string[];
   errors: string[];
 }
 
@@ -63,10 +246,152 @@ export interface RefreshResult {
   discovery: DiscoveryResult | null;
 }
 
+export interface BackfillResult {
+  stocks_processed: number;
+  bars_written: number;
+  errors: string[];
+}
+
 export interface SectorOutliers {
   sector_id: number;
   sector_name: string;
   sector_symbol: string;
+  level: GicsLevel;
+  group_id: number;
+  group_name: string;
   outlier_count: number;
   outliers: OutlierStock[];
+  resolved: OutlierStock[];
+}
+
+export type PriceSource = "Daily" | "Snapshot";
+
+export interface PricePoint {
+  timestamp: string;
+  open: number | null;
+  high: number | null;
+  low: number | null;
+  close: number;
+  adj_close: number | null;
+  volume: number | null;
+  source: PriceSource;
+}
+
+export interface StockZScores {
+  stock_id: number;
+  sector_id: number;
+  level: GicsLevel;
+  group_id: number;
+  universe: string;
+  peer_count: number;
+  z_scores: ZScores;
+  week52_position: number | null;
+  excess_return_1d_percent: number | null;
+  composite_score: number;
+  outlier_type: OutlierType;
+  significance_level: SignificanceLevel;
+}
+
+export interface DetectionRecord {
+  run_id: number | null;
+  detection_date: string;
+  detection_timestamp: string;
+  sector_id: number;
+  gics_level: GicsLevel;
+  group_id: number;
+  universe_type: string;
+  pe_z_score: number | null;
+  pb_z_score: number | null;
+  price_z_score: number;
+  volume_z_score: number | null;
+  dividend_yield_z_score: number | null;
+  beta_z_score: number | null;
+  eps_z_score: number | null;
+  range_position_z_score: number | null;
+  week52_position: number | null;
+  composite_score: number;
+  outlier_type: OutlierType;
+  significance_level: SignificanceLevel;
+  threshold_used: number;
+}
+
+export interface UniverseMembership {
+  universe_type: string;
+  date_added: string;
+  date_removed: string | null;
+}
+
+export type JobStatus = "Running" | "Paused" | "Completed" | "Cancelled" | "Failed";
+
+export interface JobInfo {
+  id: number;
+  kind: string;
+  status: JobStatus;
+  phase: string;
+  current: number;
+  total: number;
+  started_at: string;
+  finished_at: string | null;
+  error: string | null;
+}
+
+export interface SchedulerStatus {
+  enabled: boolean;
+  interval_minutes: number;
+  market_open: boolean;
+  last_run_at: string | null;
+  next_run_at: string | null;
+}
+
+export interface CompositeWeights {
+  price: number;
+  pe: number;
+  pb: number;
+  volume: number;
+  dividend_yield: number;
+  beta: number;
+  eps: number;
+  range_position: number;
+}
+
+export interface AppSettings {
+  composite_weights: CompositeWeights;
+  cache_ttl_minutes: number;
+  scheduler_enabled: boolean;
+  scheduler_interval_minutes: number;
+  market_data_provider: string;
+  yahoo_requests_per_second: number;
+  alpha_vantage_requests_per_minute: number;
+  refresh_concurrency: number;
+}
+
+export type ScoringPreset = "Balanced" | "ValuationOnly" | "MomentumOnly";
+
+export type ScoreMetric =
+  | "Price"
+  | "PeRatio"
+  | "PbRatio"
+  | "Volume"
+  | "DividendYield"
+  | "Beta"
+  | "Eps"
+  | "RangePosition";
+
+export interface ScoringOptions {
+  preset?: ScoringPreset;
+  weights?: CompositeWeights;
+  metrics?: ScoreMetric[];
+  stats?: StatsOptions;
+  horizon?: ReturnHorizon;
+  level?: GicsLevel;
+}
+
+/** Scoring fields as the detection commands take them. */
+export interface ScoringArgs {
+  threshold: number | null;
+  preset: ScoringPreset | null;
+  weights: CompositeWeights | null;
+  metrics: ScoreMetric[] | null;
+  stats: StatsOptions | null;
+  horizon: ReturnHorizon | null;
 }